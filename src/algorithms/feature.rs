@@ -1,53 +1,813 @@
 //! Feature extraction algorithms
 //!
 //! This module provides algorithms for extracting features from point clouds,
-//! including normal estimation and keypoint detection.
+//! including normal estimation and keypoint detection. Organized clouds can
+//! use integral-image normal estimation, which runs in constant time per
+//! point regardless of the neighborhood size.
 
-use crate::core::{Point, PointCloud};
+use crate::core::{Point, PointCloud, PointXYZRGBNormal};
 use crate::error::{CloudError, Result};
+use crate::search::KdTree;
+use crate::utils::math;
 use rayon::prelude::*;
+use std::ops::Range;
+
+/// Neighborhood definition used for local surface estimation
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NeighborhoodSearch {
+    /// All points within the given radius
+    Radius(f32),
+
+    /// The k nearest neighbors (including the query point itself)
+    KNearest(usize),
+}
+
+/// Surface normal and curvature estimated for a single point
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NormalEstimate {
+    /// Unit normal vector, NaN if the neighborhood was too small
+    pub normal: [f32; 3],
+
+    /// Surface variation `λ0 / (λ0 + λ1 + λ2)`, NaN if the neighborhood was too small
+    pub curvature: f32,
+}
+
+impl NormalEstimate {
+    /// Estimate returned for points without enough neighbors
+    pub const INVALID: Self = Self {
+        normal: [f32::NAN; 3],
+        curvature: f32::NAN,
+    };
+
+    /// Check whether the estimate holds a finite normal
+    pub fn is_valid(&self) -> bool {
+        self.normal.iter().all(|v| v.is_finite())
+    }
+}
 
 /// Estimate normals for a point cloud using PCA
+///
+/// Neighbors are all points within `search_radius`. Normals are oriented
+/// towards the cloud's sensor origin; points with fewer than 3 neighbors
+/// receive a NaN normal.
 pub fn estimate_normals<P: Point>(
     cloud: &PointCloud<P>,
     search_radius: f32,
 ) -> Result<Vec<[f32; 3]>> {
+    Ok(
+        estimate_normals_with_curvature(cloud, NeighborhoodSearch::Radius(search_radius))?
+            .into_iter()
+            .map(|estimate| estimate.normal)
+            .collect(),
+    )
+}
+
+/// Estimate normals and curvature for a point cloud using PCA
+///
+/// For every point the covariance matrix of its neighborhood is decomposed;
+/// the eigenvector of the smallest eigenvalue is the normal and the ratio of
+/// that eigenvalue to the eigenvalue sum is the curvature. Normals are flipped
+/// to face `Metadata::sensor_origin`.
+pub fn estimate_normals_with_curvature<P: Point>(
+    cloud: &PointCloud<P>,
+    search: NeighborhoodSearch,
+) -> Result<Vec<NormalEstimate>> {
+    match search {
+        NeighborhoodSearch::Radius(radius) if radius.is_nan() || radius <= 0.0 => {
+            return Err(CloudError::invalid_parameter(
+                "Search radius must be positive",
+            ));
+        }
+        NeighborhoodSearch::KNearest(k) if k < 3 => {
+            return Err(CloudError::invalid_parameter(
+                "At least 3 neighbors are required to estimate normals",
+            ));
+        }
+        _ => {}
+    }
+
     if cloud.is_empty() {
         return Ok(Vec::new());
     }
 
-    let normals: Vec<[f32; 3]> = cloud
+    let tree = KdTree::build(cloud.points());
+    let positions: Vec<[f32; 3]> = cloud.iter().map(|p| p.position()).collect();
+    let viewpoint = cloud.metadata().sensor_origin;
+
+    let estimates = positions
         .par_iter()
-        .map(|query_point| {
-            // Find neighbors within radius
-            let neighbors: Vec<&P> = cloud
+        .map(|&query| {
+            if !query.iter().all(|v| v.is_finite()) {
+                return NormalEstimate::INVALID;
+            }
+
+            let neighbors: Vec<usize> = match search {
+                NeighborhoodSearch::Radius(radius) => tree
+                    .radius_search_indices(query, radius)
+                    .into_iter()
+                    .map(|(i, _)| i)
+                    .collect(),
+                NeighborhoodSearch::KNearest(k) => tree
+                    .k_nearest_indices(query, k)
+                    .into_iter()
+                    .map(|(i, _)| i)
+                    .collect(),
+            };
+
+            let neighborhood: Vec<[f32; 3]> = neighbors.iter().map(|&i| positions[i]).collect();
+            match compute_point_normal(&neighborhood) {
+                Some((normal, curvature)) => NormalEstimate {
+                    normal: orient_towards_viewpoint(normal, query, viewpoint),
+                    curvature,
+                },
+                None => NormalEstimate::INVALID,
+            }
+        })
+        .collect();
+
+    Ok(estimates)
+}
+
+/// Estimate normals and attach them to a copy of the cloud
+///
+/// Colors are carried over for point types that have them. The metadata
+/// (including organization) of the input cloud is preserved.
+pub fn compute_normal_cloud<P: Point>(
+    cloud: &PointCloud<P>,
+    search: NeighborhoodSearch,
+) -> Result<PointCloud<PointXYZRGBNormal>> {
+    let estimates = estimate_normals_with_curvature(cloud, search)?;
+
+    let points = cloud
+        .par_iter()
+        .zip(estimates.par_iter())
+        .map(|(point, estimate)| {
+            let [x, y, z] = point.position();
+            let [r, g, b] = point.color().unwrap_or([0, 0, 0]);
+            let [nx, ny, nz] = estimate.normal;
+            PointXYZRGBNormal::new(x, y, z, r, g, b, nx, ny, nz)
+        })
+        .collect();
+
+    Ok(PointCloud::from_points_and_metadata(
+        points,
+        cloud.metadata().clone(),
+    ))
+}
+
+/// Surface estimate computed from integral images
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IntegralNormalMethod {
+    /// Cross product of the averaged horizontal and vertical 3D gradients
+    ///
+    /// The fastest method; curvature is not estimated and is NaN.
+    Average3DGradient,
+
+    /// Smallest eigenvector of the covariance matrix of the window
+    ///
+    /// Slower but more robust to noise, and estimates curvature.
+    CovarianceMatrix,
+}
+
+/// Configuration for integral-image normal estimation
+#[derive(Clone, Debug, PartialEq)]
+pub struct IntegralNormalConfig {
+    /// How the normal is computed from the window around each point
+    pub method: IntegralNormalMethod,
+
+    /// Half size of the square window in pixels
+    ///
+    /// Windows shrink near depth discontinuities and missing data so that
+    /// they do not mix separate surfaces.
+    pub window_radius: usize,
+
+    /// Largest depth change between neighboring pixels, relative to the
+    /// depth, that is still considered the same surface
+    pub max_depth_change_factor: f32,
+}
+
+impl Default for IntegralNormalConfig {
+    fn default() -> Self {
+        Self {
+            method: IntegralNormalMethod::Average3DGradient,
+            window_radius: 5,
+            max_depth_change_factor: 0.02,
+        }
+    }
+}
+
+/// Estimate normals of an organized cloud using integral images
+///
+/// See [`estimate_normals_integral_with_config`].
+pub fn estimate_normals_integral<P: Point>(cloud: &PointCloud<P>) -> Result<Vec<NormalEstimate>> {
+    estimate_normals_integral_with_config(cloud, &IntegralNormalConfig::default())
+}
+
+/// Estimate normals of an organized cloud using integral images with custom options
+///
+/// The sums needed for every window are precomputed as integral images, so
+/// the cost per point does not depend on the window size. Depth is measured
+/// along z, as for clouds captured by a camera. Invalid (NaN) points and
+/// points next to missing data or a depth discontinuity receive NaN normals.
+/// Normals are flipped to face `Metadata::sensor_origin`.
+///
+/// # Example
+/// ```rust
+/// use ferrum_cloud::core::{Metadata, PointXYZ};
+/// use ferrum_cloud::prelude::*;
+///
+/// let points = (0..100)
+///     .map(|i| PointXYZ::new((i % 10) as f32 * 0.01, (i / 10) as f32 * 0.01, 1.0))
+///     .collect();
+/// let cloud = PointCloud::from_points_and_metadata(points, Metadata::new_organized(10, 10));
+///
+/// let config = IntegralNormalConfig {
+///     method: IntegralNormalMethod::CovarianceMatrix,
+///     window_radius: 2,
+///     ..Default::default()
+/// };
+/// let normals = estimate_normals_integral_with_config(&cloud, &config)?;
+/// assert!((normals[55].normal[2] + 1.0).abs() < 1e-5);
+/// # Ok::<(), CloudError>(())
+/// ```
+pub fn estimate_normals_integral_with_config<P: Point>(
+    cloud: &PointCloud<P>,
+    config: &IntegralNormalConfig,
+) -> Result<Vec<NormalEstimate>> {
+    let metadata = cloud.metadata();
+    if !metadata.is_organized
+        || cloud.image_size() != (metadata.width as usize, metadata.height as usize)
+    {
+        return Err(CloudError::invalid_parameter(
+            "Integral image normal estimation requires an organized point cloud",
+        ));
+    }
+    if config.window_radius == 0 {
+        return Err(CloudError::invalid_parameter(
+            "Window radius must be at least 1 pixel",
+        ));
+    }
+
+    let (width, height) = cloud.image_size();
+    let positions: Vec<Option<[f64; 3]>> = cloud
+        .iter()
+        .map(|p| {
+            let position = p.position();
+            position
                 .iter()
-                .filter(|&p| {
-                    let distance = query_point.distance_to(p);
-                    distance <= search_radius
-                })
-                .collect();
+                .all(|v| v.is_finite())
+                .then(|| position.map(f64::from))
+        })
+        .collect();
+    // Larger windows cover the whole grid anyway
+    let window_radius = config.window_radius.min(width.max(height));
+    let distances = discontinuity_distances(&positions, width, height, window_radius, config);
+
+    let integral = match config.method {
+        IntegralNormalMethod::CovarianceMatrix => {
+            IntegralImage::new(width, height, 10, |index, sums| {
+                if let Some([x, y, z]) = positions[index] {
+                    sums.copy_from_slice(&[1.0, x, y, z, x * x, x * y, x * z, y * y, y * z, z * z]);
+                }
+            })
+        }
+        IntegralNormalMethod::Average3DGradient => {
+            IntegralImage::new(width, height, 8, |index, sums| {
+                // Central differences, only where no discontinuity is adjacent
+                if distances[index] == 0 {
+                    return;
+                }
+                let (row, column) = (index / width, index % width);
+                if column > 0 && column + 1 < width {
+                    let (Some(left), Some(right)) = (positions[index - 1], positions[index + 1])
+                    else {
+                        return;
+                    };
+                    sums[0] = 1.0;
+                    sums[1..4].copy_from_slice(&[
+                        right[0] - left[0],
+                        right[1] - left[1],
+                        right[2] - left[2],
+                    ]);
+                }
+                if row > 0 && row + 1 < height {
+                    let (Some(up), Some(down)) =
+                        (positions[index - width], positions[index + width])
+                    else {
+                        return;
+                    };
+                    sums[4] = 1.0;
+                    sums[5..8].copy_from_slice(&[
+                        down[0] - up[0],
+                        down[1] - up[1],
+                        down[2] - up[2],
+                    ]);
+                }
+            })
+        }
+    };
 
-            if neighbors.len() < 3 {
-                return [0.0, 0.0, 1.0]; // Default normal
+    let viewpoint = cloud.metadata().sensor_origin;
+    let estimates = (0..width * height)
+        .into_par_iter()
+        .map(|index| {
+            let Some(position) = positions[index] else {
+                return NormalEstimate::INVALID;
+            };
+            let radius = window_radius.min(distances[index]);
+            if radius == 0 {
+                return NormalEstimate::INVALID;
             }
 
-            // Simple normal estimation - return default for now
-            [0.0, 0.0, 1.0]
+            let (row, column) = (index / width, index % width);
+            let rows = row.saturating_sub(radius)..(row + radius + 1).min(height);
+            let columns = column.saturating_sub(radius)..(column + radius + 1).min(width);
+            let mut sums = [0.0f64; 10];
+            integral.sum(rows, columns, &mut sums);
+
+            let estimate = match config.method {
+                IntegralNormalMethod::CovarianceMatrix => covariance_normal(&sums),
+                IntegralNormalMethod::Average3DGradient => gradient_normal(&sums),
+            };
+            match estimate {
+                Some((normal, curvature)) => NormalEstimate {
+                    normal: orient_towards_viewpoint(normal, position.map(|v| v as f32), viewpoint),
+                    curvature,
+                },
+                None => NormalEstimate::INVALID,
+            }
         })
         .collect();
 
-    Ok(normals)
+    Ok(estimates)
+}
+
+/// Summed-area table with several channels per pixel
+struct IntegralImage {
+    width: usize,
+    channels: usize,
+    /// `(height + 1) x (width + 1)` cells, the first row and column are zero
+    data: Vec<f64>,
+}
+
+impl IntegralImage {
+    /// Build the table from the per-pixel values written by `values`
+    fn new<F>(width: usize, height: usize, channels: usize, values: F) -> Self
+    where
+        F: Fn(usize, &mut [f64]),
+    {
+        let stride = (width + 1) * channels;
+        let mut data = vec![0.0f64; stride * (height + 1)];
+        let mut pixel = vec![0.0f64; channels];
+        let mut row_sums = vec![0.0f64; channels];
+
+        for row in 0..height {
+            row_sums.fill(0.0);
+            for column in 0..width {
+                pixel.fill(0.0);
+                values(row * width + column, &mut pixel);
+
+                let cell = (row + 1) * stride + (column + 1) * channels;
+                let above = row * stride + (column + 1) * channels;
+                for channel in 0..channels {
+                    row_sums[channel] += pixel[channel];
+                    data[cell + channel] = data[above + channel] + row_sums[channel];
+                }
+            }
+        }
+
+        Self {
+            width,
+            channels,
+            data,
+        }
+    }
+
+    /// Sum every channel over a rectangle of pixels
+    fn sum(&self, rows: Range<usize>, columns: Range<usize>, out: &mut [f64]) {
+        let stride = (self.width + 1) * self.channels;
+        let cell = |row: usize, column: usize| row * stride + column * self.channels;
+        let (top_left, top_right) = (
+            cell(rows.start, columns.start),
+            cell(rows.start, columns.end),
+        );
+        let (bottom_left, bottom_right) =
+            (cell(rows.end, columns.start), cell(rows.end, columns.end));
+        for (channel, value) in out.iter_mut().take(self.channels).enumerate() {
+            *value = self.data[bottom_right + channel]
+                - self.data[bottom_left + channel]
+                - self.data[top_right + channel]
+                + self.data[top_left + channel];
+        }
+    }
+}
+
+/// Chessboard distance of every pixel to missing data or a depth discontinuity
+///
+/// Invalid pixels and pixels whose depth differs too much from a 4-neighbor
+/// have distance 0.
+fn discontinuity_distances(
+    positions: &[Option<[f64; 3]>],
+    width: usize,
+    height: usize,
+    window_radius: usize,
+    config: &IntegralNormalConfig,
+) -> Vec<usize> {
+    let factor = config.max_depth_change_factor as f64;
+    let cap = window_radius + 1;
+    let jump = |a: Option<[f64; 3]>, b: Option<[f64; 3]>| match (a, b) {
+        (Some(a), Some(b)) => (a[2] - b[2]).abs() > factor * a[2].abs().min(b[2].abs()),
+        _ => true,
+    };
+
+    let mut distances: Vec<usize> = (0..width * height)
+        .map(|index| {
+            let (row, column) = (index / width, index % width);
+            let position = positions[index];
+            let edge = position.is_none()
+                || (column > 0 && jump(position, positions[index - 1]))
+                || (column + 1 < width && jump(position, positions[index + 1]))
+                || (row > 0 && jump(position, positions[index - width]))
+                || (row + 1 < height && jump(position, positions[index + width]));
+            if edge { 0 } else { cap }
+        })
+        .collect();
+
+    // Two passes of a chamfer transform with unit cost to all 8 neighbors
+    for row in 0..height {
+        for column in 0..width {
+            let index = row * width + column;
+            let mut best = distances[index];
+            if column > 0 {
+                best = best.min(distances[index - 1] + 1);
+            }
+            if row > 0 {
+                let above = index - width;
+                best = best.min(distances[above] + 1);
+                if column > 0 {
+                    best = best.min(distances[above - 1] + 1);
+                }
+                if column + 1 < width {
+                    best = best.min(distances[above + 1] + 1);
+                }
+            }
+            distances[index] = best;
+        }
+    }
+    for row in (0..height).rev() {
+        for column in (0..width).rev() {
+            let index = row * width + column;
+            let mut best = distances[index];
+            if column + 1 < width {
+                best = best.min(distances[index + 1] + 1);
+            }
+            if row + 1 < height {
+                let below = index + width;
+                best = best.min(distances[below] + 1);
+                if column > 0 {
+                    best = best.min(distances[below - 1] + 1);
+                }
+                if column + 1 < width {
+                    best = best.min(distances[below + 1] + 1);
+                }
+            }
+            distances[index] = best;
+        }
+    }
+
+    distances
+}
+
+/// Normal and curvature from summed counts, coordinates and coordinate products
+fn covariance_normal(sums: &[f64; 10]) -> Option<([f32; 3], f32)> {
+    let count = sums[0];
+    if count < 3.0 {
+        return None;
+    }
+    let mean = [sums[1] / count, sums[2] / count, sums[3] / count];
+    let product = |sum: f64, a: usize, b: usize| sum / count - mean[a] * mean[b];
+    let (xx, xy, xz) = (
+        product(sums[4], 0, 0),
+        product(sums[5], 0, 1),
+        product(sums[6], 0, 2),
+    );
+    let (yy, yz, zz) = (
+        product(sums[7], 1, 1),
+        product(sums[8], 1, 2),
+        product(sums[9], 2, 2),
+    );
+    let covariance = [[xx, xy, xz], [xy, yy, yz], [xz, yz, zz]];
+
+    let (values, vectors) = math::symmetric_eigen(covariance);
+    let sum = values[0] + values[1] + values[2];
+    if sum <= f64::EPSILON {
+        return None;
+    }
+
+    let normal = math::normalize(vectors[0].map(|v| v as f32));
+    let curvature = (values[0].max(0.0) / sum) as f32;
+    Some((normal, curvature))
+}
+
+/// Normal from summed horizontal and vertical gradients
+fn gradient_normal(sums: &[f64; 10]) -> Option<([f32; 3], f32)> {
+    if sums[0] < 1.0 || sums[4] < 1.0 {
+        return None;
+    }
+    let horizontal = [sums[1], sums[2], sums[3]].map(|v| (v / sums[0]) as f32);
+    let vertical = [sums[5], sums[6], sums[7]].map(|v| (v / sums[4]) as f32);
+    let normal = math::cross_product(vertical, horizontal);
+    if math::magnitude(normal) <= f32::EPSILON {
+        return None;
+    }
+    Some((math::normalize(normal), f32::NAN))
+}
+
+/// Compute the normal and curvature of a set of neighboring points
+///
+/// Returns `None` if fewer than 3 points are given or the points are
+/// degenerate (e.g. all identical).
+pub fn compute_point_normal(points: &[[f32; 3]]) -> Option<([f32; 3], f32)> {
+    if points.len() < 3 {
+        return None;
+    }
+
+    let count = points.len() as f64;
+    let mut mean = [0.0f64; 3];
+    for p in points {
+        for (m, &v) in mean.iter_mut().zip(p.iter()) {
+            *m += v as f64 / count;
+        }
+    }
+
+    let mut covariance = [[0.0f64; 3]; 3];
+    for p in points {
+        let d = [
+            p[0] as f64 - mean[0],
+            p[1] as f64 - mean[1],
+            p[2] as f64 - mean[2],
+        ];
+        for (row, &di) in covariance.iter_mut().zip(d.iter()) {
+            for (value, &dj) in row.iter_mut().zip(d.iter()) {
+                *value += di * dj / count;
+            }
+        }
+    }
+
+    let (values, vectors) = math::symmetric_eigen(covariance);
+    let sum = values[0] + values[1] + values[2];
+    if sum <= f64::EPSILON {
+        return None;
+    }
+
+    let normal = math::normalize([
+        vectors[0][0] as f32,
+        vectors[0][1] as f32,
+        vectors[0][2] as f32,
+    ]);
+    let curvature = (values[0].max(0.0) / sum) as f32;
+
+    Some((normal, curvature))
+}
+
+/// Flip a normal so that it points towards the viewpoint
+fn orient_towards_viewpoint(normal: [f32; 3], point: [f32; 3], viewpoint: [f32; 3]) -> [f32; 3] {
+    let to_viewpoint = [
+        viewpoint[0] - point[0],
+        viewpoint[1] - point[1],
+        viewpoint[2] - point[2],
+    ];
+    if math::dot_product(normal, to_viewpoint) < 0.0 {
+        [-normal[0], -normal[1], -normal[2]]
+    } else {
+        normal
+    }
 }
 
 /// Extension trait for adding feature extraction methods to PointCloud
 pub trait FeatureExt<P: Point> {
     /// Estimate surface normals
     fn estimate_normals(&self, search_radius: f32) -> Result<Vec<[f32; 3]>>;
+
+    /// Estimate surface normals and curvature
+    fn estimate_normals_with_curvature(
+        &self,
+        search: NeighborhoodSearch,
+    ) -> Result<Vec<NormalEstimate>>;
+
+    /// Estimate normals and return a cloud carrying them
+    fn compute_normal_cloud(
+        &self,
+        search: NeighborhoodSearch,
+    ) -> Result<PointCloud<PointXYZRGBNormal>>;
+
+    /// Estimate normals of an organized cloud using integral images with custom options
+    fn estimate_normals_integral_with_config(
+        &self,
+        config: &IntegralNormalConfig,
+    ) -> Result<Vec<NormalEstimate>>;
 }
 
 impl<P: Point> FeatureExt<P> for PointCloud<P> {
     fn estimate_normals(&self, search_radius: f32) -> Result<Vec<[f32; 3]>> {
         estimate_normals(self, search_radius)
     }
+
+    fn estimate_normals_with_curvature(
+        &self,
+        search: NeighborhoodSearch,
+    ) -> Result<Vec<NormalEstimate>> {
+        estimate_normals_with_curvature(self, search)
+    }
+
+    fn compute_normal_cloud(
+        &self,
+        search: NeighborhoodSearch,
+    ) -> Result<PointCloud<PointXYZRGBNormal>> {
+        compute_normal_cloud(self, search)
+    }
+
+    fn estimate_normals_integral_with_config(
+        &self,
+        config: &IntegralNormalConfig,
+    ) -> Result<Vec<NormalEstimate>> {
+        estimate_normals_integral_with_config(self, config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{Metadata, PointXYZ, PointXYZRGB};
+
+    fn plane_grid() -> Vec<PointXYZ> {
+        let mut points = Vec::new();
+        for i in 0..10 {
+            for j in 0..10 {
+                points.push(PointXYZ::new(i as f32 * 0.1, j as f32 * 0.1, 1.0));
+            }
+        }
+        points
+    }
+
+    #[test]
+    fn test_plane_normals() {
+        let cloud = PointCloud::from_points(plane_grid());
+
+        let estimates = cloud
+            .estimate_normals_with_curvature(NeighborhoodSearch::KNearest(8))
+            .unwrap();
+        assert_eq!(estimates.len(), cloud.len());
+
+        for estimate in estimates {
+            // Sensor origin is below the plane, so normals must face -z
+            assert!((estimate.normal[2] + 1.0).abs() < 1e-5);
+            assert!(estimate.curvature.abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_viewpoint_orientation() {
+        let metadata = Metadata::new_unorganized(100).with_sensor_origin([0.5, 0.5, 5.0]);
+        let cloud = PointCloud::from_points_and_metadata(plane_grid(), metadata);
+
+        let normals = cloud.estimate_normals(0.25).unwrap();
+        for normal in normals {
+            assert!((normal[2] - 1.0).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_isolated_point_is_invalid() {
+        let mut points = plane_grid();
+        points.push(PointXYZ::new(50.0, 50.0, 50.0));
+        let cloud = PointCloud::from_points(points);
+
+        let estimates = cloud
+            .estimate_normals_with_curvature(NeighborhoodSearch::Radius(0.25))
+            .unwrap();
+        assert!(estimates[0].is_valid());
+        assert!(!estimates[100].is_valid());
+        assert!(estimates[100].curvature.is_nan());
+    }
+
+    #[test]
+    fn test_compute_normal_cloud_keeps_color() {
+        let points = plane_grid()
+            .into_iter()
+            .map(|p| PointXYZRGB::new(p.x, p.y, p.z, 10, 20, 30))
+            .collect();
+        let cloud = PointCloud::from_points(points);
+
+        let with_normals = cloud
+            .compute_normal_cloud(NeighborhoodSearch::KNearest(6))
+            .unwrap();
+        assert_eq!(with_normals.len(), cloud.len());
+
+        let point = with_normals.get(0).unwrap();
+        assert_eq!(point.rgb(), 0x0A141E);
+        assert!((point.normal()[2].abs() - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_invalid_search_parameters() {
+        let cloud = PointCloud::from_points(plane_grid());
+        assert!(cloud.estimate_normals(0.0).is_err());
+        assert!(matches!(
+            cloud.estimate_normals(f32::NAN),
+            Err(CloudError::InvalidParameter(_))
+        ));
+        assert!(
+            cloud
+                .estimate_normals_with_curvature(NeighborhoodSearch::KNearest(2))
+                .is_err()
+        );
+    }
+
+    /// A 20x20 organized grid on the plane z = 2 + 0.5x, with a hole and a step
+    fn organized_grid() -> PointCloud<PointXYZ> {
+        let mut points = Vec::new();
+        for row in 0..20 {
+            for column in 0..20 {
+                let (x, y) = (column as f32 * 0.01, row as f32 * 0.01);
+                let z = if column >= 15 { 3.0 } else { 2.0 + 0.5 * x };
+                points.push(PointXYZ::new(x, y, z));
+            }
+        }
+        points[5 * 20 + 5] = PointXYZ::new(f32::NAN, f32::NAN, f32::NAN);
+        PointCloud::from_points_and_metadata(points, Metadata::new_organized(20, 20))
+    }
+
+    #[test]
+    fn test_integral_normals() {
+        let cloud = organized_grid();
+        let expected = math::normalize([0.5, 0.0, -1.0]);
+
+        for method in [
+            IntegralNormalMethod::Average3DGradient,
+            IntegralNormalMethod::CovarianceMatrix,
+        ] {
+            let config = IntegralNormalConfig {
+                method,
+                window_radius: 3,
+                ..Default::default()
+            };
+            let estimates = cloud
+                .estimate_normals_integral_with_config(&config)
+                .unwrap();
+            assert_eq!(estimates.len(), 400);
+
+            // Far from the hole and the step, normals follow the plane
+            let normal = estimates[14 * 20 + 8].normal;
+            for (n, e) in normal.iter().zip(expected.iter()) {
+                assert!((n - e).abs() < 1e-4, "{:?}: {:?}", method, normal);
+            }
+            assert!(estimates[0].is_valid());
+
+            // The hole, its border and both sides of the step are invalid
+            assert!(!estimates[5 * 20 + 5].is_valid());
+            assert!(!estimates[5 * 20 + 6].is_valid());
+            assert!(!estimates[10 * 20 + 14].is_valid());
+            assert!(!estimates[10 * 20 + 15].is_valid());
+            assert!(estimates[10 * 20 + 17].normal[2] < -0.99);
+        }
+    }
+
+    #[test]
+    fn test_integral_normals_require_organized_cloud() {
+        let cloud = PointCloud::from_points(plane_grid());
+        assert!(estimate_normals_integral(&cloud).is_err());
+
+        let config = IntegralNormalConfig {
+            window_radius: 0,
+            ..Default::default()
+        };
+        assert!(
+            organized_grid()
+                .estimate_normals_integral_with_config(&config)
+                .is_err()
+        );
+
+        let mut mismatched = organized_grid();
+        mismatched.metadata_mut().height += 1;
+        assert!(estimate_normals_integral(&mismatched).is_err());
+    }
+
+    #[test]
+    fn test_integral_normals_clamp_window_radius() {
+        let cloud = organized_grid();
+        let huge = IntegralNormalConfig {
+            window_radius: usize::MAX,
+            ..Default::default()
+        };
+        let whole = IntegralNormalConfig {
+            window_radius: 1000,
+            ..Default::default()
+        };
+        let estimates = cloud.estimate_normals_integral_with_config(&huge).unwrap();
+        let expected = cloud.estimate_normals_integral_with_config(&whole).unwrap();
+        for (estimate, expected) in estimates.iter().zip(&expected) {
+            assert_eq!(estimate.normal[2].to_bits(), expected.normal[2].to_bits());
+        }
+    }
 }
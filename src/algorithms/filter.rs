@@ -3,15 +3,17 @@
 //! This module provides various filtering algorithms including voxel downsampling,
 //! outlier removal, and statistical filtering.
 
-use crate::core::{Point, PointCloud};
-use crate::error::{CloudError, Result};
+use crate::core::{Point, PointCloud, PointMut};
+use crate::error::Result;
 use rayon::prelude::*;
 use std::collections::HashMap;
 
 /// Voxel downsampling filter
 ///
-/// Reduces point cloud density by averaging points within voxel grids.
-pub fn voxel_downsample<P: Point>(cloud: PointCloud<P>, voxel_size: f32) -> PointCloud<P> {
+/// Reduces point cloud density by averaging points within voxel grids. Each
+/// output point keeps the attributes of the first point in its voxel and is
+/// moved to the centroid of all points in that voxel.
+pub fn voxel_downsample<P: PointMut>(cloud: PointCloud<P>, voxel_size: f32) -> PointCloud<P> {
     if voxel_size <= 0.0 {
         return cloud;
     }
@@ -19,27 +21,20 @@ pub fn voxel_downsample<P: Point>(cloud: PointCloud<P>, voxel_size: f32) -> Poin
     let mut voxel_map: HashMap<(i32, i32, i32), Vec<P>> = HashMap::new();
 
     // Group points by voxel
-    for point in cloud.into_iter() {
+    for point in cloud {
         let pos = point.position();
         let voxel_key = (
             (pos[0] / voxel_size).floor() as i32,
             (pos[1] / voxel_size).floor() as i32,
             (pos[2] / voxel_size).floor() as i32,
         );
-        voxel_map
-            .entry(voxel_key)
-            .or_insert_with(Vec::new)
-            .push(point);
+        voxel_map.entry(voxel_key).or_default().push(point);
     }
 
     // Average points in each voxel
     let downsampled_points: Vec<P> = voxel_map
         .into_par_iter()
-        .map(|(_, points)| {
-            if points.is_empty() {
-                return None;
-            }
-
+        .filter_map(|(_, points)| {
             // Calculate average position
             let sum = points.iter().fold([0.0; 3], |acc, p| {
                 let pos = p.position();
@@ -49,15 +44,11 @@ pub fn voxel_downsample<P: Point>(cloud: PointCloud<P>, voxel_size: f32) -> Poin
             let count = points.len() as f32;
             let avg_pos = [sum[0] / count, sum[1] / count, sum[2] / count];
 
-            // Return the first point with averaged position
-            // This is a simplified approach - in practice, you might want to
-            // average other properties as well
-            let mut result = points.into_iter().next().unwrap();
-            // Note: This would require Point trait to have a set_position method
-            // For now, we'll just return the first point
+            // Keep the first point's attributes, moved to the voxel centroid
+            let mut result = points.into_iter().next()?;
+            result.set_position(avg_pos);
             Some(result)
         })
-        .filter_map(|p| p)
         .collect();
 
     PointCloud::from_points(downsampled_points)
@@ -111,7 +102,7 @@ pub fn remove_statistical_outliers<P: Point>(
     // Filter points based on threshold
     let filtered_points: Vec<P> = cloud
         .into_iter()
-        .zip(mean_distances.into_iter())
+        .zip(mean_distances)
         .filter_map(|(point, mean_dist)| {
             if mean_dist <= threshold {
                 Some(point)
@@ -186,7 +177,9 @@ pub enum Axis {
 /// Extension trait for PointCloud to add filtering methods
 pub trait FilterExt<P: Point> {
     /// Apply voxel downsampling
-    fn voxel_downsample(self, voxel_size: f32) -> PointCloud<P>;
+    fn voxel_downsample(self, voxel_size: f32) -> PointCloud<P>
+    where
+        P: PointMut;
 
     /// Remove statistical outliers
     fn remove_outliers(self, k_neighbors: usize, std_dev_threshold: f32) -> Result<PointCloud<P>>;
@@ -199,7 +192,10 @@ pub trait FilterExt<P: Point> {
 }
 
 impl<P: Point> FilterExt<P> for PointCloud<P> {
-    fn voxel_downsample(self, voxel_size: f32) -> PointCloud<P> {
+    fn voxel_downsample(self, voxel_size: f32) -> PointCloud<P>
+    where
+        P: PointMut,
+    {
         voxel_downsample(self, voxel_size)
     }
 
@@ -234,6 +230,23 @@ mod tests {
         assert!(downsampled.len() <= 2); // Should reduce to at most 2 points
     }
 
+    #[test]
+    fn test_voxel_downsample_centroid() {
+        let points = vec![
+            PointXYZ::new(0.02, 0.02, 0.02),
+            PointXYZ::new(0.04, 0.06, 0.08),
+        ];
+        let cloud = PointCloud::from_points(points);
+
+        let downsampled = cloud.voxel_downsample(0.1);
+        assert_eq!(downsampled.len(), 1);
+
+        let pos = downsampled.get(0).unwrap().position();
+        assert!((pos[0] - 0.03).abs() < 1e-6);
+        assert!((pos[1] - 0.04).abs() < 1e-6);
+        assert!((pos[2] - 0.05).abs() < 1e-6);
+    }
+
     #[test]
     fn test_pass_through_filter() {
         let points = vec![
@@ -6,10 +6,12 @@
 pub mod feature;
 pub mod filter;
 pub mod registration;
+pub mod sample_consensus;
 pub mod segmentation;
 
 // Re-export commonly used algorithms
 pub use feature::*;
 pub use filter::*;
 pub use registration::*;
+pub use sample_consensus::*;
 pub use segmentation::*;
@@ -3,8 +3,11 @@
 //! This module provides algorithms for aligning point clouds,
 //! including ICP (Iterative Closest Point) and other registration methods.
 
-use crate::core::{Point, PointCloud};
+use crate::core::{Point, PointCloud, PointMut};
 use crate::error::{CloudError, Result};
+use crate::search::KdTree;
+use crate::utils::math;
+use rayon::prelude::*;
 
 /// Transformation matrix (4x4 homogeneous transformation)
 pub type Transform = [[f32; 4]; 4];
@@ -17,34 +20,654 @@ pub const IDENTITY_TRANSFORM: Transform = [
     [0.0, 0.0, 0.0, 1.0],
 ];
 
+/// Result of an ICP registration
+#[derive(Clone, Debug, PartialEq)]
+pub struct IcpResult {
+    /// Transformation that maps the source cloud onto the target cloud
+    pub transform: Transform,
+
+    /// Fraction of source points that found a correspondence in the target
+    pub fitness: f32,
+
+    /// Root mean square distance of the final correspondences
+    pub inlier_rmse: f32,
+
+    /// Number of iterations performed
+    pub iterations: usize,
+
+    /// Whether the error change dropped below the tolerance
+    pub converged: bool,
+}
+
+/// ICP variant used to estimate the incremental transform at each iteration
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum IcpMethod {
+    /// Minimize point-to-point distances (closed-form solution)
+    #[default]
+    PointToPoint,
+
+    /// Minimize distances along the target normals
+    PointToPlane,
+
+    /// Generalized ICP: plane-to-plane distances weighted by local covariances
+    Generalized,
+}
+
+/// Configuration for ICP registration
+#[derive(Clone, Debug)]
+pub struct IcpConfig {
+    /// ICP variant to run
+    pub method: IcpMethod,
+
+    /// Maximum number of iterations
+    pub max_iterations: usize,
+
+    /// Stop once the RMSE changes by less than this amount between iterations
+    pub tolerance: f32,
+
+    /// Correspondences farther apart than this distance are rejected
+    pub max_correspondence_distance: f32,
+
+    /// Fraction of the closest correspondences kept each iteration (trimmed ICP)
+    pub inlier_ratio: f32,
+
+    /// Transformation applied to the source before the first iteration
+    pub initial_guess: Transform,
+
+    /// Number of neighbors used to estimate local covariances for GICP
+    pub covariance_neighbors: usize,
+}
+
+impl Default for IcpConfig {
+    fn default() -> Self {
+        Self {
+            method: IcpMethod::PointToPoint,
+            max_iterations: 50,
+            tolerance: 1e-6,
+            max_correspondence_distance: f32::INFINITY,
+            inlier_ratio: 1.0,
+            initial_guess: IDENTITY_TRANSFORM,
+            covariance_neighbors: 20,
+        }
+    }
+}
+
+/// Point correspondence between a transformed source point and the target cloud
+#[derive(Clone, Copy, Debug)]
+struct Correspondence {
+    source_index: usize,
+    source: [f32; 3],
+    target_index: usize,
+    distance_squared: f32,
+}
+
 /// ICP (Iterative Closest Point) registration
 ///
-/// This is a placeholder implementation. A full ICP would require:
-/// - Nearest neighbor search (KD-tree)
-/// - Correspondence estimation
-/// - Transformation estimation (SVD)
-/// - Iterative refinement
+/// Point-to-point ICP: every iteration pairs each transformed source point
+/// with its nearest target point (found through a KD-tree) and solves for the
+/// rigid transform minimizing the squared distances in closed form. Iteration
+/// stops after `max_iterations` or once the RMSE changes by less than
+/// `tolerance` between iterations.
 pub fn icp_registration<P: Point>(
-    _source: &PointCloud<P>,
-    _target: &PointCloud<P>,
-    _max_iterations: usize,
-    _tolerance: f32,
+    source: &PointCloud<P>,
+    target: &PointCloud<P>,
+    max_iterations: usize,
+    tolerance: f32,
+) -> Result<IcpResult> {
+    let config = IcpConfig {
+        max_iterations,
+        tolerance,
+        ..IcpConfig::default()
+    };
+    icp_registration_with_config(source, target, None, &config)
+}
+
+/// ICP registration with a selectable variant and correspondence rejection
+///
+/// `target_normals` must hold one normal per target point when running
+/// [`IcpMethod::PointToPlane`]; they can come from [`PointCloud::normals`] or
+/// from `FeatureExt::estimate_normals`. GICP estimates the local covariances
+/// of both clouds from their `covariance_neighbors` nearest neighbors.
+pub fn icp_registration_with_config<P: Point>(
+    source: &PointCloud<P>,
+    target: &PointCloud<P>,
+    target_normals: Option<&[[f32; 3]]>,
+    config: &IcpConfig,
+) -> Result<IcpResult> {
+    if source.is_empty() || target.is_empty() {
+        return Err(CloudError::invalid_parameter(
+            "ICP requires non-empty source and target clouds",
+        ));
+    }
+    if config.max_correspondence_distance <= 0.0 {
+        return Err(CloudError::invalid_parameter(
+            "Maximum correspondence distance must be positive",
+        ));
+    }
+    if !(config.inlier_ratio > 0.0 && config.inlier_ratio <= 1.0) {
+        return Err(CloudError::invalid_parameter(
+            "Inlier ratio must be in (0, 1]",
+        ));
+    }
+
+    let target_normals = match (config.method, target_normals) {
+        (IcpMethod::PointToPlane, None) => {
+            return Err(CloudError::invalid_parameter(
+                "Point-to-plane ICP requires target normals",
+            ));
+        }
+        (_, Some(normals)) if normals.len() != target.len() => {
+            return Err(CloudError::invalid_parameter(
+                "Target normals must match the number of target points",
+            ));
+        }
+        (_, normals) => normals,
+    };
+
+    let tree = KdTree::build(target.points());
+    let source_positions: Vec<[f32; 3]> = source.iter().map(|p| p.position()).collect();
+    let target_positions: Vec<[f32; 3]> = target.iter().map(|p| p.position()).collect();
+
+    let covariances = if config.method == IcpMethod::Generalized {
+        let source_tree = KdTree::build(source.points());
+        Some((
+            plane_covariances(&source_positions, &source_tree, config.covariance_neighbors),
+            plane_covariances(&target_positions, &tree, config.covariance_neighbors),
+        ))
+    } else {
+        None
+    };
+
+    let mut transform = config.initial_guess;
+    let mut previous_rmse = f32::INFINITY;
+    let mut iterations = 0;
+    let mut converged = false;
+
+    while iterations < config.max_iterations {
+        iterations += 1;
+
+        let mut correspondences = find_correspondences(
+            &source_positions,
+            &tree,
+            &transform,
+            config.max_correspondence_distance,
+        );
+        reject_worst_correspondences(&mut correspondences, config.inlier_ratio);
+        if correspondences.len() < 3 {
+            return Err(CloudError::algorithm_error(
+                "ICP needs at least 3 correspondences",
+            ));
+        }
+
+        let step = match config.method {
+            IcpMethod::PointToPoint => {
+                let (from, to): (Vec<[f32; 3]>, Vec<[f32; 3]>) = correspondences
+                    .iter()
+                    .map(|c| (c.source, target_positions[c.target_index]))
+                    .unzip();
+                estimate_rigid_transform(&from, &to)?
+            }
+            IcpMethod::PointToPlane => {
+                // Checked above: point-to-plane always has normals
+                let normals = target_normals.unwrap_or_default();
+                point_to_plane_step(&correspondences, &target_positions, normals)?
+            }
+            IcpMethod::Generalized => {
+                let (source_covariances, target_covariances) =
+                    covariances.as_ref().expect("covariances computed for GICP");
+                generalized_step(
+                    &correspondences,
+                    &target_positions,
+                    source_covariances,
+                    target_covariances,
+                    &transform,
+                )?
+            }
+        };
+        transform = compose_transforms(&step, &transform);
+
+        let rmse = correspondence_rmse(&correspondences);
+        if (previous_rmse - rmse).abs() < config.tolerance {
+            converged = true;
+            break;
+        }
+        previous_rmse = rmse;
+    }
+
+    let correspondences = find_correspondences(
+        &source_positions,
+        &tree,
+        &transform,
+        config.max_correspondence_distance,
+    );
+
+    Ok(IcpResult {
+        transform,
+        fitness: correspondences.len() as f32 / source_positions.len() as f32,
+        inlier_rmse: correspondence_rmse(&correspondences),
+        iterations,
+        converged,
+    })
+}
+
+/// Pair every transformed source position with its nearest target point
+///
+/// Pairs farther apart than `max_distance` are rejected.
+fn find_correspondences<P: Point>(
+    source_positions: &[[f32; 3]],
+    tree: &KdTree<P>,
+    transform: &Transform,
+    max_distance: f32,
+) -> Vec<Correspondence> {
+    let max_distance_squared = max_distance * max_distance;
+
+    source_positions
+        .par_iter()
+        .enumerate()
+        .filter_map(|(source_index, &pos)| {
+            let source = apply_transform(pos, transform);
+            let (target_index, distance_squared) = tree.nearest_index(source)?;
+            if distance_squared > max_distance_squared {
+                return None;
+            }
+            Some(Correspondence {
+                source_index,
+                source,
+                target_index,
+                distance_squared,
+            })
+        })
+        .collect()
+}
+
+/// Keep only the closest `inlier_ratio` fraction of the correspondences
+fn reject_worst_correspondences(correspondences: &mut Vec<Correspondence>, inlier_ratio: f32) {
+    if inlier_ratio >= 1.0 || correspondences.is_empty() {
+        return;
+    }
+
+    let keep = ((correspondences.len() as f32 * inlier_ratio).ceil() as usize).max(1);
+    correspondences.sort_by(|a, b| a.distance_squared.total_cmp(&b.distance_squared));
+    correspondences.truncate(keep);
+}
+
+/// Root mean square distance over a set of correspondences
+fn correspondence_rmse(correspondences: &[Correspondence]) -> f32 {
+    if correspondences.is_empty() {
+        return 0.0;
+    }
+
+    let sum: f64 = correspondences
+        .iter()
+        .map(|c| c.distance_squared as f64)
+        .sum();
+    (sum / correspondences.len() as f64).sqrt() as f32
+}
+
+/// Linearized point-to-plane step
+///
+/// Solves for the small rotation and translation minimizing the squared
+/// distances of the source points to the tangent planes of their targets.
+fn point_to_plane_step(
+    correspondences: &[Correspondence],
+    target_positions: &[[f32; 3]],
+    target_normals: &[[f32; 3]],
+) -> Result<Transform> {
+    let mut ata = [[0.0f64; 6]; 6];
+    let mut atb = [0.0f64; 6];
+
+    for c in correspondences {
+        let n = target_normals[c.target_index];
+        if !n.iter().all(|v| v.is_finite()) {
+            continue;
+        }
+
+        let s = to_f64(c.source);
+        let t = to_f64(target_positions[c.target_index]);
+        let n = to_f64(n);
+
+        let sxn = cross(s, n);
+        let row = [sxn[0], sxn[1], sxn[2], n[0], n[1], n[2]];
+        let residual = (t[0] - s[0]) * n[0] + (t[1] - s[1]) * n[1] + (t[2] - s[2]) * n[2];
+
+        for i in 0..6 {
+            for j in 0..6 {
+                ata[i][j] += row[i] * row[j];
+            }
+            atb[i] += row[i] * residual;
+        }
+    }
+
+    let x = math::solve_linear_system(ata, atb).ok_or_else(|| {
+        CloudError::algorithm_error("Degenerate point-to-plane system (check target normals)")
+    })?;
+
+    Ok(transform_from_parameters(x))
+}
+
+/// Linearized generalized-ICP step
+///
+/// Each residual is weighted by the inverse of the combined covariance
+/// `C_target + R * C_source * R^T`, where R is the current rotation estimate.
+fn generalized_step(
+    correspondences: &[Correspondence],
+    target_positions: &[[f32; 3]],
+    source_covariances: &[[[f64; 3]; 3]],
+    target_covariances: &[[[f64; 3]; 3]],
+    transform: &Transform,
 ) -> Result<Transform> {
-    // TODO: Implement full ICP algorithm
-    // For now, return identity transformation
-    Ok(IDENTITY_TRANSFORM)
+    let mut rotation = [[0.0f64; 3]; 3];
+    for i in 0..3 {
+        for j in 0..3 {
+            rotation[i][j] = transform[i][j] as f64;
+        }
+    }
+
+    let mut h = [[0.0f64; 6]; 6];
+    let mut g = [0.0f64; 6];
+
+    for c in correspondences {
+        let rotated = mat_mul(
+            &mat_mul(&rotation, &source_covariances[c.source_index]),
+            &transpose(&rotation),
+        );
+        let target_covariance = &target_covariances[c.target_index];
+        let mut combined = [[0.0f64; 3]; 3];
+        for i in 0..3 {
+            for j in 0..3 {
+                combined[i][j] = rotated[i][j] + target_covariance[i][j];
+            }
+        }
+        let Some(weight) = invert_3x3(&combined) else {
+            continue;
+        };
+
+        let s = to_f64(c.source);
+        let t = to_f64(target_positions[c.target_index]);
+        let residual = [t[0] - s[0], t[1] - s[1], t[2] - s[2]];
+
+        // Jacobian of the moved source point w.r.t. [rotation, translation]
+        let jacobian = [
+            [0.0, s[2], -s[1], 1.0, 0.0, 0.0],
+            [-s[2], 0.0, s[0], 0.0, 1.0, 0.0],
+            [s[1], -s[0], 0.0, 0.0, 0.0, 1.0],
+        ];
+
+        // weighted = W * J (3x6)
+        let mut weighted = [[0.0f64; 6]; 3];
+        for i in 0..3 {
+            for j in 0..6 {
+                weighted[i][j] = (0..3).map(|k| weight[i][k] * jacobian[k][j]).sum();
+            }
+        }
+
+        for i in 0..6 {
+            for j in 0..6 {
+                h[i][j] += (0..3).map(|k| jacobian[k][i] * weighted[k][j]).sum::<f64>();
+            }
+            g[i] += (0..3).map(|k| weighted[k][i] * residual[k]).sum::<f64>();
+        }
+    }
+
+    let x = math::solve_linear_system(h, g)
+        .ok_or_else(|| CloudError::algorithm_error("Degenerate generalized ICP system"))?;
+
+    Ok(transform_from_parameters(x))
+}
+
+/// Estimate plane-like covariances for GICP from each point's neighborhood
+///
+/// The covariance eigenvalues are replaced by `(epsilon, 1, 1)` so that every
+/// point is modelled as a small disk aligned with its local surface.
+fn plane_covariances<P: Point>(
+    positions: &[[f32; 3]],
+    tree: &KdTree<P>,
+    neighbors: usize,
+) -> Vec<[[f64; 3]; 3]> {
+    const EPSILON: f64 = 1e-3;
+
+    positions
+        .par_iter()
+        .map(|&pos| {
+            let neighbor_positions: Vec<[f64; 3]> = tree
+                .k_nearest_indices(pos, neighbors.max(3))
+                .into_iter()
+                .map(|(i, _)| to_f64(positions[i]))
+                .collect();
+
+            if neighbor_positions.len() < 3 {
+                return [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+            }
+
+            let count = neighbor_positions.len() as f64;
+            let mut mean = [0.0f64; 3];
+            for p in &neighbor_positions {
+                for i in 0..3 {
+                    mean[i] += p[i] / count;
+                }
+            }
+            let mut covariance = [[0.0f64; 3]; 3];
+            for p in &neighbor_positions {
+                for i in 0..3 {
+                    for j in 0..3 {
+                        covariance[i][j] += (p[i] - mean[i]) * (p[j] - mean[j]) / count;
+                    }
+                }
+            }
+
+            let (_, vectors) = math::symmetric_eigen(covariance);
+            let scales = [EPSILON, 1.0, 1.0];
+            let mut result = [[0.0f64; 3]; 3];
+            for (v, scale) in vectors.iter().zip(scales) {
+                for i in 0..3 {
+                    for j in 0..3 {
+                        result[i][j] += scale * v[i] * v[j];
+                    }
+                }
+            }
+            result
+        })
+        .collect()
+}
+
+/// Build a transform from small-angle rotation and translation parameters
+///
+/// `x` is `[alpha, beta, gamma, tx, ty, tz]`, with the rotation applied as
+/// `Rz(gamma) * Ry(beta) * Rx(alpha)`.
+fn transform_from_parameters(x: [f64; 6]) -> Transform {
+    let (sa, ca) = x[0].sin_cos();
+    let (sb, cb) = x[1].sin_cos();
+    let (sg, cg) = x[2].sin_cos();
+
+    [
+        [
+            (cg * cb) as f32,
+            (cg * sb * sa - sg * ca) as f32,
+            (cg * sb * ca + sg * sa) as f32,
+            x[3] as f32,
+        ],
+        [
+            (sg * cb) as f32,
+            (sg * sb * sa + cg * ca) as f32,
+            (sg * sb * ca - cg * sa) as f32,
+            x[4] as f32,
+        ],
+        [
+            (-sb) as f32,
+            (cb * sa) as f32,
+            (cb * ca) as f32,
+            x[5] as f32,
+        ],
+        [0.0, 0.0, 0.0, 1.0],
+    ]
+}
+
+fn to_f64(v: [f32; 3]) -> [f64; 3] {
+    [v[0] as f64, v[1] as f64, v[2] as f64]
+}
+
+fn cross(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn mat_mul(a: &[[f64; 3]; 3], b: &[[f64; 3]; 3]) -> [[f64; 3]; 3] {
+    let mut result = [[0.0; 3]; 3];
+    for (i, row) in result.iter_mut().enumerate() {
+        for (j, value) in row.iter_mut().enumerate() {
+            *value = (0..3).map(|k| a[i][k] * b[k][j]).sum();
+        }
+    }
+    result
+}
+
+fn transpose(m: &[[f64; 3]; 3]) -> [[f64; 3]; 3] {
+    [
+        [m[0][0], m[1][0], m[2][0]],
+        [m[0][1], m[1][1], m[2][1]],
+        [m[0][2], m[1][2], m[2][2]],
+    ]
+}
+
+fn invert_3x3(m: &[[f64; 3]; 3]) -> Option<[[f64; 3]; 3]> {
+    let det = m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+        - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+        + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0]);
+    if det.abs() < 1e-18 {
+        return None;
+    }
+
+    let inv_det = 1.0 / det;
+    Some([
+        [
+            (m[1][1] * m[2][2] - m[1][2] * m[2][1]) * inv_det,
+            (m[0][2] * m[2][1] - m[0][1] * m[2][2]) * inv_det,
+            (m[0][1] * m[1][2] - m[0][2] * m[1][1]) * inv_det,
+        ],
+        [
+            (m[1][2] * m[2][0] - m[1][0] * m[2][2]) * inv_det,
+            (m[0][0] * m[2][2] - m[0][2] * m[2][0]) * inv_det,
+            (m[0][2] * m[1][0] - m[0][0] * m[1][2]) * inv_det,
+        ],
+        [
+            (m[1][0] * m[2][1] - m[1][1] * m[2][0]) * inv_det,
+            (m[0][1] * m[2][0] - m[0][0] * m[2][1]) * inv_det,
+            (m[0][0] * m[1][1] - m[0][1] * m[1][0]) * inv_det,
+        ],
+    ])
+}
+
+/// Estimate the rigid transform that best maps `source` onto `target`
+///
+/// Uses Horn's closed-form quaternion solution, minimizing the sum of squared
+/// distances between corresponding points. Both slices must have the same
+/// length and contain at least 3 points.
+pub fn estimate_rigid_transform(source: &[[f32; 3]], target: &[[f32; 3]]) -> Result<Transform> {
+    if source.len() != target.len() {
+        return Err(CloudError::invalid_parameter(
+            "Source and target must have the same number of points",
+        ));
+    }
+    if source.len() < 3 {
+        return Err(CloudError::algorithm_error(
+            "Need at least 3 point pairs to estimate a rigid transform",
+        ));
+    }
+
+    let count = source.len() as f64;
+    let mut source_centroid = [0.0f64; 3];
+    let mut target_centroid = [0.0f64; 3];
+    for (s, t) in source.iter().zip(target.iter()) {
+        for i in 0..3 {
+            source_centroid[i] += s[i] as f64 / count;
+            target_centroid[i] += t[i] as f64 / count;
+        }
+    }
+
+    // Cross-covariance matrix of the centered point sets
+    let mut m = [[0.0f64; 3]; 3];
+    for (s, t) in source.iter().zip(target.iter()) {
+        for i in 0..3 {
+            for j in 0..3 {
+                m[i][j] += (s[i] as f64 - source_centroid[i]) * (t[j] as f64 - target_centroid[j]);
+            }
+        }
+    }
+
+    let n = [
+        [
+            m[0][0] + m[1][1] + m[2][2],
+            m[1][2] - m[2][1],
+            m[2][0] - m[0][2],
+            m[0][1] - m[1][0],
+        ],
+        [
+            m[1][2] - m[2][1],
+            m[0][0] - m[1][1] - m[2][2],
+            m[0][1] + m[1][0],
+            m[2][0] + m[0][2],
+        ],
+        [
+            m[2][0] - m[0][2],
+            m[0][1] + m[1][0],
+            -m[0][0] + m[1][1] - m[2][2],
+            m[1][2] + m[2][1],
+        ],
+        [
+            m[0][1] - m[1][0],
+            m[2][0] + m[0][2],
+            m[1][2] + m[2][1],
+            -m[0][0] - m[1][1] + m[2][2],
+        ],
+    ];
+
+    // The optimal rotation is the eigenvector of the largest eigenvalue
+    let (_, vectors) = math::symmetric_eigen(n);
+    let q = vectors[3];
+    let rotation =
+        math::quaternion_to_rotation([q[0] as f32, q[1] as f32, q[2] as f32, q[3] as f32]);
+
+    let mut transform = IDENTITY_TRANSFORM;
+    for i in 0..3 {
+        let mut translation = target_centroid[i];
+        for j in 0..3 {
+            transform[i][j] = rotation[i][j];
+            translation -= rotation[i][j] as f64 * source_centroid[j];
+        }
+        transform[i][3] = translation as f32;
+    }
+
+    Ok(transform)
+}
+
+/// Compose two transformations, returning `a * b` (apply `b` first, then `a`)
+pub fn compose_transforms(a: &Transform, b: &Transform) -> Transform {
+    let mut result = [[0.0; 4]; 4];
+    for (i, row) in result.iter_mut().enumerate() {
+        for (j, value) in row.iter_mut().enumerate() {
+            *value = (0..4).map(|k| a[i][k] * b[k][j]).sum();
+        }
+    }
+    result
 }
 
 /// Apply transformation to a point cloud
-pub fn transform_point_cloud<P: Point>(
+///
+/// Positions are transformed by the full matrix. Directional attributes such
+/// as normals are rotated by its upper-left 3x3 block, which assumes the
+/// transformation is rigid.
+pub fn transform_point_cloud<P: PointMut>(
     cloud: PointCloud<P>,
     transform: &Transform,
 ) -> PointCloud<P> {
-    cloud.map(|point| {
-        let pos = point.position();
-        let transformed_pos = apply_transform(pos, transform);
-        // Note: This is simplified - we'd need a way to create a new point
-        // with the transformed position. For now, return the original point.
+    let rotation = [0, 1, 2].map(|i| [transform[i][0], transform[i][1], transform[i][2]]);
+    cloud.map(|mut point| {
+        let transformed_pos = apply_transform(point.position(), transform);
+        point.set_position(transformed_pos);
+        point.rotate_directions(&rotation);
         point
     })
 }
@@ -74,10 +697,20 @@ pub trait RegistrationExt<P: Point> {
         target: &PointCloud<P>,
         max_iterations: usize,
         tolerance: f32,
-    ) -> Result<Transform>;
+    ) -> Result<IcpResult>;
+
+    /// Perform ICP registration using the given configuration
+    fn icp_register_with_config(
+        &self,
+        target: &PointCloud<P>,
+        target_normals: Option<&[[f32; 3]]>,
+        config: &IcpConfig,
+    ) -> Result<IcpResult>;
 
     /// Apply transformation to the point cloud
-    fn transform(self, transform: &Transform) -> PointCloud<P>;
+    fn transform(self, transform: &Transform) -> PointCloud<P>
+    where
+        P: PointMut;
 }
 
 impl<P: Point> RegistrationExt<P> for PointCloud<P> {
@@ -86,11 +719,23 @@ impl<P: Point> RegistrationExt<P> for PointCloud<P> {
         target: &PointCloud<P>,
         max_iterations: usize,
         tolerance: f32,
-    ) -> Result<Transform> {
+    ) -> Result<IcpResult> {
         icp_registration(self, target, max_iterations, tolerance)
     }
 
-    fn transform(self, transform: &Transform) -> PointCloud<P> {
+    fn icp_register_with_config(
+        &self,
+        target: &PointCloud<P>,
+        target_normals: Option<&[[f32; 3]]>,
+        config: &IcpConfig,
+    ) -> Result<IcpResult> {
+        icp_registration_with_config(self, target, target_normals, config)
+    }
+
+    fn transform(self, transform: &Transform) -> PointCloud<P>
+    where
+        P: PointMut,
+    {
         transform_point_cloud(self, transform)
     }
 }
@@ -98,7 +743,7 @@ impl<P: Point> RegistrationExt<P> for PointCloud<P> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::core::PointXYZ;
+    use crate::core::{PointXYZ, PointXYZRGBNormal};
 
     #[test]
     fn test_identity_transform() {
@@ -107,18 +752,247 @@ mod tests {
 
         let transformed = cloud.transform(&IDENTITY_TRANSFORM);
         assert_eq!(transformed.len(), 1);
+        assert_eq!(transformed.get(0).unwrap().position(), [1.0, 2.0, 3.0]);
     }
 
     #[test]
-    fn test_icp_placeholder() {
-        let source_points = vec![PointXYZ::new(0.0, 0.0, 0.0)];
-        let target_points = vec![PointXYZ::new(1.0, 0.0, 0.0)];
+    fn test_translation_transform() {
+        let points = vec![PointXYZ::new(1.0, 2.0, 3.0)];
+        let cloud = PointCloud::from_points(points);
 
-        let source = PointCloud::from_points(source_points);
-        let target = PointCloud::from_points(target_points);
+        // 90 degree rotation about z followed by a translation
+        let transform = [
+            [0.0, -1.0, 0.0, 10.0],
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, -1.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ];
+
+        let transformed = cloud.transform(&transform);
+        assert_eq!(transformed.get(0).unwrap().position(), [8.0, 1.0, 2.0]);
+    }
 
-        let transform = source.icp_register(&target, 10, 1e-6).unwrap();
-        // Should return identity for now
-        assert_eq!(transform, IDENTITY_TRANSFORM);
+    #[test]
+    fn test_transform_rotates_normals() {
+        let point = PointXYZRGBNormal::new(1.0, 2.0, 3.0, 10, 20, 30, 1.0, 0.0, 0.0);
+        let cloud = PointCloud::from_points(vec![point]);
+
+        // 90 degree rotation about z followed by a translation
+        let transform = [
+            [0.0, -1.0, 0.0, 10.0],
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, -1.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ];
+
+        let transformed = cloud.transform(&transform);
+        let point = transformed.get(0).unwrap();
+        assert_eq!(point.position(), [8.0, 1.0, 2.0]);
+        assert_eq!(point.normal(), [0.0, 1.0, 0.0]);
+        assert_eq!(point.color(), Some([10, 20, 30]));
+    }
+
+    /// Build a non-symmetric test surface so ICP has a unique solution
+    fn sample_surface() -> PointCloud<PointXYZ> {
+        let mut points = Vec::new();
+        for i in 0..15 {
+            for j in 0..15 {
+                let x = i as f32 * 0.1;
+                let y = j as f32 * 0.1;
+                let z = 0.3 * x * x - 0.2 * y + 0.1 * x * y;
+                points.push(PointXYZ::new(x, y, z));
+            }
+        }
+        PointCloud::from_points(points)
+    }
+
+    #[test]
+    fn test_estimate_rigid_transform() {
+        let source = vec![
+            [0.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [0.0, 2.0, 0.0],
+            [0.0, 0.0, 3.0],
+        ];
+        let expected = [
+            [0.0, -1.0, 0.0, 1.0],
+            [1.0, 0.0, 0.0, 2.0],
+            [0.0, 0.0, 1.0, 3.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ];
+        let target: Vec<[f32; 3]> = source
+            .iter()
+            .map(|&p| apply_transform(p, &expected))
+            .collect();
+
+        let transform = estimate_rigid_transform(&source, &target).unwrap();
+        for i in 0..4 {
+            for j in 0..4 {
+                assert!((transform[i][j] - expected[i][j]).abs() < 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn test_icp_recovers_transform() {
+        let target = sample_surface();
+
+        let angle: f32 = 0.1;
+        let (sin, cos) = angle.sin_cos();
+        let misalignment = [
+            [cos, -sin, 0.0, 0.05],
+            [sin, cos, 0.0, -0.03],
+            [0.0, 0.0, 1.0, 0.02],
+            [0.0, 0.0, 0.0, 1.0],
+        ];
+        let source = target.clone().transform(&misalignment);
+
+        let result = source.icp_register(&target, 100, 1e-7).unwrap();
+        assert!(result.iterations > 0);
+        assert!(result.inlier_rmse < 1e-3);
+        assert!((result.fitness - 1.0).abs() < f32::EPSILON);
+
+        let aligned = source.transform(&result.transform);
+        for (a, b) in aligned.iter().zip(target.iter()) {
+            assert!(a.distance_to(b) < 1e-3);
+        }
+    }
+
+    /// Misalign a cloud by a small rotation about z plus a translation
+    fn misalignment() -> Transform {
+        let angle: f32 = 0.08;
+        let (sin, cos) = angle.sin_cos();
+        [
+            [cos, -sin, 0.0, 0.04],
+            [sin, cos, 0.0, -0.03],
+            [0.0, 0.0, 1.0, 0.02],
+            [0.0, 0.0, 0.0, 1.0],
+        ]
+    }
+
+    #[test]
+    fn test_point_to_plane_icp() {
+        // Bowl z = 0.5x^2 + 0.3y^2 + 0.1xy with analytic normals
+        let target: PointCloud<PointXYZRGBNormal> = sample_surface().map(|p| {
+            let (x, y) = (p.x - 0.7, p.y - 0.7);
+            let z = 0.5 * x * x + 0.3 * y * y + 0.1 * x * y;
+            let n = math::normalize([-(x + 0.1 * y), -(0.6 * y + 0.1 * x), 1.0]);
+            PointXYZRGBNormal::new(x, y, z, 0, 0, 0, n[0], n[1], n[2])
+        });
+        let source = target.clone().transform(&misalignment());
+
+        let config = IcpConfig {
+            method: IcpMethod::PointToPlane,
+            max_iterations: 100,
+            tolerance: 1e-8,
+            ..IcpConfig::default()
+        };
+        let normals = target.normals();
+        let result = source
+            .icp_register_with_config(&target, Some(&normals), &config)
+            .unwrap();
+
+        let aligned = source.transform(&result.transform);
+        for (a, b) in aligned.iter().zip(target.iter()) {
+            assert!(a.distance_to(b) < 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_point_to_plane_requires_normals() {
+        let target = sample_surface();
+        let config = IcpConfig {
+            method: IcpMethod::PointToPlane,
+            ..IcpConfig::default()
+        };
+        assert!(
+            target
+                .icp_register_with_config(&target, None, &config)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_generalized_icp() {
+        let target = sample_surface();
+        let source = target.clone().transform(&misalignment());
+
+        let config = IcpConfig {
+            method: IcpMethod::Generalized,
+            max_iterations: 100,
+            tolerance: 1e-8,
+            covariance_neighbors: 10,
+            ..IcpConfig::default()
+        };
+        let result = source
+            .icp_register_with_config(&target, None, &config)
+            .unwrap();
+
+        let aligned = source.transform(&result.transform);
+        for (a, b) in aligned.iter().zip(target.iter()) {
+            assert!(a.distance_to(b) < 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_icp_initial_guess_and_rejection() {
+        let target = sample_surface();
+        let offset = [
+            [1.0, 0.0, 0.0, 5.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ];
+        let source = target.clone().transform(&offset);
+
+        // Without a guess, no correspondences survive the distance threshold
+        let mut config = IcpConfig {
+            max_correspondence_distance: 0.5,
+            inlier_ratio: 0.9,
+            ..IcpConfig::default()
+        };
+        assert!(
+            source
+                .icp_register_with_config(&target, None, &config)
+                .is_err()
+        );
+
+        config.initial_guess = [
+            [1.0, 0.0, 0.0, -4.95],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ];
+        let result = source
+            .icp_register_with_config(&target, None, &config)
+            .unwrap();
+        assert!((result.transform[0][3] + 5.0).abs() < 1e-3);
+        assert!(result.fitness > 0.99);
+    }
+
+    #[test]
+    fn test_icp_skips_invalid_points() {
+        let target = sample_surface();
+        let mut source = target.clone().transform(&misalignment());
+        source.points_mut().push(PointXYZ::new(f32::NAN, 0.0, 0.0));
+
+        let result = source
+            .icp_register_with_config(&target, None, &IcpConfig::default())
+            .unwrap();
+        assert!(result.inlier_rmse.is_finite());
+        assert!(result.fitness < 1.0);
+        assert!(result.transform.iter().flatten().all(|v| v.is_finite()));
+
+        let aligned = source.transform(&result.transform);
+        for (a, b) in aligned.iter().zip(target.iter()) {
+            assert!(a.distance_to(b) < 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_icp_requires_points() {
+        let source = PointCloud::<PointXYZ>::new();
+        let target = sample_surface();
+        assert!(source.icp_register(&target, 10, 1e-6).is_err());
     }
 }
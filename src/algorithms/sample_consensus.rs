@@ -0,0 +1,1099 @@
+//! Sample consensus model fitting
+//!
+//! This module provides a generic framework for robustly fitting geometric
+//! models (planes, lines, spheres, cylinders, circles) to point clouds that
+//! contain outliers, together with the RANSAC, MSAC, LMedS and PROSAC
+//! estimators.
+
+use crate::algorithms::feature::compute_point_normal;
+use crate::core::{Point, PointCloud, PointNormal};
+use crate::error::{CloudError, Result};
+use crate::utils::math;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rayon::prelude::*;
+
+/// A geometric model that can be fit to a point cloud by sample consensus
+///
+/// Models own the point data they are fit to and describe a hypothesis with
+/// a flat coefficient vector whose layout is documented on each model.
+pub trait SampleConsensusModel: Sync {
+    /// Number of points the model is fit to
+    fn len(&self) -> usize;
+
+    /// Check if the model has no points
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of points needed to compute a model hypothesis
+    fn sample_size(&self) -> usize;
+
+    /// Compute model coefficients from a minimal sample of point indices
+    ///
+    /// Returns `None` for degenerate samples or hypotheses that violate the
+    /// model's constraints.
+    fn compute_model(&self, sample: &[usize]) -> Option<Vec<f32>>;
+
+    /// Distance of a point to the model described by `coefficients`
+    fn distance(&self, index: usize, coefficients: &[f32]) -> f32;
+
+    /// Distances of all points to the model
+    fn distances(&self, coefficients: &[f32]) -> Vec<f32> {
+        (0..self.len())
+            .into_par_iter()
+            .map(|i| self.distance(i, coefficients))
+            .collect()
+    }
+
+    /// Refine model coefficients using all inliers
+    ///
+    /// The default implementation returns the coefficients unchanged.
+    fn refine_model(&self, _inliers: &[usize], coefficients: &[f32]) -> Option<Vec<f32>> {
+        Some(coefficients.to_vec())
+    }
+}
+
+/// Robust estimator used to score model hypotheses
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum SacMethod {
+    /// Random sample consensus: maximize the number of inliers
+    #[default]
+    Ransac,
+
+    /// M-estimator sample consensus: minimize truncated squared distances
+    Msac,
+
+    /// Least median of squares: minimize the median squared distance
+    Lmeds,
+
+    /// Progressive sample consensus: sample from the best-ranked points
+    /// first. Points must be ordered by decreasing quality.
+    Prosac,
+}
+
+/// Configuration for sample consensus estimation
+#[derive(Clone, Debug)]
+pub struct SacConfig {
+    /// Estimator used to score hypotheses
+    pub method: SacMethod,
+
+    /// Maximum point-to-model distance for a point to count as an inlier
+    pub distance_threshold: f32,
+
+    /// Upper bound on the number of sampled hypotheses
+    pub max_iterations: usize,
+
+    /// Probability of drawing at least one outlier-free sample, used to
+    /// stop early once enough hypotheses have been tested
+    pub confidence: f64,
+
+    /// Seed for the random sampler; `None` draws a seed from the OS
+    pub seed: Option<u64>,
+
+    /// Refit the best model to all of its inliers
+    pub refine: bool,
+}
+
+impl Default for SacConfig {
+    fn default() -> Self {
+        Self {
+            method: SacMethod::Ransac,
+            distance_threshold: 0.01,
+            max_iterations: 1000,
+            confidence: 0.99,
+            seed: None,
+            refine: true,
+        }
+    }
+}
+
+/// Result of a sample consensus estimation
+#[derive(Clone, Debug, PartialEq)]
+pub struct SacResult {
+    /// Coefficients of the best model
+    pub coefficients: Vec<f32>,
+
+    /// Indices of the points within the distance threshold of the model
+    pub inliers: Vec<usize>,
+
+    /// Number of hypotheses tested
+    pub iterations: usize,
+}
+
+/// Fit a model to its points with the configured robust estimator
+pub fn sample_consensus<M: SampleConsensusModel>(
+    model: &M,
+    config: &SacConfig,
+) -> Result<SacResult> {
+    let sample_size = model.sample_size();
+    if model.len() < sample_size {
+        return Err(CloudError::algorithm_error(format!(
+            "Need at least {} points to fit the model",
+            sample_size
+        )));
+    }
+    if !(config.confidence > 0.0 && config.confidence < 1.0) {
+        return Err(CloudError::invalid_parameter(
+            "Sample consensus confidence must be in (0, 1)",
+        ));
+    }
+    if config.distance_threshold < 0.0 {
+        return Err(CloudError::invalid_parameter(
+            "Distance threshold must not be negative",
+        ));
+    }
+
+    let mut rng = match config.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+    let mut sampler = Sampler::new(
+        config.method,
+        model.len(),
+        sample_size,
+        config.max_iterations,
+    );
+
+    let threshold = config.distance_threshold;
+    let threshold_squared = threshold * threshold;
+
+    let mut best: Option<(Vec<f32>, f64)> = None;
+    let mut required_iterations = config.max_iterations;
+    let mut iterations = 0;
+
+    while iterations < required_iterations.min(config.max_iterations) {
+        iterations += 1;
+
+        let sample = sampler.next_sample(&mut rng, iterations);
+        let Some(coefficients) = model.compute_model(&sample) else {
+            continue;
+        };
+        let distances = model.distances(&coefficients);
+
+        // Lower costs are better for every estimator
+        let inlier_count = distances.iter().filter(|&&d| d <= threshold).count();
+        let cost = match config.method {
+            SacMethod::Ransac | SacMethod::Prosac => -(inlier_count as f64),
+            SacMethod::Msac => distances
+                .iter()
+                .map(|&d| {
+                    let d_squared = d * d;
+                    if d_squared.is_finite() {
+                        d_squared.min(threshold_squared) as f64
+                    } else {
+                        threshold_squared as f64
+                    }
+                })
+                .sum(),
+            SacMethod::Lmeds => median_squared_distance(&distances),
+        };
+
+        if best.as_ref().is_none_or(|(_, best_cost)| cost < *best_cost) {
+            best = Some((coefficients, cost));
+            if config.method != SacMethod::Lmeds {
+                required_iterations = adaptive_iteration_count(
+                    inlier_count as f64 / model.len() as f64,
+                    sample_size as i32,
+                    config.confidence,
+                );
+            }
+        }
+    }
+
+    let (mut coefficients, _) = best.ok_or_else(|| {
+        CloudError::algorithm_error("Sample consensus could not find a valid model")
+    })?;
+    let mut inliers = inliers_within(&model.distances(&coefficients), threshold);
+
+    if config.refine
+        && inliers.len() >= sample_size
+        && let Some(refined) = model.refine_model(&inliers, &coefficients)
+    {
+        let refined_inliers = inliers_within(&model.distances(&refined), threshold);
+        if refined_inliers.len() >= inliers.len() {
+            coefficients = refined;
+            inliers = refined_inliers;
+        }
+    }
+
+    Ok(SacResult {
+        coefficients,
+        inliers,
+        iterations,
+    })
+}
+
+/// Number of iterations needed to draw an all-inlier sample with the given confidence
+pub(crate) fn adaptive_iteration_count(
+    inlier_ratio: f64,
+    sample_size: i32,
+    confidence: f64,
+) -> usize {
+    let all_inliers = inlier_ratio.powi(sample_size);
+    if all_inliers >= 1.0 {
+        return 1;
+    }
+    if all_inliers <= f64::EPSILON {
+        return usize::MAX;
+    }
+
+    let iterations = (1.0 - confidence).ln() / (1.0 - all_inliers).ln();
+    if iterations.is_finite() {
+        iterations.ceil().max(1.0) as usize
+    } else {
+        usize::MAX
+    }
+}
+
+/// Indices whose distance is within the threshold
+fn inliers_within(distances: &[f32], threshold: f32) -> Vec<usize> {
+    distances
+        .iter()
+        .enumerate()
+        .filter(|&(_, &d)| d <= threshold)
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Median of the squared distances, treating NaN as infinitely far
+fn median_squared_distance(distances: &[f32]) -> f64 {
+    let mut squared: Vec<f32> = distances
+        .iter()
+        .map(|&d| if d.is_nan() { f32::INFINITY } else { d * d })
+        .collect();
+    let middle = squared.len() / 2;
+    let (_, median, _) = squared.select_nth_unstable_by(middle, |a, b| a.total_cmp(b));
+    *median as f64
+}
+
+/// Draws minimal samples, uniformly or progressively (PROSAC)
+struct Sampler {
+    progressive: bool,
+    point_count: usize,
+    sample_size: usize,
+
+    // PROSAC growth schedule (Chum & Matas, 2005)
+    subset_size: usize,
+    t_n: f64,
+    t_n_prime: usize,
+}
+
+impl Sampler {
+    fn new(
+        method: SacMethod,
+        point_count: usize,
+        sample_size: usize,
+        max_iterations: usize,
+    ) -> Self {
+        let progressive = method == SacMethod::Prosac;
+
+        // Expected number of samples drawn from the first `sample_size` points
+        let mut t_n = max_iterations.max(1) as f64;
+        for i in 0..sample_size {
+            t_n *= (sample_size - i) as f64 / (point_count - i) as f64;
+        }
+
+        Self {
+            progressive,
+            point_count,
+            sample_size,
+            subset_size: sample_size,
+            t_n,
+            t_n_prime: 1,
+        }
+    }
+
+    fn next_sample(&mut self, rng: &mut StdRng, iteration: usize) -> Vec<usize> {
+        if !self.progressive {
+            return rand::seq::index::sample(rng, self.point_count, self.sample_size).into_vec();
+        }
+
+        if iteration > self.t_n_prime && self.subset_size < self.point_count {
+            let n = self.subset_size as f64;
+            let t_next = self.t_n * (n + 1.0) / (n + 1.0 - self.sample_size as f64);
+            self.t_n_prime += (t_next - self.t_n).ceil() as usize;
+            self.t_n = t_next;
+            self.subset_size += 1;
+        }
+
+        if self.t_n_prime < iteration || self.subset_size == self.sample_size {
+            // Sample entirely from the current top-ranked subset
+            rand::seq::index::sample(rng, self.subset_size, self.sample_size).into_vec()
+        } else {
+            // Always include the newest point of the subset
+            let mut sample =
+                rand::seq::index::sample(rng, self.subset_size - 1, self.sample_size - 1)
+                    .into_vec();
+            sample.push(self.subset_size - 1);
+            sample
+        }
+    }
+}
+
+/// Orientation constraint for plane models
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PlaneConstraint {
+    /// Any plane is accepted
+    None,
+
+    /// The plane must contain the axis direction (its normal is
+    /// perpendicular to the axis) within `eps_angle` radians
+    ParallelTo { axis: [f32; 3], eps_angle: f32 },
+
+    /// The plane normal must be parallel to the axis within `eps_angle` radians
+    PerpendicularTo { axis: [f32; 3], eps_angle: f32 },
+}
+
+/// Plane model, coefficients `[a, b, c, d]` with `ax + by + cz + d = 0`
+pub struct PlaneModel {
+    positions: Vec<[f32; 3]>,
+    constraint: PlaneConstraint,
+}
+
+impl PlaneModel {
+    /// Create an unconstrained plane model for a point cloud
+    pub fn new<P: Point>(cloud: &PointCloud<P>) -> Self {
+        Self {
+            positions: positions_of(cloud),
+            constraint: PlaneConstraint::None,
+        }
+    }
+
+    /// Create a model for planes parallel to `axis`
+    pub fn parallel<P: Point>(cloud: &PointCloud<P>, axis: [f32; 3], eps_angle: f32) -> Self {
+        Self::new(cloud).with_constraint(PlaneConstraint::ParallelTo {
+            axis: math::normalize(axis),
+            eps_angle,
+        })
+    }
+
+    /// Create a model for planes perpendicular to `axis`
+    pub fn perpendicular<P: Point>(cloud: &PointCloud<P>, axis: [f32; 3], eps_angle: f32) -> Self {
+        Self::new(cloud).with_constraint(PlaneConstraint::PerpendicularTo {
+            axis: math::normalize(axis),
+            eps_angle,
+        })
+    }
+
+    /// Set the orientation constraint
+    pub fn with_constraint(mut self, constraint: PlaneConstraint) -> Self {
+        self.constraint = constraint;
+        self
+    }
+
+    /// Check the plane normal against the orientation constraint
+    fn satisfies_constraint(&self, normal: [f32; 3]) -> bool {
+        match self.constraint {
+            PlaneConstraint::None => true,
+            PlaneConstraint::ParallelTo { axis, eps_angle } => {
+                // Angle between normal and axis must be ~90 degrees
+                math::dot_product(normal, axis).abs() <= eps_angle.sin()
+            }
+            PlaneConstraint::PerpendicularTo { axis, eps_angle } => {
+                math::dot_product(normal, axis).abs() >= eps_angle.cos()
+            }
+        }
+    }
+}
+
+impl SampleConsensusModel for PlaneModel {
+    fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    fn sample_size(&self) -> usize {
+        3
+    }
+
+    fn compute_model(&self, sample: &[usize]) -> Option<Vec<f32>> {
+        let plane = plane_from_points(
+            self.positions[sample[0]],
+            self.positions[sample[1]],
+            self.positions[sample[2]],
+        )?;
+        self.satisfies_constraint([plane[0], plane[1], plane[2]])
+            .then(|| plane.to_vec())
+    }
+
+    fn distance(&self, index: usize, coefficients: &[f32]) -> f32 {
+        let p = self.positions[index];
+        (coefficients[0] * p[0] + coefficients[1] * p[1] + coefficients[2] * p[2] + coefficients[3])
+            .abs()
+    }
+
+    fn refine_model(&self, inliers: &[usize], _coefficients: &[f32]) -> Option<Vec<f32>> {
+        let points: Vec<[f32; 3]> = inliers.iter().map(|&i| self.positions[i]).collect();
+        let plane = plane_least_squares(&points)?;
+        self.satisfies_constraint([plane[0], plane[1], plane[2]])
+            .then(|| plane.to_vec())
+    }
+}
+
+/// Line model, coefficients `[px, py, pz, dx, dy, dz]` (point and unit direction)
+pub struct LineModel {
+    positions: Vec<[f32; 3]>,
+}
+
+impl LineModel {
+    /// Create a line model for a point cloud
+    pub fn new<P: Point>(cloud: &PointCloud<P>) -> Self {
+        Self {
+            positions: positions_of(cloud),
+        }
+    }
+}
+
+impl SampleConsensusModel for LineModel {
+    fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    fn sample_size(&self) -> usize {
+        2
+    }
+
+    fn compute_model(&self, sample: &[usize]) -> Option<Vec<f32>> {
+        let p1 = self.positions[sample[0]];
+        let p2 = self.positions[sample[1]];
+        let direction = sub(p2, p1);
+        if math::magnitude(direction) < 1e-6 || !is_finite(p1) || !is_finite(p2) {
+            return None;
+        }
+
+        let d = math::normalize(direction);
+        Some(vec![p1[0], p1[1], p1[2], d[0], d[1], d[2]])
+    }
+
+    fn distance(&self, index: usize, coefficients: &[f32]) -> f32 {
+        distance_to_line(
+            self.positions[index],
+            line_point(coefficients),
+            line_direction(coefficients),
+        )
+    }
+
+    fn refine_model(&self, inliers: &[usize], _coefficients: &[f32]) -> Option<Vec<f32>> {
+        let points: Vec<[f32; 3]> = inliers.iter().map(|&i| self.positions[i]).collect();
+        let (centroid, covariance) = centroid_and_covariance(&points)?;
+
+        // The line direction is the principal axis of the inliers
+        let (_, vectors) = math::symmetric_eigen(covariance);
+        let d = math::normalize([
+            vectors[2][0] as f32,
+            vectors[2][1] as f32,
+            vectors[2][2] as f32,
+        ]);
+        Some(vec![
+            centroid[0],
+            centroid[1],
+            centroid[2],
+            d[0],
+            d[1],
+            d[2],
+        ])
+    }
+}
+
+/// Sphere model, coefficients `[cx, cy, cz, r]`
+pub struct SphereModel {
+    positions: Vec<[f32; 3]>,
+    radius_limits: (f32, f32),
+}
+
+impl SphereModel {
+    /// Create a sphere model for a point cloud
+    pub fn new<P: Point>(cloud: &PointCloud<P>) -> Self {
+        Self {
+            positions: positions_of(cloud),
+            radius_limits: (0.0, f32::INFINITY),
+        }
+    }
+
+    /// Only accept spheres whose radius lies within `[min, max]`
+    pub fn with_radius_limits(mut self, min: f32, max: f32) -> Self {
+        self.radius_limits = (min, max);
+        self
+    }
+
+    /// Fit `x² + y² + z² + Dx + Ey + Fz + G = 0` to the points with least squares
+    fn algebraic_fit(&self, indices: &[usize]) -> Option<Vec<f32>> {
+        let mut ata = [[0.0f64; 4]; 4];
+        let mut atb = [0.0f64; 4];
+        for &i in indices {
+            let p = to_f64(self.positions[i]);
+            let row = [p[0], p[1], p[2], 1.0];
+            let rhs = -(p[0] * p[0] + p[1] * p[1] + p[2] * p[2]);
+            for r in 0..4 {
+                for c in 0..4 {
+                    ata[r][c] += row[r] * row[c];
+                }
+                atb[r] += row[r] * rhs;
+            }
+        }
+
+        let x = math::solve_linear_system(ata, atb)?;
+        let center = [-x[0] / 2.0, -x[1] / 2.0, -x[2] / 2.0];
+        let radius_squared =
+            center[0] * center[0] + center[1] * center[1] + center[2] * center[2] - x[3];
+        if radius_squared.is_nan() || radius_squared <= 0.0 {
+            return None;
+        }
+
+        let radius = radius_squared.sqrt() as f32;
+        within(radius, self.radius_limits)
+            .then(|| vec![center[0] as f32, center[1] as f32, center[2] as f32, radius])
+    }
+}
+
+impl SampleConsensusModel for SphereModel {
+    fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    fn sample_size(&self) -> usize {
+        4
+    }
+
+    fn compute_model(&self, sample: &[usize]) -> Option<Vec<f32>> {
+        self.algebraic_fit(sample)
+    }
+
+    fn distance(&self, index: usize, coefficients: &[f32]) -> f32 {
+        let center = [coefficients[0], coefficients[1], coefficients[2]];
+        (math::magnitude(sub(self.positions[index], center)) - coefficients[3]).abs()
+    }
+
+    fn refine_model(&self, inliers: &[usize], _coefficients: &[f32]) -> Option<Vec<f32>> {
+        self.algebraic_fit(inliers)
+    }
+}
+
+/// Cylinder model, coefficients `[px, py, pz, dx, dy, dz, r]`
+///
+/// Hypotheses are computed from two points and their surface normals. The
+/// distance blends the Euclidean distance to the cylinder surface with the
+/// angular deviation of the point normal from the surface normal, weighted
+/// by `normal_distance_weight`.
+pub struct CylinderModel {
+    positions: Vec<[f32; 3]>,
+    normals: Vec<[f32; 3]>,
+    normal_distance_weight: f32,
+    radius_limits: (f32, f32),
+}
+
+impl CylinderModel {
+    /// Create a cylinder model from a cloud and one normal per point
+    pub fn new<P: Point>(cloud: &PointCloud<P>, normals: Vec<[f32; 3]>) -> Result<Self> {
+        if normals.len() != cloud.len() {
+            return Err(CloudError::invalid_parameter(
+                "Normals must match the number of points",
+            ));
+        }
+
+        Ok(Self {
+            positions: positions_of(cloud),
+            normals,
+            normal_distance_weight: 0.1,
+            radius_limits: (0.0, f32::INFINITY),
+        })
+    }
+
+    /// Create a cylinder model from a cloud that carries normals
+    pub fn from_normal_cloud<P: PointNormal>(cloud: &PointCloud<P>) -> Self {
+        Self {
+            positions: positions_of(cloud),
+            normals: cloud.normals(),
+            normal_distance_weight: 0.1,
+            radius_limits: (0.0, f32::INFINITY),
+        }
+    }
+
+    /// Set the weight of the angular normal distance in `[0, 1]`
+    pub fn with_normal_distance_weight(mut self, weight: f32) -> Self {
+        self.normal_distance_weight = weight.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Only accept cylinders whose radius lies within `[min, max]`
+    pub fn with_radius_limits(mut self, min: f32, max: f32) -> Self {
+        self.radius_limits = (min, max);
+        self
+    }
+}
+
+impl SampleConsensusModel for CylinderModel {
+    fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    fn sample_size(&self) -> usize {
+        2
+    }
+
+    fn compute_model(&self, sample: &[usize]) -> Option<Vec<f32>> {
+        let (p1, p2) = (self.positions[sample[0]], self.positions[sample[1]]);
+        let (n1, n2) = (self.normals[sample[0]], self.normals[sample[1]]);
+        if ![p1, p2, n1, n2].iter().all(|v| is_finite(*v)) {
+            return None;
+        }
+        if (p1[0] - p2[0]).abs() <= f32::EPSILON
+            && (p1[1] - p2[1]).abs() <= f32::EPSILON
+            && (p1[2] - p2[2]).abs() <= f32::EPSILON
+        {
+            return None;
+        }
+
+        // Closest points between the two normal lines p1 + s*n1 and p2 + t*n2
+        let w = sub(p1, p2);
+        let a = math::dot_product(n1, n1);
+        let b = math::dot_product(n1, n2);
+        let c = math::dot_product(n2, n2);
+        let d = math::dot_product(n1, w);
+        let e = math::dot_product(n2, w);
+        let denominator = a * c - b * b;
+        if denominator < 1e-8 {
+            return None; // Parallel normals do not define an axis
+        }
+        let s = (b * e - c * d) / denominator;
+        let t = (a * e - b * d) / denominator;
+
+        let axis_point = add(p1, scale(n1, s));
+        let axis_direction = math::normalize(sub(add(p2, scale(n2, t)), axis_point));
+        if math::magnitude(axis_direction) < 0.5 {
+            return None;
+        }
+
+        let radius = distance_to_line(p1, axis_point, axis_direction);
+        within(radius, self.radius_limits).then(|| {
+            vec![
+                axis_point[0],
+                axis_point[1],
+                axis_point[2],
+                axis_direction[0],
+                axis_direction[1],
+                axis_direction[2],
+                radius,
+            ]
+        })
+    }
+
+    fn distance(&self, index: usize, coefficients: &[f32]) -> f32 {
+        let p = self.positions[index];
+        let axis_point = line_point(coefficients);
+        let axis_direction = line_direction(coefficients);
+        let radius = coefficients[6];
+
+        // Surface normal of the cylinder at the point's projection
+        let along = math::dot_product(sub(p, axis_point), axis_direction);
+        let projection = add(axis_point, scale(axis_direction, along));
+        let surface_normal = math::normalize(sub(p, projection));
+
+        let euclidean = (math::magnitude(sub(p, projection)) - radius).abs();
+        let cos_angle = math::dot_product(math::normalize(self.normals[index]), surface_normal)
+            .abs()
+            .min(1.0);
+        let angular = cos_angle.acos();
+
+        let w = self.normal_distance_weight;
+        (w * angular + (1.0 - w) * euclidean).abs()
+    }
+}
+
+/// 3D circle model, coefficients `[cx, cy, cz, r, nx, ny, nz]`
+pub struct Circle3dModel {
+    positions: Vec<[f32; 3]>,
+    radius_limits: (f32, f32),
+}
+
+impl Circle3dModel {
+    /// Create a 3D circle model for a point cloud
+    pub fn new<P: Point>(cloud: &PointCloud<P>) -> Self {
+        Self {
+            positions: positions_of(cloud),
+            radius_limits: (0.0, f32::INFINITY),
+        }
+    }
+
+    /// Only accept circles whose radius lies within `[min, max]`
+    pub fn with_radius_limits(mut self, min: f32, max: f32) -> Self {
+        self.radius_limits = (min, max);
+        self
+    }
+}
+
+impl SampleConsensusModel for Circle3dModel {
+    fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    fn sample_size(&self) -> usize {
+        3
+    }
+
+    fn compute_model(&self, sample: &[usize]) -> Option<Vec<f32>> {
+        let p1 = to_f64(self.positions[sample[0]]);
+        let p2 = to_f64(self.positions[sample[1]]);
+        let p3 = to_f64(self.positions[sample[2]]);
+
+        // Circumcenter of the triangle
+        let a = sub64(p1, p3);
+        let b = sub64(p2, p3);
+        let axb = cross64(a, b);
+        let axb_squared = dot64(axb, axb);
+        if axb_squared.is_nan() || axb_squared <= 1e-18 {
+            return None;
+        }
+
+        let a_squared = dot64(a, a);
+        let b_squared = dot64(b, b);
+        let numerator = cross64(
+            [
+                a_squared * b[0] - b_squared * a[0],
+                a_squared * b[1] - b_squared * a[1],
+                a_squared * b[2] - b_squared * a[2],
+            ],
+            axb,
+        );
+        let center = [
+            p3[0] + numerator[0] / (2.0 * axb_squared),
+            p3[1] + numerator[1] / (2.0 * axb_squared),
+            p3[2] + numerator[2] / (2.0 * axb_squared),
+        ];
+        let offset = sub64(p1, center);
+        let radius = dot64(offset, offset).sqrt() as f32;
+        let normal_length = axb_squared.sqrt();
+
+        within(radius, self.radius_limits).then(|| {
+            vec![
+                center[0] as f32,
+                center[1] as f32,
+                center[2] as f32,
+                radius,
+                (axb[0] / normal_length) as f32,
+                (axb[1] / normal_length) as f32,
+                (axb[2] / normal_length) as f32,
+            ]
+        })
+    }
+
+    fn distance(&self, index: usize, coefficients: &[f32]) -> f32 {
+        let center = [coefficients[0], coefficients[1], coefficients[2]];
+        let radius = coefficients[3];
+        let normal = [coefficients[4], coefficients[5], coefficients[6]];
+
+        let offset = sub(self.positions[index], center);
+        let height = math::dot_product(offset, normal);
+        let in_plane = sub(offset, scale(normal, height));
+        let radial = math::magnitude(in_plane) - radius;
+
+        (height * height + radial * radial).sqrt()
+    }
+}
+
+/// Collect the positions of all points in a cloud
+fn positions_of<P: Point>(cloud: &PointCloud<P>) -> Vec<[f32; 3]> {
+    cloud.iter().map(|p| p.position()).collect()
+}
+
+/// Fit a plane through three points
+///
+/// Returns plane equation coefficients [a, b, c, d] where ax + by + cz + d = 0
+pub(crate) fn plane_from_points(p1: [f32; 3], p2: [f32; 3], p3: [f32; 3]) -> Option<[f32; 4]> {
+    // Calculate normal vector (cross product of two in-plane vectors)
+    let normal = math::cross_product(sub(p2, p1), sub(p3, p1));
+
+    let length = math::magnitude(normal);
+    if length.is_nan() || length < 1e-6 {
+        return None; // Degenerate or non-finite case
+    }
+
+    let [a, b, c] = [normal[0] / length, normal[1] / length, normal[2] / length];
+    let d = -(a * p1[0] + b * p1[1] + c * p1[2]);
+
+    Some([a, b, c, d])
+}
+
+/// Fit a plane to a set of points with least squares (PCA)
+///
+/// Returns plane equation coefficients [a, b, c, d] where ax + by + cz + d = 0
+pub(crate) fn plane_least_squares(points: &[[f32; 3]]) -> Option<[f32; 4]> {
+    let (normal, _) = compute_point_normal(points)?;
+    let (centroid, _) = centroid_and_covariance(points)?;
+
+    let d = -math::dot_product(normal, centroid);
+    Some([normal[0], normal[1], normal[2], d])
+}
+
+/// Centroid and covariance matrix of a set of points
+fn centroid_and_covariance(points: &[[f32; 3]]) -> Option<([f32; 3], [[f64; 3]; 3])> {
+    if points.is_empty() {
+        return None;
+    }
+
+    let count = points.len() as f64;
+    let mut mean = [0.0f64; 3];
+    for p in points {
+        for (m, &v) in mean.iter_mut().zip(p.iter()) {
+            *m += v as f64 / count;
+        }
+    }
+
+    let mut covariance = [[0.0f64; 3]; 3];
+    for p in points {
+        let d = sub64(to_f64(*p), mean);
+        for (row, &di) in covariance.iter_mut().zip(d.iter()) {
+            for (value, &dj) in row.iter_mut().zip(d.iter()) {
+                *value += di * dj / count;
+            }
+        }
+    }
+
+    Some(([mean[0] as f32, mean[1] as f32, mean[2] as f32], covariance))
+}
+
+fn line_point(coefficients: &[f32]) -> [f32; 3] {
+    [coefficients[0], coefficients[1], coefficients[2]]
+}
+
+fn line_direction(coefficients: &[f32]) -> [f32; 3] {
+    [coefficients[3], coefficients[4], coefficients[5]]
+}
+
+/// Distance from a point to a line through `origin` with unit `direction`
+fn distance_to_line(point: [f32; 3], origin: [f32; 3], direction: [f32; 3]) -> f32 {
+    math::magnitude(math::cross_product(sub(point, origin), direction))
+}
+
+fn within(value: f32, limits: (f32, f32)) -> bool {
+    value >= limits.0 && value <= limits.1
+}
+
+fn is_finite(v: [f32; 3]) -> bool {
+    v.iter().all(|c| c.is_finite())
+}
+
+fn add(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn scale(v: [f32; 3], s: f32) -> [f32; 3] {
+    [v[0] * s, v[1] * s, v[2] * s]
+}
+
+fn to_f64(v: [f32; 3]) -> [f64; 3] {
+    [v[0] as f64, v[1] as f64, v[2] as f64]
+}
+
+fn sub64(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn dot64(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross64(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{PointXYZ, PointXYZRGBNormal};
+    use std::f32::consts::PI;
+
+    /// Deterministic pseudo-random outliers spread over a box
+    fn outliers(count: usize) -> Vec<PointXYZ> {
+        (0..count)
+            .map(|i| {
+                let t = i as f32;
+                PointXYZ::new(
+                    (t * 0.618).fract() * 4.0 - 2.0,
+                    (t * 0.414).fract() * 4.0 - 2.0,
+                    (t * 0.732).fract() * 4.0 - 2.0,
+                )
+            })
+            .collect()
+    }
+
+    fn config(method: SacMethod) -> SacConfig {
+        SacConfig {
+            method,
+            distance_threshold: 0.01,
+            seed: Some(7),
+            ..SacConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_plane_estimators() {
+        let mut points: Vec<PointXYZ> = (0..400)
+            .map(|i| PointXYZ::new((i % 20) as f32 * 0.1, (i / 20) as f32 * 0.1, 0.25))
+            .collect();
+        points.extend(outliers(50));
+        let cloud = PointCloud::from_points(points);
+        let model = PlaneModel::new(&cloud);
+
+        for method in [
+            SacMethod::Ransac,
+            SacMethod::Msac,
+            SacMethod::Lmeds,
+            SacMethod::Prosac,
+        ] {
+            let result = sample_consensus(&model, &config(method)).unwrap();
+            assert!(result.inliers.len() >= 400, "{:?}", method);
+            assert!(
+                (result.coefficients[2].abs() - 1.0).abs() < 1e-4,
+                "{:?}",
+                method
+            );
+        }
+    }
+
+    #[test]
+    fn test_constrained_planes() {
+        // A floor (z = 0) and a wall (x = 0) with the floor much larger
+        let mut points = Vec::new();
+        for i in 0..20 {
+            for j in 0..20 {
+                points.push(PointXYZ::new(i as f32 * 0.1 + 0.1, j as f32 * 0.1, 0.0));
+            }
+        }
+        for i in 0..10 {
+            for j in 0..10 {
+                points.push(PointXYZ::new(0.0, i as f32 * 0.1, j as f32 * 0.1 + 0.1));
+            }
+        }
+        let cloud = PointCloud::from_points(points);
+
+        let wall = PlaneModel::parallel(&cloud, [0.0, 0.0, 1.0], 0.05);
+        let result = sample_consensus(&wall, &config(SacMethod::Ransac)).unwrap();
+        assert!((result.coefficients[0].abs() - 1.0).abs() < 1e-4);
+        assert_eq!(result.inliers.len(), 100);
+
+        let floor = PlaneModel::perpendicular(&cloud, [0.0, 0.0, 1.0], 0.05);
+        let result = sample_consensus(&floor, &config(SacMethod::Ransac)).unwrap();
+        assert!((result.coefficients[2].abs() - 1.0).abs() < 1e-4);
+        assert_eq!(result.inliers.len(), 400);
+    }
+
+    #[test]
+    fn test_line_model() {
+        let mut points: Vec<PointXYZ> = (0..100)
+            .map(|i| {
+                let t = i as f32 * 0.02;
+                PointXYZ::new(1.0 + t, 2.0 - t, 0.5 + 2.0 * t)
+            })
+            .collect();
+        points.extend(outliers(30));
+        let cloud = PointCloud::from_points(points);
+
+        let result = sample_consensus(&LineModel::new(&cloud), &config(SacMethod::Ransac)).unwrap();
+        assert!(result.inliers.len() >= 100);
+        let d = line_direction(&result.coefficients);
+        let expected = math::normalize([1.0, -1.0, 2.0]);
+        assert!((math::dot_product(d, expected).abs() - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_sphere_model() {
+        let center = [0.5, -0.3, 1.2];
+        let radius = 0.4;
+        let mut points = Vec::new();
+        for i in 0..20 {
+            for j in 0..20 {
+                let theta = i as f32 / 20.0 * PI;
+                let phi = j as f32 / 20.0 * 2.0 * PI;
+                points.push(PointXYZ::new(
+                    center[0] + radius * theta.sin() * phi.cos(),
+                    center[1] + radius * theta.sin() * phi.sin(),
+                    center[2] + radius * theta.cos(),
+                ));
+            }
+        }
+        points.extend(outliers(60));
+        let cloud = PointCloud::from_points(points);
+
+        let model = SphereModel::new(&cloud).with_radius_limits(0.1, 1.0);
+        let result = sample_consensus(&model, &config(SacMethod::Msac)).unwrap();
+        assert!(result.inliers.len() >= 400);
+        assert!((result.coefficients[3] - radius).abs() < 1e-3);
+        for (c, e) in result.coefficients[..3].iter().zip(center.iter()) {
+            assert!((c - e).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_cylinder_model() {
+        // Vertical pipe of radius 0.2 around x = 1, y = 0
+        let radius = 0.2;
+        let mut points = Vec::new();
+        for i in 0..36 {
+            for j in 0..10 {
+                let angle = i as f32 / 36.0 * 2.0 * PI;
+                let (s, c) = angle.sin_cos();
+                points.push(PointXYZRGBNormal::new(
+                    1.0 + radius * c,
+                    radius * s,
+                    j as f32 * 0.1,
+                    0,
+                    0,
+                    0,
+                    c,
+                    s,
+                    0.0,
+                ));
+            }
+        }
+        let cloud = PointCloud::from_points(points);
+
+        let model = CylinderModel::from_normal_cloud(&cloud).with_radius_limits(0.05, 0.5);
+        let result = sample_consensus(&model, &config(SacMethod::Ransac)).unwrap();
+        assert_eq!(result.inliers.len(), 360);
+        assert!((result.coefficients[6] - radius).abs() < 1e-4);
+        assert!((result.coefficients[5].abs() - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_circle3d_model() {
+        let radius = 0.5;
+        let mut points: Vec<PointXYZ> = (0..60)
+            .map(|i| {
+                let angle = i as f32 / 60.0 * 2.0 * PI;
+                // Circle in the plane x = 2
+                PointXYZ::new(2.0, radius * angle.cos(), 1.0 + radius * angle.sin())
+            })
+            .collect();
+        points.extend(outliers(20));
+        let cloud = PointCloud::from_points(points);
+
+        let result =
+            sample_consensus(&Circle3dModel::new(&cloud), &config(SacMethod::Ransac)).unwrap();
+        assert!(result.inliers.len() >= 60);
+        assert!((result.coefficients[0] - 2.0).abs() < 1e-4);
+        assert!((result.coefficients[3] - radius).abs() < 1e-4);
+        assert!((result.coefficients[4].abs() - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_adaptive_iteration_count() {
+        assert_eq!(adaptive_iteration_count(1.0, 3, 0.99), 1);
+        assert_eq!(adaptive_iteration_count(0.5, 3, 0.99), 35);
+        assert_eq!(adaptive_iteration_count(0.0, 3, 0.99), usize::MAX);
+    }
+
+    #[test]
+    fn test_too_few_points() {
+        let cloud = PointCloud::from_points(vec![PointXYZ::new(0.0, 0.0, 0.0)]);
+        assert!(sample_consensus(&PlaneModel::new(&cloud), &SacConfig::default()).is_err());
+    }
+}
@@ -3,10 +3,52 @@
 //! This module provides algorithms for segmenting point clouds into
 //! meaningful regions or objects.
 
-use crate::core::{Point, PointCloud};
+use crate::algorithms::feature::{
+    NeighborhoodSearch, NormalEstimate, compute_point_normal, estimate_normals_with_curvature,
+};
+use crate::algorithms::sample_consensus::{PlaneModel, SacConfig, sample_consensus};
+use crate::core::{Point, PointCloud, PointColor, PointNormal};
 use crate::error::{CloudError, Result};
-// use rayon::prelude::*;
-use std::collections::{HashMap, HashSet};
+use crate::search::KdTree;
+use crate::utils::math;
+use rayon::prelude::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Label assigned to points that do not belong to any cluster
+pub const NOISE_LABEL: i32 = -1;
+
+/// Clusters together with a per-point label vector
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ClusteringResult {
+    /// Point indices of every cluster, each sorted ascending
+    pub clusters: Vec<Vec<usize>>,
+
+    /// Cluster index of every point, or `NOISE_LABEL`
+    pub labels: Vec<i32>,
+}
+
+impl ClusteringResult {
+    /// Build a result from clusters, labelling all other points as noise
+    pub fn from_clusters(clusters: Vec<Vec<usize>>, point_count: usize) -> Self {
+        let mut labels = vec![NOISE_LABEL; point_count];
+        for (label, cluster) in clusters.iter().enumerate() {
+            for &i in cluster {
+                labels[i] = label as i32;
+            }
+        }
+        Self { clusters, labels }
+    }
+
+    /// Number of clusters found
+    pub fn num_clusters(&self) -> usize {
+        self.clusters.len()
+    }
+
+    /// Number of points labelled as noise
+    pub fn noise_count(&self) -> usize {
+        self.labels.iter().filter(|&&l| l == NOISE_LABEL).count()
+    }
+}
 
 /// Euclidean cluster extraction
 ///
@@ -17,42 +59,1037 @@ pub fn euclidean_clustering<P: Point>(
     min_cluster_size: usize,
     max_cluster_size: usize,
 ) -> Vec<Vec<usize>> {
-    let mut clusters = Vec::new();
-    let mut processed = vec![false; cloud.len()];
+    euclidean_clustering_with_labels(cloud, tolerance, min_cluster_size, max_cluster_size).clusters
+}
+
+/// Euclidean cluster extraction with per-point labels
+///
+/// Neighbors within `tolerance` are found with a KD-tree and merged in
+/// parallel. Clusters are ordered by their lowest point index; points in
+/// clusters outside the size limits, and points with non-finite
+/// coordinates, are labelled `NOISE_LABEL`.
+pub fn euclidean_clustering_with_labels<P: Point>(
+    cloud: &PointCloud<P>,
+    tolerance: f32,
+    min_cluster_size: usize,
+    max_cluster_size: usize,
+) -> ClusteringResult {
+    let tree = KdTree::build(cloud.points());
+    let components = DisjointSet::new(cloud.len());
+
+    cloud.par_iter().enumerate().for_each(|(i, point)| {
+        let position = point.position();
+        if !position.iter().all(|v| v.is_finite()) {
+            return;
+        }
+
+        for (j, _) in tree.radius_search_indices(position, tolerance) {
+            // Each pair only needs to be merged once
+            if j > i {
+                components.union(i, j);
+            }
+        }
+    });
+
+    let finite: Vec<bool> = cloud
+        .iter()
+        .map(|p| p.position().iter().all(|v| v.is_finite()))
+        .collect();
+    let clusters = components
+        .components()
+        .into_iter()
+        .filter(|c| finite[c[0]] && c.len() >= min_cluster_size && c.len() <= max_cluster_size)
+        .collect();
+
+    ClusteringResult::from_clusters(clusters, cloud.len())
+}
+
+/// Lock-free union-find used to merge neighborhoods from parallel queries
+///
+/// Roots are always linked towards the lower index, so the root of a set is
+/// its smallest member.
+struct DisjointSet {
+    parents: Vec<AtomicUsize>,
+}
+
+impl DisjointSet {
+    fn new(len: usize) -> Self {
+        Self {
+            parents: (0..len).map(AtomicUsize::new).collect(),
+        }
+    }
 
-    for (i, point) in cloud.iter().enumerate() {
-        if processed[i] {
+    /// Find the root of `x`, halving the path along the way
+    fn find(&self, mut x: usize) -> usize {
+        loop {
+            let parent = self.parents[x].load(Ordering::Acquire);
+            if parent == x {
+                return x;
+            }
+            let grandparent = self.parents[parent].load(Ordering::Acquire);
+            if parent != grandparent {
+                let _ = self.parents[x].compare_exchange(
+                    parent,
+                    grandparent,
+                    Ordering::AcqRel,
+                    Ordering::Acquire,
+                );
+            }
+            x = grandparent;
+        }
+    }
+
+    /// Merge the sets containing `a` and `b`
+    fn union(&self, a: usize, b: usize) {
+        loop {
+            let (root_a, root_b) = (self.find(a), self.find(b));
+            if root_a == root_b {
+                return;
+            }
+
+            let (high, low) = (root_a.max(root_b), root_a.min(root_b));
+            if self.parents[high]
+                .compare_exchange(high, low, Ordering::AcqRel, Ordering::Acquire)
+                .is_ok()
+            {
+                return;
+            }
+        }
+    }
+
+    /// Group all elements by set, ordered by their smallest member
+    fn components(&self) -> Vec<Vec<usize>> {
+        let roots: Vec<Option<usize>> = (0..self.parents.len())
+            .map(|i| Some(self.find(i)))
+            .collect();
+        group_by_key(&roots)
+    }
+}
+
+/// Group point indices by key, ordered by the first index of each group
+///
+/// Points with a `None` key are left out.
+fn group_by_key(keys: &[Option<usize>]) -> Vec<Vec<usize>> {
+    let mut slots = vec![usize::MAX; keys.len()];
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+
+    for (i, key) in keys.iter().enumerate() {
+        let Some(key) = *key else {
             continue;
+        };
+        if slots[key] == usize::MAX {
+            slots[key] = groups.len();
+            groups.push(Vec::new());
+        }
+        groups[slots[key]].push(i);
+    }
+
+    groups
+}
+
+/// DBSCAN density-based clustering
+///
+/// A point is a core point if at least `min_points` points (including
+/// itself) lie within `eps`. Core points within `eps` of each other form a
+/// cluster; other points join the cluster of their nearest core point within
+/// `eps` or are labelled `NOISE_LABEL`.
+pub fn dbscan_clustering<P: Point>(
+    cloud: &PointCloud<P>,
+    eps: f32,
+    min_points: usize,
+) -> Result<ClusteringResult> {
+    if eps <= 0.0 {
+        return Err(CloudError::invalid_parameter("DBSCAN eps must be positive"));
+    }
+    if min_points == 0 {
+        return Err(CloudError::invalid_parameter(
+            "DBSCAN min_points must be at least 1",
+        ));
+    }
+
+    let tree = KdTree::build(cloud.points());
+    let positions: Vec<[f32; 3]> = cloud.iter().map(|p| p.position()).collect();
+
+    let core: Vec<bool> = positions
+        .par_iter()
+        .map(|&position| {
+            position.iter().all(|v| v.is_finite())
+                && tree.radius_search_indices(position, eps).len() >= min_points
+        })
+        .collect();
+
+    let components = DisjointSet::new(cloud.len());
+    positions.par_iter().enumerate().for_each(|(i, &position)| {
+        if !core[i] {
+            return;
+        }
+        for (j, _) in tree.radius_search_indices(position, eps) {
+            if j > i && core[j] {
+                components.union(i, j);
+            }
+        }
+    });
+
+    let keys: Vec<Option<usize>> = positions
+        .par_iter()
+        .enumerate()
+        .map(|(i, &position)| {
+            if core[i] {
+                return Some(components.find(i));
+            }
+            if !position.iter().all(|v| v.is_finite()) {
+                return None;
+            }
+
+            // Border points join the cluster of their nearest core point
+            tree.radius_search_indices(position, eps)
+                .into_iter()
+                .filter(|&(j, _)| core[j])
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .map(|(j, _)| components.find(j))
+        })
+        .collect();
+
+    Ok(ClusteringResult::from_clusters(
+        group_by_key(&keys),
+        cloud.len(),
+    ))
+}
+
+/// Configuration for HDBSCAN clustering
+#[derive(Clone, Debug)]
+pub struct HdbscanConfig {
+    /// Smallest group of points that is considered a cluster
+    pub min_cluster_size: usize,
+
+    /// Neighbors (including the point itself) used for the core distance;
+    /// `None` uses `min_cluster_size`
+    pub min_samples: Option<usize>,
+
+    /// Allow the whole cloud to be returned as a single cluster
+    pub allow_single_cluster: bool,
+}
+
+impl Default for HdbscanConfig {
+    fn default() -> Self {
+        Self {
+            min_cluster_size: 5,
+            min_samples: None,
+            allow_single_cluster: false,
+        }
+    }
+}
+
+/// HDBSCAN hierarchical density-based clustering
+///
+/// Clusters are extracted from the hierarchy of DBSCAN solutions over all
+/// `eps`, so only the minimum cluster size needs to be chosen.
+pub fn hdbscan_clustering<P: Point>(
+    cloud: &PointCloud<P>,
+    min_cluster_size: usize,
+) -> Result<ClusteringResult> {
+    let config = HdbscanConfig {
+        min_cluster_size,
+        ..HdbscanConfig::default()
+    };
+    hdbscan_clustering_with_config(cloud, &config)
+}
+
+/// HDBSCAN clustering using the given configuration
+///
+/// Builds the minimum spanning tree of the mutual reachability graph with
+/// KD-tree accelerated Borůvka steps, condenses the resulting single-linkage
+/// hierarchy with `min_cluster_size` and selects the clusters with the
+/// highest stability (excess of mass). Points outside the selected clusters
+/// and points with non-finite coordinates are labelled `NOISE_LABEL`.
+pub fn hdbscan_clustering_with_config<P: Point>(
+    cloud: &PointCloud<P>,
+    config: &HdbscanConfig,
+) -> Result<ClusteringResult> {
+    if config.min_cluster_size < 2 {
+        return Err(CloudError::invalid_parameter(
+            "HDBSCAN min_cluster_size must be at least 2",
+        ));
+    }
+    let min_samples = config.min_samples.unwrap_or(config.min_cluster_size);
+    if min_samples == 0 {
+        return Err(CloudError::invalid_parameter(
+            "HDBSCAN min_samples must be at least 1",
+        ));
+    }
+
+    let finite: Vec<usize> = (0..cloud.len())
+        .filter(|&i| cloud.points()[i].position().iter().all(|v| v.is_finite()))
+        .collect();
+    if finite.len() < config.min_cluster_size {
+        return Ok(ClusteringResult::from_clusters(Vec::new(), cloud.len()));
+    }
+
+    let points: Vec<P> = finite.iter().map(|&i| cloud.points()[i].clone()).collect();
+    let edges = mutual_reachability_mst(&points, min_samples);
+    let hierarchy = single_linkage(points.len(), edges);
+    let labels = extract_hdbscan_clusters(&hierarchy, config);
+
+    let mut keys = vec![None; cloud.len()];
+    for (&index, label) in finite.iter().zip(labels) {
+        keys[index] = label;
+    }
+
+    Ok(ClusteringResult::from_clusters(
+        group_by_key(&keys),
+        cloud.len(),
+    ))
+}
+
+/// Minimum spanning tree of the mutual reachability graph
+///
+/// The mutual reachability distance of two points is the largest of their
+/// distance and both core distances. Returns `(a, b, distance)` edges.
+fn mutual_reachability_mst<P: Point>(points: &[P], min_samples: usize) -> Vec<(usize, usize, f32)> {
+    let tree = KdTree::build(points);
+    let positions: Vec<[f32; 3]> = points.iter().map(|p| p.position()).collect();
+
+    // Squared distance to the min_samples-th nearest neighbor
+    let core_squared: Vec<f32> = positions
+        .par_iter()
+        .map(|&position| {
+            tree.k_nearest_indices(position, min_samples)
+                .last()
+                .map_or(0.0, |&(_, d)| d)
+        })
+        .collect();
+
+    let mut boruvka = BoruvkaTree::build(&positions, &core_squared);
+    let components = DisjointSet::new(points.len());
+    let mut edges = Vec::with_capacity(points.len() - 1);
+
+    // Borůvka: connect every component to its closest neighbor each round
+    while edges.len() + 1 < points.len() {
+        let roots: Vec<usize> = (0..points.len()).map(|i| components.find(i)).collect();
+        boruvka.update_components(&roots);
+
+        // Lightest outgoing edge per component, ties broken by endpoints
+        let keys: Vec<Option<usize>> = roots.iter().copied().map(Some).collect();
+        let best: Vec<(f32, usize, usize)> = group_by_key(&keys)
+            .into_par_iter()
+            .filter_map(|members| {
+                let mut best = None;
+                for &i in &members {
+                    boruvka.lightest_edge(i, &positions, &core_squared, &roots, &mut best);
+                }
+                best
+            })
+            .collect();
+
+        for (weight, a, b) in best {
+            if components.find(a) != components.find(b) {
+                components.union(a, b);
+                edges.push((a, b, weight.sqrt()));
+            }
+        }
+    }
+
+    edges
+}
+
+/// Number of points below which a Borůvka tree node is not split further
+const BORUVKA_LEAF_SIZE: usize = 16;
+
+/// KD-tree used to find the lightest mutual reachability edge leaving each
+/// component in a Borůvka round
+///
+/// Besides its bounding box, every node records the smallest core distance
+/// and point index below it, and the component shared by all of its points,
+/// if any. Queries skip subtrees that lie entirely in the query's component
+/// or whose lower bound cannot beat the best edge found so far.
+struct BoruvkaTree {
+    order: Vec<usize>,
+    nodes: Vec<BoruvkaNode>,
+}
+
+struct BoruvkaNode {
+    range: std::ops::Range<usize>,
+    min: [f32; 3],
+    max: [f32; 3],
+    children: Option<(usize, usize)>,
+    min_core: f32,
+    min_index: usize,
+    component: Option<usize>,
+}
+
+impl BoruvkaTree {
+    fn build(positions: &[[f32; 3]], core_squared: &[f32]) -> Self {
+        let mut tree = Self {
+            order: (0..positions.len()).collect(),
+            nodes: Vec::new(),
+        };
+        tree.build_node(positions, core_squared, 0..positions.len());
+        tree
+    }
+
+    /// Build the node covering `range` of the order and its descendants,
+    /// returning its index. Children always follow their parent.
+    fn build_node(
+        &mut self,
+        positions: &[[f32; 3]],
+        core_squared: &[f32],
+        range: std::ops::Range<usize>,
+    ) -> usize {
+        let members = &mut self.order[range.clone()];
+        let (mut min, mut max) = ([f32::INFINITY; 3], [f32::NEG_INFINITY; 3]);
+        let (mut min_core, mut min_index) = (f32::INFINITY, usize::MAX);
+        for &i in members.iter() {
+            for axis in 0..3 {
+                min[axis] = min[axis].min(positions[i][axis]);
+                max[axis] = max[axis].max(positions[i][axis]);
+            }
+            min_core = min_core.min(core_squared[i]);
+            min_index = min_index.min(i);
         }
 
-        let mut cluster = Vec::new();
-        let mut queue = vec![i];
+        let node = self.nodes.len();
+        self.nodes.push(BoruvkaNode {
+            range: range.clone(),
+            min,
+            max,
+            children: None,
+            min_core,
+            min_index,
+            component: None,
+        });
 
-        while let Some(current_idx) = queue.pop() {
-            if processed[current_idx] {
+        if members.len() > BORUVKA_LEAF_SIZE {
+            let axis = (0..3)
+                .max_by(|&a, &b| (max[a] - min[a]).total_cmp(&(max[b] - min[b])))
+                .unwrap_or(0);
+            let median = members.len() / 2;
+            members.select_nth_unstable_by(median, |&a, &b| {
+                positions[a][axis].total_cmp(&positions[b][axis])
+            });
+
+            let split = range.start + median;
+            let left = self.build_node(positions, core_squared, range.start..split);
+            let right = self.build_node(positions, core_squared, split..range.end);
+            self.nodes[node].children = Some((left, right));
+        }
+        node
+    }
+
+    /// Record which nodes lie entirely inside a single component
+    fn update_components(&mut self, roots: &[usize]) {
+        for node in (0..self.nodes.len()).rev() {
+            let component = match self.nodes[node].children {
+                Some((left, right)) => {
+                    match (self.nodes[left].component, self.nodes[right].component) {
+                        (Some(a), Some(b)) if a == b => Some(a),
+                        _ => None,
+                    }
+                }
+                None => {
+                    let mut members = self.order[self.nodes[node].range.clone()].iter();
+                    let first = members.next().map(|&i| roots[i]);
+                    first.filter(|&root| members.all(|&i| roots[i] == root))
+                }
+            };
+            self.nodes[node].component = component;
+        }
+    }
+
+    /// Lower the best `(squared_weight, a, b)` edge with edges from point `i`
+    /// to points outside its component
+    fn lightest_edge(
+        &self,
+        i: usize,
+        positions: &[[f32; 3]],
+        core_squared: &[f32],
+        roots: &[usize],
+        best: &mut Option<(f32, usize, usize)>,
+    ) {
+        let mut stack = vec![0];
+        while let Some(node_index) = stack.pop() {
+            let node = &self.nodes[node_index];
+            if node.component == Some(roots[i]) {
                 continue;
             }
 
-            processed[current_idx] = true;
-            cluster.push(current_idx);
+            // No edge into this node can be lighter than this bound
+            let weight = self
+                .box_distance_squared(node, positions[i])
+                .max(core_squared[i])
+                .max(node.min_core);
+            let key = if i < node.min_index {
+                (i, node.min_index)
+            } else {
+                (node.min_index, 0)
+            };
+            if best.is_some_and(|best| (weight, key.0, key.1) >= best) {
+                continue;
+            }
 
-            if let Some(current_point) = cloud.get(current_idx) {
-                // Find neighbors within tolerance
-                for (j, neighbor) in cloud.iter().enumerate() {
-                    if !processed[j] && current_point.distance_to(neighbor) <= tolerance {
-                        queue.push(j);
+            match node.children {
+                Some((left, right)) => {
+                    // Visit the closer child first
+                    let left_distance = self.box_distance_squared(&self.nodes[left], positions[i]);
+                    let right_distance =
+                        self.box_distance_squared(&self.nodes[right], positions[i]);
+                    if left_distance < right_distance {
+                        stack.extend([right, left]);
+                    } else {
+                        stack.extend([left, right]);
+                    }
+                }
+                None => {
+                    for &j in &self.order[node.range.clone()] {
+                        if roots[j] == roots[i] {
+                            continue;
+                        }
+                        let offset = [
+                            positions[j][0] - positions[i][0],
+                            positions[j][1] - positions[i][1],
+                            positions[j][2] - positions[i][2],
+                        ];
+                        let weight = math::dot_product(offset, offset)
+                            .max(core_squared[i])
+                            .max(core_squared[j]);
+                        let edge = (weight, i.min(j), i.max(j));
+                        if best.is_none_or(|best| edge < best) {
+                            *best = Some(edge);
+                        }
                     }
                 }
             }
         }
+    }
+
+    /// Squared distance from a position to the bounding box of a node
+    fn box_distance_squared(&self, node: &BoruvkaNode, position: [f32; 3]) -> f32 {
+        (0..3)
+            .map(|axis| {
+                let d = (node.min[axis] - position[axis])
+                    .max(position[axis] - node.max[axis])
+                    .max(0.0);
+                d * d
+            })
+            .sum()
+    }
+}
+
+/// Binary merge tree built from minimum spanning tree edges
+///
+/// Leaves are the points `0..n`; merge `k` creates node `n + k`.
+struct SingleLinkage {
+    point_count: usize,
+    children: Vec<(usize, usize)>,
+    distances: Vec<f32>,
+    sizes: Vec<usize>,
+}
+
+impl SingleLinkage {
+    fn size(&self, node: usize) -> usize {
+        if node < self.point_count {
+            1
+        } else {
+            self.sizes[node - self.point_count]
+        }
+    }
+
+    /// Collect the points below a node
+    fn leaves(&self, node: usize) -> Vec<usize> {
+        let mut leaves = Vec::new();
+        let mut stack = vec![node];
+        while let Some(node) = stack.pop() {
+            if node < self.point_count {
+                leaves.push(node);
+            } else {
+                let (left, right) = self.children[node - self.point_count];
+                stack.push(left);
+                stack.push(right);
+            }
+        }
+        leaves
+    }
+}
+
+/// Build the single-linkage hierarchy by merging edges in order of distance
+fn single_linkage(point_count: usize, mut edges: Vec<(usize, usize, f32)>) -> SingleLinkage {
+    edges.sort_by(|a, b| a.2.total_cmp(&b.2));
+
+    let components = DisjointSet::new(point_count);
+    let mut node_of_root: Vec<usize> = (0..point_count).collect();
+    let mut hierarchy = SingleLinkage {
+        point_count,
+        children: Vec::with_capacity(edges.len()),
+        distances: Vec::with_capacity(edges.len()),
+        sizes: Vec::with_capacity(edges.len()),
+    };
+
+    for (a, b, distance) in edges {
+        let (left, right) = (
+            node_of_root[components.find(a)],
+            node_of_root[components.find(b)],
+        );
+        let size = hierarchy.size(left) + hierarchy.size(right);
+
+        components.union(a, b);
+        node_of_root[components.find(a)] = point_count + hierarchy.children.len();
+
+        hierarchy.children.push((left, right));
+        hierarchy.distances.push(distance);
+        hierarchy.sizes.push(size);
+    }
+
+    hierarchy
+}
+
+/// Cluster of the condensed HDBSCAN tree
+struct CondensedCluster {
+    parent: Option<usize>,
+    birth_lambda: f64,
+    stability: f64,
+    children: Vec<usize>,
+}
+
+/// Condense the hierarchy and label every point with its selected cluster
+fn extract_hdbscan_clusters(
+    hierarchy: &SingleLinkage,
+    config: &HdbscanConfig,
+) -> Vec<Option<usize>> {
+    let point_count = hierarchy.point_count;
+    let min_size = config.min_cluster_size;
+
+    // Duplicate points merge at distance zero; cap the density instead
+    let lambda = |distance: f32| 1.0 / (distance as f64).max(1e-12);
+
+    let mut clusters = vec![CondensedCluster {
+        parent: None,
+        birth_lambda: 0.0,
+        stability: 0.0,
+        children: Vec::new(),
+    }];
+    let mut point_cluster = vec![0; point_count];
+
+    // Walk the hierarchy top-down, following clusters as they shrink or split
+    let mut stack = vec![(point_count + hierarchy.children.len() - 1, 0)];
+    while let Some((node, cluster)) = stack.pop() {
+        let merge = node - point_count;
+        let node_lambda = lambda(hierarchy.distances[merge]);
+        let birth = clusters[cluster].birth_lambda;
+        let (left, right) = hierarchy.children[merge];
+
+        let mut fall_out = |child: usize, clusters: &mut Vec<CondensedCluster>| {
+            for point in hierarchy.leaves(child) {
+                point_cluster[point] = cluster;
+                clusters[cluster].stability += node_lambda - birth;
+            }
+        };
+
+        match (
+            hierarchy.size(left) >= min_size,
+            hierarchy.size(right) >= min_size,
+        ) {
+            (true, true) => {
+                for child in [left, right] {
+                    let id = clusters.len();
+                    clusters[cluster].stability +=
+                        hierarchy.size(child) as f64 * (node_lambda - birth);
+                    clusters[cluster].children.push(id);
+                    clusters.push(CondensedCluster {
+                        parent: Some(cluster),
+                        birth_lambda: node_lambda,
+                        stability: 0.0,
+                        children: Vec::new(),
+                    });
+                    stack.push((child, id));
+                }
+            }
+            (true, false) => {
+                fall_out(right, &mut clusters);
+                stack.push((left, cluster));
+            }
+            (false, true) => {
+                fall_out(left, &mut clusters);
+                stack.push((right, cluster));
+            }
+            (false, false) => {
+                fall_out(left, &mut clusters);
+                fall_out(right, &mut clusters);
+            }
+        }
+    }
+
+    // Excess of mass: keep a cluster unless its children are more stable.
+    // Children always have higher ids than their parent.
+    let mut selected = vec![false; clusters.len()];
+    let mut score = vec![0.0f64; clusters.len()];
+    let first = if config.allow_single_cluster { 0 } else { 1 };
+    for id in (first..clusters.len()).rev() {
+        let children_score: f64 = clusters[id].children.iter().map(|&c| score[c]).sum();
+        if clusters[id].children.is_empty() || clusters[id].stability >= children_score {
+            selected[id] = true;
+            score[id] = clusters[id].stability;
+
+            let mut descendants = clusters[id].children.clone();
+            while let Some(descendant) = descendants.pop() {
+                selected[descendant] = false;
+                descendants.extend_from_slice(&clusters[descendant].children);
+            }
+        } else {
+            score[id] = children_score;
+        }
+    }
+
+    let mut selected_ancestor: Vec<Option<usize>> = vec![None; clusters.len()];
+    for id in 0..clusters.len() {
+        selected_ancestor[id] = if selected[id] {
+            Some(id)
+        } else {
+            clusters[id].parent.and_then(|p| selected_ancestor[p])
+        };
+    }
+
+    point_cluster
+        .into_iter()
+        .map(|cluster| selected_ancestor[cluster])
+        .collect()
+}
+
+/// Configuration for normal-based region growing
+#[derive(Clone, Debug)]
+pub struct RegionGrowingConfig {
+    /// Neighborhood used to grow regions
+    pub search: NeighborhoodSearch,
+
+    /// Maximum angle in radians between neighboring normals in one region
+    pub smoothness_threshold: f32,
+
+    /// Points with a curvature below this value keep growing their region
+    pub curvature_threshold: f32,
+
+    /// Regions with fewer points are labelled as noise
+    pub min_cluster_size: usize,
+
+    /// Regions with more points are labelled as noise
+    pub max_cluster_size: usize,
+}
+
+impl Default for RegionGrowingConfig {
+    fn default() -> Self {
+        Self {
+            search: NeighborhoodSearch::KNearest(30),
+            smoothness_threshold: 3.0f32.to_radians(),
+            curvature_threshold: 1.0,
+            min_cluster_size: 1,
+            max_cluster_size: usize::MAX,
+        }
+    }
+}
+
+/// Region growing segmentation based on normals and curvature
+///
+/// Regions are seeded from the point with the lowest curvature that is not
+/// yet assigned. A neighbor joins the region if the angle between its normal
+/// and the normal of the point it was reached from is below
+/// `smoothness_threshold`; it continues the growth only if its curvature is
+/// below `curvature_threshold`. Points with invalid normals are labelled
+/// `NOISE_LABEL`.
+pub fn region_growing_segmentation<P: Point>(
+    cloud: &PointCloud<P>,
+    normals: &[NormalEstimate],
+    config: &RegionGrowingConfig,
+) -> Result<ClusteringResult> {
+    if normals.len() != cloud.len() {
+        return Err(CloudError::invalid_parameter(
+            "Normals must match the number of points",
+        ));
+    }
+    if config.smoothness_threshold <= 0.0 {
+        return Err(CloudError::invalid_parameter(
+            "Smoothness threshold must be positive",
+        ));
+    }
+
+    let tree = KdTree::build(cloud.points());
+    let positions: Vec<[f32; 3]> = cloud.iter().map(|p| p.position()).collect();
+    let cos_threshold = config.smoothness_threshold.cos();
+
+    let valid: Vec<bool> = normals
+        .iter()
+        .zip(positions.iter())
+        .map(|(n, p)| n.is_valid() && n.curvature.is_finite() && p.iter().all(|v| v.is_finite()))
+        .collect();
+
+    // Seeds in order of increasing curvature
+    let mut order: Vec<usize> = (0..cloud.len()).filter(|&i| valid[i]).collect();
+    order.par_sort_by(|&a, &b| normals[a].curvature.total_cmp(&normals[b].curvature));
+
+    let mut region_of: Vec<Option<usize>> = vec![None; cloud.len()];
+    for &seed in &order {
+        if region_of[seed].is_some() {
+            continue;
+        }
+        region_of[seed] = Some(seed);
 
-        // Check cluster size constraints
-        if cluster.len() >= min_cluster_size && cluster.len() <= max_cluster_size {
-            clusters.push(cluster);
+        let mut queue = vec![seed];
+        while let Some(current) = queue.pop() {
+            for j in neighbor_indices(&tree, positions[current], config.search) {
+                if region_of[j].is_some() || !valid[j] {
+                    continue;
+                }
+
+                let cos_angle = math::dot_product(normals[current].normal, normals[j].normal).abs();
+                if cos_angle < cos_threshold {
+                    continue;
+                }
+
+                region_of[j] = Some(seed);
+                if normals[j].curvature < config.curvature_threshold {
+                    queue.push(j);
+                }
+            }
         }
     }
 
-    clusters
+    let clusters = group_by_key(&region_of)
+        .into_iter()
+        .filter(|c| c.len() >= config.min_cluster_size && c.len() <= config.max_cluster_size)
+        .collect();
+
+    Ok(ClusteringResult::from_clusters(clusters, cloud.len()))
+}
+
+/// Region growing segmentation using the normals stored in the cloud
+///
+/// Curvature is estimated from the neighborhoods given by `config.search`.
+pub fn region_growing_from_normal_cloud<P: PointNormal>(
+    cloud: &PointCloud<P>,
+    config: &RegionGrowingConfig,
+) -> Result<ClusteringResult> {
+    let estimates: Vec<NormalEstimate> = estimate_normals_with_curvature(cloud, config.search)?
+        .into_iter()
+        .zip(cloud.iter())
+        .map(|(estimate, point)| NormalEstimate {
+            normal: math::normalize(point.normal()),
+            curvature: estimate.curvature,
+        })
+        .collect();
+
+    region_growing_segmentation(cloud, &estimates, config)
+}
+
+/// Indices of the neighbors of a position
+fn neighbor_indices<P: Point>(
+    tree: &KdTree<P>,
+    position: [f32; 3],
+    search: NeighborhoodSearch,
+) -> Vec<usize> {
+    let neighbors = match search {
+        NeighborhoodSearch::Radius(radius) => tree.radius_search_indices(position, radius),
+        NeighborhoodSearch::KNearest(k) => tree.k_nearest_indices(position, k),
+    };
+    neighbors.into_iter().map(|(i, _)| i).collect()
+}
+
+/// Configuration for color-based region growing
+#[derive(Clone, Debug)]
+pub struct ColorRegionGrowingConfig {
+    /// Radius within which points are considered neighbors
+    pub distance_threshold: f32,
+
+    /// Maximum RGB distance between neighboring points in one region
+    pub point_color_threshold: f32,
+
+    /// Adjacent regions whose mean colors are closer than this are merged
+    pub region_color_threshold: f32,
+
+    /// Smaller regions are merged into their most similar neighbor
+    pub min_cluster_size: usize,
+
+    /// Larger regions are discarded
+    pub max_cluster_size: usize,
+}
+
+impl Default for ColorRegionGrowingConfig {
+    fn default() -> Self {
+        Self {
+            distance_threshold: 0.05,
+            point_color_threshold: 6.0,
+            region_color_threshold: 5.0,
+            min_cluster_size: 1,
+            max_cluster_size: usize::MAX,
+        }
+    }
+}
+
+/// Color-based region growing segmentation
+///
+/// Regions grow over neighbors within `distance_threshold` whose color is
+/// within `point_color_threshold` of the point they were reached from.
+/// Adjacent regions with similar mean colors are then merged, and regions
+/// smaller than `min_cluster_size` are merged into the adjacent region with
+/// the closest mean color. Small regions without neighbors are discarded.
+/// Clusters are returned in the same format as [`euclidean_clustering`].
+pub fn color_region_growing_segmentation<P: PointColor>(
+    cloud: &PointCloud<P>,
+    config: &ColorRegionGrowingConfig,
+) -> Result<Vec<Vec<usize>>> {
+    if config.distance_threshold <= 0.0 {
+        return Err(CloudError::invalid_parameter(
+            "Distance threshold must be positive",
+        ));
+    }
+
+    let colors: Vec<[f32; 3]> = cloud
+        .iter()
+        .map(|p| p.rgb_color().map(|c| c as f32))
+        .collect();
+    let positions: Vec<[f32; 3]> = cloud.iter().map(|p| p.position()).collect();
+    let tree = KdTree::build(cloud.points());
+
+    // Grow regions of similar neighboring colors
+    let mut region_of: Vec<Option<usize>> = vec![None; cloud.len()];
+    let mut region_count = 0;
+    for seed in 0..cloud.len() {
+        if region_of[seed].is_some() || !positions[seed].iter().all(|v| v.is_finite()) {
+            continue;
+        }
+        region_of[seed] = Some(region_count);
+
+        let mut queue = vec![seed];
+        while let Some(current) = queue.pop() {
+            for (j, _) in tree.radius_search_indices(positions[current], config.distance_threshold)
+            {
+                if region_of[j].is_none()
+                    && color_distance(colors[current], colors[j]) < config.point_color_threshold
+                {
+                    region_of[j] = Some(region_count);
+                    queue.push(j);
+                }
+            }
+        }
+        region_count += 1;
+    }
+
+    // Pairs of regions that touch each other
+    let regions = &region_of;
+    let mut adjacency: Vec<(usize, usize)> = positions
+        .par_iter()
+        .enumerate()
+        .flat_map_iter(|(i, &position)| {
+            let region = region_of[i];
+            let neighbors = match region {
+                Some(_) => tree.radius_search_indices(position, config.distance_threshold),
+                None => Vec::new(),
+            };
+            neighbors.into_iter().filter_map(move |(j, _)| {
+                let (a, b) = (region?, regions[j]?);
+                (a < b).then_some((a, b))
+            })
+        })
+        .collect();
+    adjacency.par_sort_unstable();
+    adjacency.dedup();
+
+    let mut region_sizes = vec![0usize; region_count];
+    let mut region_sums = vec![[0.0f64; 3]; region_count];
+    for (region, color) in region_of.iter().zip(colors.iter()) {
+        if let Some(region) = *region {
+            region_sizes[region] += 1;
+            for (sum, &c) in region_sums[region].iter_mut().zip(color.iter()) {
+                *sum += c as f64;
+            }
+        }
+    }
+
+    // Merge adjacent regions with similar mean colors
+    let groups = DisjointSet::new(region_count);
+    let mean = |sum: [f64; 3], size: usize| {
+        [
+            (sum[0] / size as f64) as f32,
+            (sum[1] / size as f64) as f32,
+            (sum[2] / size as f64) as f32,
+        ]
+    };
+    for &(a, b) in &adjacency {
+        let (mean_a, mean_b) = (
+            mean(region_sums[a], region_sizes[a]),
+            mean(region_sums[b], region_sizes[b]),
+        );
+        if color_distance(mean_a, mean_b) < config.region_color_threshold {
+            groups.union(a, b);
+        }
+    }
+
+    // Merge small groups into their most similar neighbor until none can grow
+    loop {
+        let mut sizes = vec![0usize; region_count];
+        let mut sums = vec![[0.0f64; 3]; region_count];
+        for region in 0..region_count {
+            let root = groups.find(region);
+            sizes[root] += region_sizes[region];
+            for c in 0..3 {
+                sums[root][c] += region_sums[region][c];
+            }
+        }
+
+        let mut neighbors: Vec<Vec<usize>> = vec![Vec::new(); region_count];
+        for &(a, b) in &adjacency {
+            let (root_a, root_b) = (groups.find(a), groups.find(b));
+            if root_a != root_b {
+                neighbors[root_a].push(root_b);
+                neighbors[root_b].push(root_a);
+            }
+        }
+
+        let mut small: Vec<usize> = (0..region_count)
+            .filter(|&g| {
+                groups.find(g) == g
+                    && sizes[g] < config.min_cluster_size
+                    && !neighbors[g].is_empty()
+            })
+            .collect();
+        if small.is_empty() {
+            break;
+        }
+        small.sort_by_key(|&g| (sizes[g], g));
+
+        let mut merged = vec![false; region_count];
+        for g in small {
+            if merged[g] {
+                continue;
+            }
+            let own = mean(sums[g], sizes[g]);
+            let target = neighbors[g]
+                .iter()
+                .copied()
+                .filter(|&n| !merged[n])
+                .min_by(|&a, &b| {
+                    let da = color_distance(own, mean(sums[a], sizes[a]));
+                    let db = color_distance(own, mean(sums[b], sizes[b]));
+                    da.total_cmp(&db).then(a.cmp(&b))
+                });
+            if let Some(target) = target {
+                groups.union(g, target);
+                merged[g] = true;
+                merged[target] = true;
+            }
+        }
+    }
+
+    let keys: Vec<Option<usize>> = region_of
+        .iter()
+        .map(|region| region.map(|r| groups.find(r)))
+        .collect();
+
+    Ok(group_by_key(&keys)
+        .into_iter()
+        .filter(|c| c.len() >= config.min_cluster_size && c.len() <= config.max_cluster_size)
+        .collect())
+}
+
+/// Euclidean distance between two RGB colors
+fn color_distance(a: [f32; 3], b: [f32; 3]) -> f32 {
+    let d = [a[0] - b[0], a[1] - b[1], a[2] - b[2]];
+    math::magnitude(d)
 }
 
 /// RANSAC plane segmentation
@@ -62,6 +1099,25 @@ pub fn ransac_plane_segmentation<P: Point>(
     cloud: &PointCloud<P>,
     distance_threshold: f32,
     max_iterations: usize,
+) -> Result<(Vec<usize>, [f32; 4])> {
+    let config = SacConfig {
+        distance_threshold,
+        max_iterations,
+        ..SacConfig::default()
+    };
+    sac_plane_segmentation(cloud, &config)
+}
+
+/// Plane segmentation with a configurable sample consensus estimator
+///
+/// Hypotheses are planes through 3 randomly sampled points, scored with the
+/// estimator selected by `config.method` (RANSAC, MSAC, LMedS or PROSAC).
+/// The number of iterations adapts to the best inlier ratio found so far so
+/// that an outlier-free sample is drawn with probability `confidence`. The
+/// winning plane is then refit to its inliers with least squares.
+pub fn sac_plane_segmentation<P: Point>(
+    cloud: &PointCloud<P>,
+    config: &SacConfig,
 ) -> Result<(Vec<usize>, [f32; 4])> {
     if cloud.len() < 3 {
         return Err(CloudError::algorithm_error(
@@ -69,87 +1125,275 @@ pub fn ransac_plane_segmentation<P: Point>(
         ));
     }
 
-    let mut best_inliers = Vec::new();
-    let mut best_plane = [0.0, 0.0, 1.0, 0.0]; // Default plane equation: z = 0
-    let mut best_inlier_count = 0;
-
-    for _ in 0..max_iterations {
-        // Randomly sample 3 points
-        let indices = sample_three_points(cloud.len());
-        if let Some((p1, p2, p3)) = get_three_points(cloud, &indices) {
-            // Fit plane to these 3 points
-            if let Some(plane) = fit_plane_to_points(p1, p2, p3) {
-                // Count inliers
-                let inliers: Vec<usize> = (0..cloud.len())
-                    .filter(|&i| {
-                        if let Some(point) = cloud.get(i) {
-                            distance_to_plane(point.position(), &plane) <= distance_threshold
-                        } else {
-                            false
-                        }
-                    })
-                    .collect();
+    let result = sample_consensus(&PlaneModel::new(cloud), config)?;
+    let c = &result.coefficients;
+    Ok((result.inliers, [c[0], c[1], c[2], c[3]]))
+}
 
-                if inliers.len() > best_inlier_count {
-                    best_inlier_count = inliers.len();
-                    best_inliers = inliers;
-                    best_plane = plane;
-                }
-            }
+/// Configuration for organized multi-plane segmentation
+#[derive(Clone, Debug, PartialEq)]
+pub struct OrganizedPlaneConfig {
+    /// Maximum angle in radians between the normals of neighboring pixels
+    pub angular_threshold: f32,
+
+    /// Maximum distance of a pixel from the tangent plane of its neighbor
+    pub distance_threshold: f32,
+
+    /// Regions with fewer points are discarded
+    pub min_inliers: usize,
+
+    /// Regions whose fitted plane has a larger curvature are discarded
+    pub max_curvature: f32,
+}
+
+impl Default for OrganizedPlaneConfig {
+    fn default() -> Self {
+        Self {
+            angular_threshold: 3.0f32.to_radians(),
+            distance_threshold: 0.02,
+            min_inliers: 1000,
+            max_curvature: 0.001,
         }
     }
-
-    Ok((best_inliers, best_plane))
 }
 
-/// Sample three random point indices
-fn sample_three_points(cloud_size: usize) -> [usize; 3] {
-    // Simple random sampling - in practice, you'd use a proper RNG
-    [0, cloud_size / 3, (2 * cloud_size) / 3]
+/// Planar region found by organized multi-plane segmentation
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlanarRegion {
+    /// Plane `[a, b, c, d]` with `ax + by + cz + d = 0` and a unit normal
+    /// facing the sensor origin
+    pub coefficients: [f32; 4],
+
+    /// Indices of the points of the region, sorted ascending
+    pub inliers: Vec<usize>,
+
+    /// Mean position of the inliers
+    pub centroid: [f32; 3],
+
+    /// Indices of the outer contour of the region, in clockwise image order
+    pub boundary: Vec<usize>,
+
+    /// Surface variation of the fitted plane
+    pub curvature: f32,
 }
 
-/// Get three points from the cloud by indices
-fn get_three_points<P: Point>(
+/// Organized multi-plane segmentation
+///
+/// Neighboring pixels (4-connected) of an organized cloud are joined if their
+/// normals differ by less than `angular_threshold` and each lies within
+/// `distance_threshold` of the other's tangent plane. Every connected region
+/// with at least `min_inliers` points is fitted with a least-squares plane
+/// and kept if its curvature does not exceed `max_curvature`. Pixels with
+/// invalid points or normals belong to no region.
+///
+/// Regions are returned largest first. Normals are typically computed with
+/// [`estimate_normals_integral`](crate::algorithms::feature::estimate_normals_integral).
+///
+/// # Example
+/// ```rust,no_run
+/// use ferrum_cloud::prelude::*;
+///
+/// let cloud: PointCloud<PointXYZ> = io::load("frame.pcd")?;
+/// let normals = cloud.estimate_normals_integral_with_config(&IntegralNormalConfig::default())?;
+/// let planes = cloud.organized_planes(&normals, &OrganizedPlaneConfig::default())?;
+/// for plane in &planes {
+///     println!("{:?} with {} points", plane.coefficients, plane.inliers.len());
+/// }
+/// # Ok::<(), CloudError>(())
+/// ```
+pub fn organized_multi_plane_segmentation<P: Point>(
     cloud: &PointCloud<P>,
-    indices: &[usize; 3],
-) -> Option<([f32; 3], [f32; 3], [f32; 3])> {
-    let p1 = cloud.get(indices[0])?.position();
-    let p2 = cloud.get(indices[1])?.position();
-    let p3 = cloud.get(indices[2])?.position();
-    Some((p1, p2, p3))
-}
-
-/// Fit a plane to three points
-/// Returns plane equation coefficients [a, b, c, d] where ax + by + cz + d = 0
-fn fit_plane_to_points(p1: [f32; 3], p2: [f32; 3], p3: [f32; 3]) -> Option<[f32; 4]> {
-    // Calculate two vectors in the plane
-    let v1 = [p2[0] - p1[0], p2[1] - p1[1], p2[2] - p1[2]];
-    let v2 = [p3[0] - p1[0], p3[1] - p1[1], p3[2] - p1[2]];
-
-    // Calculate normal vector (cross product)
-    let normal = [
-        v1[1] * v2[2] - v1[2] * v2[1],
-        v1[2] * v2[0] - v1[0] * v2[2],
-        v1[0] * v2[1] - v1[1] * v2[0],
-    ];
+    normals: &[NormalEstimate],
+    config: &OrganizedPlaneConfig,
+) -> Result<Vec<PlanarRegion>> {
+    let metadata = cloud.metadata();
+    if !metadata.is_organized
+        || cloud.image_size() != (metadata.width as usize, metadata.height as usize)
+    {
+        return Err(CloudError::invalid_parameter(
+            "Organized plane segmentation requires an organized point cloud",
+        ));
+    }
+    if normals.len() != cloud.len() {
+        return Err(CloudError::invalid_parameter(
+            "Normals must match the number of points",
+        ));
+    }
+    if config.angular_threshold <= 0.0 || config.distance_threshold <= 0.0 {
+        return Err(CloudError::invalid_parameter(
+            "Angular and distance thresholds must be positive",
+        ));
+    }
 
-    // Normalize the normal vector
-    let length = (normal[0] * normal[0] + normal[1] * normal[1] + normal[2] * normal[2]).sqrt();
-    if length < 1e-6 {
-        return None; // Degenerate case
+    let (width, height) = cloud.image_size();
+    let positions: Vec<[f32; 3]> = cloud.iter().map(|p| p.position()).collect();
+    let valid: Vec<bool> = positions
+        .iter()
+        .zip(normals.iter())
+        .map(|(p, n)| n.is_valid() && p.iter().all(|v| v.is_finite()))
+        .collect();
+
+    let cos_threshold = config.angular_threshold.cos();
+    let similar = |a: usize, b: usize| {
+        let (na, nb) = (normals[a].normal, normals[b].normal);
+        let offset = [
+            positions[b][0] - positions[a][0],
+            positions[b][1] - positions[a][1],
+            positions[b][2] - positions[a][2],
+        ];
+        math::dot_product(na, nb) >= cos_threshold
+            && math::dot_product(na, offset).abs() <= config.distance_threshold
+            && math::dot_product(nb, offset).abs() <= config.distance_threshold
+    };
+
+    // Connected components over the pixel grid
+    let mut labels: Vec<Option<usize>> = vec![None; cloud.len()];
+    let mut components = Vec::new();
+    for seed in 0..cloud.len() {
+        if !valid[seed] || labels[seed].is_some() {
+            continue;
+        }
+        let label = components.len();
+        labels[seed] = Some(label);
+        let mut members = vec![seed];
+        let mut queue = vec![seed];
+        while let Some(current) = queue.pop() {
+            let (row, column) = (current / width, current % width);
+            let neighbors = [
+                (column > 0).then(|| current - 1),
+                (column + 1 < width).then(|| current + 1),
+                (row > 0).then(|| current - width),
+                (row + 1 < height).then(|| current + width),
+            ];
+            for neighbor in neighbors.into_iter().flatten() {
+                if valid[neighbor] && labels[neighbor].is_none() && similar(current, neighbor) {
+                    labels[neighbor] = Some(label);
+                    members.push(neighbor);
+                    queue.push(neighbor);
+                }
+            }
+        }
+        components.push(members);
     }
 
-    let a = normal[0] / length;
-    let b = normal[1] / length;
-    let c = normal[2] / length;
-    let d = -(a * p1[0] + b * p1[1] + c * p1[2]);
+    let viewpoint = cloud.metadata().sensor_origin;
+    let mut regions: Vec<PlanarRegion> = components
+        .into_par_iter()
+        .enumerate()
+        .filter(|(_, members)| members.len() >= config.min_inliers.max(3))
+        .filter_map(|(label, mut inliers)| {
+            inliers.sort_unstable();
+            let region: Vec<[f32; 3]> = inliers.iter().map(|&i| positions[i]).collect();
+            let (mut normal, curvature) = compute_point_normal(&region)?;
+            if curvature > config.max_curvature {
+                return None;
+            }
+
+            let count = region.len() as f64;
+            let mut sum = [0.0f64; 3];
+            for p in &region {
+                for (s, &v) in sum.iter_mut().zip(p.iter()) {
+                    *s += v as f64;
+                }
+            }
+            let centroid = sum.map(|s| (s / count) as f32);
+            let to_viewpoint = [
+                viewpoint[0] - centroid[0],
+                viewpoint[1] - centroid[1],
+                viewpoint[2] - centroid[2],
+            ];
+            if math::dot_product(normal, to_viewpoint) < 0.0 {
+                normal = normal.map(|v| -v);
+            }
+            let d = -math::dot_product(normal, centroid);
+
+            let boundary = trace_contour(&labels, label, inliers[0], width, height);
+            Some(PlanarRegion {
+                coefficients: [normal[0], normal[1], normal[2], d],
+                inliers,
+                centroid,
+                boundary,
+                curvature,
+            })
+        })
+        .collect();
 
-    Some([a, b, c, d])
+    regions.sort_by(|a, b| {
+        b.inliers
+            .len()
+            .cmp(&a.inliers.len())
+            .then(a.inliers[0].cmp(&b.inliers[0]))
+    });
+    Ok(regions)
 }
 
-/// Calculate distance from a point to a plane
-fn distance_to_plane(point: [f32; 3], plane: &[f32; 4]) -> f32 {
-    (plane[0] * point[0] + plane[1] * point[1] + plane[2] * point[2] + plane[3]).abs()
+/// Trace the outer contour of a labelled region with Moore-neighbor tracing
+///
+/// `start` must be the first pixel of the region in row-major order. The
+/// contour is returned in clockwise order (with rows growing downwards),
+/// starting at `start`.
+fn trace_contour(
+    labels: &[Option<usize>],
+    label: usize,
+    start: usize,
+    width: usize,
+    height: usize,
+) -> Vec<usize> {
+    // Clockwise from west
+    const DIRECTIONS: [(isize, isize); 8] = [
+        (0, -1),
+        (-1, -1),
+        (-1, 0),
+        (-1, 1),
+        (0, 1),
+        (1, 1),
+        (1, 0),
+        (1, -1),
+    ];
+    let step = |pixel: usize, direction: usize| {
+        let (dr, dc) = DIRECTIONS[direction];
+        let row = (pixel / width).checked_add_signed(dr)?;
+        let column = (pixel % width).checked_add_signed(dc)?;
+        (row < height && column < width).then_some(row * width + column)
+    };
+    let inside = |pixel: Option<usize>| pixel.is_some_and(|p| labels[p] == Some(label));
+
+    let mut contour = vec![start];
+    // The west neighbor of the first pixel is outside the region
+    let (mut current, mut backtrack) = (start, 0);
+    let mut first_move = None;
+    for _ in 0..4 * labels.len() + 8 {
+        let found = (1..=8)
+            .map(|k| (backtrack + k) % 8)
+            .find(|&direction| inside(step(current, direction)));
+        let Some(direction) = found else {
+            // Isolated pixel
+            break;
+        };
+        let next = step(current, direction).unwrap();
+
+        if first_move.is_none() {
+            first_move = Some(next);
+        } else if current == start && first_move == Some(next) {
+            break;
+        }
+
+        // The previously checked neighbor is outside; find it seen from `next`
+        let (dr, dc) = DIRECTIONS[(direction + 7) % 8];
+        let (mr, mc) = DIRECTIONS[direction];
+        let outside = (dr - mr, dc - mc);
+        backtrack = DIRECTIONS
+            .iter()
+            .position(|&offset| offset == outside)
+            .unwrap_or(0);
+
+        current = next;
+        if current == start {
+            continue;
+        }
+        contour.push(current);
+    }
+    contour
 }
 
 /// Extension trait for adding segmentation methods to PointCloud
@@ -162,12 +1406,60 @@ pub trait SegmentationExt<P: Point> {
         max_size: usize,
     ) -> Vec<Vec<usize>>;
 
+    /// Perform Euclidean clustering and return per-point labels
+    fn euclidean_cluster_with_labels(
+        &self,
+        tolerance: f32,
+        min_size: usize,
+        max_size: usize,
+    ) -> ClusteringResult;
+
+    /// Perform DBSCAN clustering
+    fn dbscan(&self, eps: f32, min_points: usize) -> Result<ClusteringResult>;
+
+    /// Perform HDBSCAN clustering
+    fn hdbscan(&self, min_cluster_size: usize) -> Result<ClusteringResult>;
+
+    /// Perform HDBSCAN clustering using the given configuration
+    fn hdbscan_with_config(&self, config: &HdbscanConfig) -> Result<ClusteringResult>;
+
+    /// Perform region growing segmentation with precomputed normals
+    fn region_growing(
+        &self,
+        normals: &[NormalEstimate],
+        config: &RegionGrowingConfig,
+    ) -> Result<ClusteringResult>;
+
+    /// Perform region growing segmentation using the cloud's own normals
+    fn region_growing_with_cloud_normals(
+        &self,
+        config: &RegionGrowingConfig,
+    ) -> Result<ClusteringResult>
+    where
+        P: PointNormal;
+
+    /// Perform color-based region growing segmentation
+    fn color_region_growing(&self, config: &ColorRegionGrowingConfig) -> Result<Vec<Vec<usize>>>
+    where
+        P: PointColor;
+
     /// Perform RANSAC plane segmentation
     fn ransac_plane(
         &self,
         distance_threshold: f32,
         max_iterations: usize,
     ) -> Result<(Vec<usize>, [f32; 4])>;
+
+    /// Perform plane segmentation with the estimator selected in the
+    /// sample consensus configuration
+    fn sac_plane(&self, config: &SacConfig) -> Result<(Vec<usize>, [f32; 4])>;
+
+    /// Extract all planar regions of an organized cloud
+    fn organized_planes(
+        &self,
+        normals: &[NormalEstimate],
+        config: &OrganizedPlaneConfig,
+    ) -> Result<Vec<PlanarRegion>>;
 }
 
 impl<P: Point> SegmentationExt<P> for PointCloud<P> {
@@ -180,6 +1472,52 @@ impl<P: Point> SegmentationExt<P> for PointCloud<P> {
         euclidean_clustering(self, tolerance, min_size, max_size)
     }
 
+    fn euclidean_cluster_with_labels(
+        &self,
+        tolerance: f32,
+        min_size: usize,
+        max_size: usize,
+    ) -> ClusteringResult {
+        euclidean_clustering_with_labels(self, tolerance, min_size, max_size)
+    }
+
+    fn dbscan(&self, eps: f32, min_points: usize) -> Result<ClusteringResult> {
+        dbscan_clustering(self, eps, min_points)
+    }
+
+    fn hdbscan(&self, min_cluster_size: usize) -> Result<ClusteringResult> {
+        hdbscan_clustering(self, min_cluster_size)
+    }
+
+    fn hdbscan_with_config(&self, config: &HdbscanConfig) -> Result<ClusteringResult> {
+        hdbscan_clustering_with_config(self, config)
+    }
+
+    fn region_growing(
+        &self,
+        normals: &[NormalEstimate],
+        config: &RegionGrowingConfig,
+    ) -> Result<ClusteringResult> {
+        region_growing_segmentation(self, normals, config)
+    }
+
+    fn region_growing_with_cloud_normals(
+        &self,
+        config: &RegionGrowingConfig,
+    ) -> Result<ClusteringResult>
+    where
+        P: PointNormal,
+    {
+        region_growing_from_normal_cloud(self, config)
+    }
+
+    fn color_region_growing(&self, config: &ColorRegionGrowingConfig) -> Result<Vec<Vec<usize>>>
+    where
+        P: PointColor,
+    {
+        color_region_growing_segmentation(self, config)
+    }
+
     fn ransac_plane(
         &self,
         distance_threshold: f32,
@@ -187,12 +1525,25 @@ impl<P: Point> SegmentationExt<P> for PointCloud<P> {
     ) -> Result<(Vec<usize>, [f32; 4])> {
         ransac_plane_segmentation(self, distance_threshold, max_iterations)
     }
+
+    fn sac_plane(&self, config: &SacConfig) -> Result<(Vec<usize>, [f32; 4])> {
+        sac_plane_segmentation(self, config)
+    }
+
+    fn organized_planes(
+        &self,
+        normals: &[NormalEstimate],
+        config: &OrganizedPlaneConfig,
+    ) -> Result<Vec<PlanarRegion>> {
+        organized_multi_plane_segmentation(self, normals, config)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::core::PointXYZ;
+    use crate::algorithms::feature::{FeatureExt, IntegralNormalConfig, IntegralNormalMethod};
+    use crate::core::{Metadata, PointXYZ, PointXYZRGB, PointXYZRGBNormal};
 
     #[test]
     fn test_euclidean_clustering() {
@@ -207,6 +1558,264 @@ mod tests {
         assert!(!clusters.is_empty());
     }
 
+    #[test]
+    fn test_euclidean_clustering_with_labels() {
+        // Two lines of points 5 units apart, a lone point and an invalid point
+        let mut points = Vec::new();
+        for i in 0..50 {
+            points.push(PointXYZ::new(i as f32 * 0.05, 0.0, 0.0));
+            points.push(PointXYZ::new(i as f32 * 0.05, 5.0, 0.0));
+        }
+        points.push(PointXYZ::new(20.0, 20.0, 20.0));
+        points.push(PointXYZ::new(f32::NAN, 0.0, 0.0));
+        let cloud = PointCloud::from_points(points);
+
+        let result = cloud.euclidean_cluster_with_labels(0.1, 2, 1000);
+        assert_eq!(result.num_clusters(), 2);
+        assert_eq!(result.labels.len(), cloud.len());
+        assert_eq!(result.noise_count(), 2);
+        assert_eq!(result.clusters[0], (0..100).step_by(2).collect::<Vec<_>>());
+        assert_eq!(result.labels[1], 1);
+        assert_eq!(result.labels[100], NOISE_LABEL);
+        assert_eq!(result.labels[101], NOISE_LABEL);
+
+        // Size limits turn clusters into noise
+        let result = cloud.euclidean_cluster_with_labels(0.1, 2, 10);
+        assert_eq!(result.num_clusters(), 0);
+        assert!(result.labels.iter().all(|&l| l == NOISE_LABEL));
+    }
+
+    /// Two dense blobs, one sparse blob and a few scattered outliers
+    fn blobs() -> Vec<PointXYZ> {
+        let mut points = Vec::new();
+        for (center, spacing) in [([0.0, 0.0, 0.0], 0.05), ([3.0, 0.0, 0.0], 0.05)] {
+            for i in 0..5 {
+                for j in 0..5 {
+                    for k in 0..2 {
+                        points.push(PointXYZ::new(
+                            center[0] + i as f32 * spacing,
+                            center[1] + j as f32 * spacing,
+                            center[2] + k as f32 * spacing,
+                        ));
+                    }
+                }
+            }
+        }
+        for (x, y, z) in [(10.0, 10.0, 0.0), (-8.0, 5.0, 3.0), (4.0, -9.0, -6.0)] {
+            points.push(PointXYZ::new(x, y, z));
+        }
+        points
+    }
+
+    #[test]
+    fn test_dbscan() {
+        let cloud = PointCloud::from_points(blobs());
+
+        let result = cloud.dbscan(0.08, 4).unwrap();
+        assert_eq!(result.num_clusters(), 2);
+        assert_eq!(result.clusters[0], (0..50).collect::<Vec<_>>());
+        assert_eq!(result.clusters[1], (50..100).collect::<Vec<_>>());
+        assert_eq!(result.noise_count(), 3);
+        assert_eq!(result.labels[100], NOISE_LABEL);
+
+        assert!(cloud.dbscan(0.0, 4).is_err());
+    }
+
+    #[test]
+    fn test_dbscan_border_points() {
+        // A dense row with one point just in reach of its last core point
+        let mut points: Vec<PointXYZ> = (0..5)
+            .map(|i| PointXYZ::new(i as f32 * 0.1, 0.0, 0.0))
+            .collect();
+        points.push(PointXYZ::new(0.55, 0.0, 0.0));
+        let cloud = PointCloud::from_points(points);
+
+        let result = cloud.dbscan(0.15, 3).unwrap();
+        assert_eq!(result.num_clusters(), 1);
+        assert_eq!(result.labels, vec![0; 6]);
+    }
+
+    #[test]
+    fn test_hdbscan() {
+        let cloud = PointCloud::from_points(blobs());
+
+        let result = cloud.hdbscan(10).unwrap();
+        assert_eq!(result.num_clusters(), 2);
+        assert_eq!(result.clusters[0], (0..50).collect::<Vec<_>>());
+        assert_eq!(result.clusters[1], (50..100).collect::<Vec<_>>());
+        assert_eq!(result.labels[101], NOISE_LABEL);
+        assert_eq!(result.labels.len(), cloud.len());
+
+        assert!(cloud.hdbscan(1).is_err());
+    }
+
+    #[test]
+    fn test_mutual_reachability_mst() {
+        // Scattered points with repeated coordinates to exercise ties
+        let points: Vec<PointXYZ> = (0..300u32)
+            .map(|i| {
+                let h = i.wrapping_mul(2654435761);
+                let c = |shift: u32| ((h >> shift) % 16) as f32 * 0.25;
+                PointXYZ::new(c(0), c(8), c(16))
+            })
+            .collect();
+        let min_samples = 4;
+        let edges = mutual_reachability_mst(&points, min_samples);
+        assert_eq!(edges.len(), points.len() - 1);
+
+        // Prim's algorithm on the dense mutual reachability graph
+        let core: Vec<f32> = points
+            .iter()
+            .map(|p| {
+                let mut d: Vec<f32> = points.iter().map(|q| p.distance_to(q)).collect();
+                d.sort_by(f32::total_cmp);
+                d[min_samples - 1]
+            })
+            .collect();
+        let weight =
+            |a: usize, b: usize| points[a].distance_to(&points[b]).max(core[a]).max(core[b]);
+        let mut in_tree = vec![false; points.len()];
+        let mut cost = vec![f32::INFINITY; points.len()];
+        cost[0] = 0.0;
+        let mut expected = 0.0f64;
+        for _ in 0..points.len() {
+            let next = (0..points.len())
+                .filter(|&i| !in_tree[i])
+                .min_by(|&a, &b| cost[a].total_cmp(&cost[b]))
+                .unwrap();
+            in_tree[next] = true;
+            expected += cost[next] as f64;
+            for i in 0..points.len() {
+                if !in_tree[i] {
+                    cost[i] = cost[i].min(weight(next, i));
+                }
+            }
+        }
+
+        let total: f64 = edges.iter().map(|&(_, _, d)| d as f64).sum();
+        assert!((total - expected).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_hdbscan_single_cluster() {
+        let points: Vec<PointXYZ> = (0..30)
+            .map(|i| PointXYZ::new(i as f32 * 0.1, 0.0, 0.0))
+            .collect();
+        let cloud = PointCloud::from_points(points);
+
+        let config = HdbscanConfig {
+            min_cluster_size: 5,
+            allow_single_cluster: true,
+            ..HdbscanConfig::default()
+        };
+        let result = cloud.hdbscan_with_config(&config).unwrap();
+        assert_eq!(result.num_clusters(), 1);
+        assert_eq!(result.noise_count(), 0);
+    }
+
+    /// A floor (z = 0) meeting a wall (x = 0) at a right angle
+    fn floor_and_wall() -> Vec<PointXYZRGBNormal> {
+        let mut points = Vec::new();
+        for i in 0..15 {
+            for j in 0..15 {
+                let (a, b) = (i as f32 * 0.1 + 0.1, j as f32 * 0.1);
+                points.push(PointXYZRGBNormal::new(a, b, 0.0, 0, 0, 0, 0.0, 0.0, 1.0));
+            }
+        }
+        for i in 0..10 {
+            for j in 0..15 {
+                let (a, b) = (i as f32 * 0.1 + 0.1, j as f32 * 0.1);
+                points.push(PointXYZRGBNormal::new(0.0, b, a, 0, 0, 0, 1.0, 0.0, 0.0));
+            }
+        }
+        points
+    }
+
+    #[test]
+    fn test_region_growing_with_cloud_normals() {
+        let cloud = PointCloud::from_points(floor_and_wall());
+
+        let config = RegionGrowingConfig {
+            search: NeighborhoodSearch::KNearest(8),
+            ..RegionGrowingConfig::default()
+        };
+        let result = cloud.region_growing_with_cloud_normals(&config).unwrap();
+        assert_eq!(result.num_clusters(), 2);
+        assert_eq!(result.clusters[0], (0..225).collect::<Vec<_>>());
+        assert_eq!(result.clusters[1], (225..375).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_region_growing_with_estimated_normals() {
+        let points: Vec<PointXYZ> = floor_and_wall()
+            .into_iter()
+            .map(|p| PointXYZ::new(p.x, p.y, p.z))
+            .collect();
+        let cloud = PointCloud::from_points(points);
+        let normals = cloud
+            .estimate_normals_with_curvature(NeighborhoodSearch::KNearest(8))
+            .unwrap();
+
+        let config = RegionGrowingConfig {
+            search: NeighborhoodSearch::KNearest(8),
+            smoothness_threshold: 10.0f32.to_radians(),
+            curvature_threshold: 0.05,
+            min_cluster_size: 50,
+            ..RegionGrowingConfig::default()
+        };
+        let result = cloud.region_growing(&normals, &config).unwrap();
+        assert_eq!(result.num_clusters(), 2);
+
+        // Each plane is dominated by a single region
+        let floor_label = result.labels[7 * 15 + 7];
+        let wall_label = result.labels[225 + 5 * 15 + 7];
+        assert_ne!(floor_label, NOISE_LABEL);
+        assert_ne!(wall_label, NOISE_LABEL);
+        assert_ne!(floor_label, wall_label);
+
+        assert!(cloud.region_growing(&normals[1..], &config).is_err());
+    }
+
+    #[test]
+    fn test_color_region_growing() {
+        // A red half and a blue half on one plane, with a slightly darker
+        // red speck and a smooth gradient within the red half
+        let mut points = Vec::new();
+        for i in 0..20 {
+            for j in 0..10 {
+                let (x, y) = (i as f32 * 0.05, j as f32 * 0.05);
+                let point = if i >= 10 {
+                    PointXYZRGB::new(x, y, 0.0, 0, 0, 200)
+                } else if i == 4 && j == 4 {
+                    PointXYZRGB::new(x, y, 0.0, 170, 0, 0)
+                } else {
+                    PointXYZRGB::new(x, y, 0.0, 200 + j as u8, 0, 0)
+                };
+                points.push(point);
+            }
+        }
+        let cloud = PointCloud::from_points(points);
+
+        let config = ColorRegionGrowingConfig {
+            distance_threshold: 0.06,
+            point_color_threshold: 6.0,
+            region_color_threshold: 5.0,
+            min_cluster_size: 5,
+            ..ColorRegionGrowingConfig::default()
+        };
+        let clusters = cloud.color_region_growing(&config).unwrap();
+        assert_eq!(clusters.len(), 2);
+        assert_eq!(clusters[0], (0..100).collect::<Vec<_>>());
+        assert_eq!(clusters[1], (100..200).collect::<Vec<_>>());
+
+        // Without merging the speck stays on its own
+        let config = ColorRegionGrowingConfig {
+            min_cluster_size: 1,
+            ..config
+        };
+        assert_eq!(cloud.color_region_growing(&config).unwrap().len(), 3);
+    }
+
     #[test]
     fn test_ransac_plane() {
         // Create points on a plane z = 0
@@ -225,4 +1834,136 @@ mod tests {
         assert!(!inliers.is_empty());
         assert_eq!(plane.len(), 4);
     }
+
+    #[test]
+    fn test_ransac_plane_with_outliers() {
+        // Plane z = 0.5 with a handful of outliers above it
+        let mut points = Vec::new();
+        for i in 0..20 {
+            for j in 0..20 {
+                points.push(PointXYZ::new(i as f32 * 0.1, j as f32 * 0.1, 0.5));
+            }
+        }
+        for i in 0..40 {
+            let t = i as f32;
+            points.push(PointXYZ::new(
+                t * 0.05,
+                (t * 0.37) % 2.0,
+                1.0 + (t * 0.13) % 1.0,
+            ));
+        }
+        let cloud = PointCloud::from_points(points);
+
+        let config = SacConfig {
+            distance_threshold: 0.01,
+            seed: Some(42),
+            ..SacConfig::default()
+        };
+        let (inliers, plane) = cloud.sac_plane(&config).unwrap();
+        assert_eq!(inliers.len(), 400);
+        assert!(inliers.iter().all(|&i| i < 400));
+        assert!((plane[2].abs() - 1.0).abs() < 1e-5);
+        assert!((plane[3] / plane[2] + 0.5).abs() < 1e-5);
+
+        // The same seed reproduces the same result
+        let (again, again_plane) = cloud.sac_plane(&config).unwrap();
+        assert_eq!(inliers, again);
+        assert_eq!(plane, again_plane);
+    }
+
+    #[test]
+    fn test_organized_planes() {
+        // A table at depth 2 with a box top at depth 1.5 on its right, and a hole
+        let (width, height) = (40, 30);
+        let mut points = Vec::new();
+        for row in 0..height {
+            for column in 0..width {
+                let z = if column >= 25 && row >= 10 { 1.5 } else { 2.0 };
+                let (u, v) = (column as f32 - 20.0, row as f32 - 15.0);
+                points.push(PointXYZ::new(u * z / 100.0, v * z / 100.0, z));
+            }
+        }
+        points[5 * width + 5] = PointXYZ::new(f32::NAN, f32::NAN, f32::NAN);
+        let metadata = Metadata::new_organized(width as u32, height as u32);
+        let cloud = PointCloud::from_points_and_metadata(points, metadata);
+
+        let normal_config = IntegralNormalConfig {
+            method: IntegralNormalMethod::CovarianceMatrix,
+            window_radius: 2,
+            ..IntegralNormalConfig::default()
+        };
+        let normals = cloud
+            .estimate_normals_integral_with_config(&normal_config)
+            .unwrap();
+        let config = OrganizedPlaneConfig {
+            min_inliers: 50,
+            ..OrganizedPlaneConfig::default()
+        };
+        let planes = cloud.organized_planes(&normals, &config).unwrap();
+        assert_eq!(planes.len(), 2);
+
+        let (table, top) = (&planes[0], &planes[1]);
+        assert!(table.inliers.len() > top.inliers.len());
+        for (plane, depth) in [(table, 2.0), (top, 1.5)] {
+            let [a, b, c, d] = plane.coefficients;
+            assert!(a.abs() < 1e-4 && b.abs() < 1e-4);
+            assert!((c + 1.0).abs() < 1e-4);
+            assert!((d - depth).abs() < 1e-4);
+            assert!((plane.centroid[2] - depth).abs() < 1e-4);
+            assert!(plane.curvature < 1e-6);
+            assert!(
+                plane
+                    .inliers
+                    .iter()
+                    .all(|&i| (cloud.get(i).unwrap().z - depth).abs() < 1e-6)
+            );
+
+            // Every boundary pixel is an inlier with a neighbor outside the region
+            assert_eq!(plane.boundary[0], plane.inliers[0]);
+            for &pixel in &plane.boundary {
+                assert!(plane.inliers.binary_search(&pixel).is_ok());
+                let (row, column) = cloud.coordinates_of(pixel).unwrap();
+                let outside =
+                    |r: usize, c: usize| plane.inliers.binary_search(&(r * width + c)).is_err();
+                assert!(
+                    row == 0
+                        || column == 0
+                        || row + 1 == height
+                        || column + 1 == width
+                        || outside(row - 1, column)
+                        || outside(row + 1, column)
+                        || outside(row, column - 1)
+                        || outside(row, column + 1)
+                );
+            }
+        }
+        assert!(!table.inliers.contains(&(5 * width + 5)));
+
+        assert!(
+            PointCloud::from_points(cloud.points().to_vec())
+                .organized_planes(&normals, &config)
+                .is_err()
+        );
+        assert!(cloud.organized_planes(&normals[1..], &config).is_err());
+        let mut mismatched = cloud.clone();
+        mismatched.metadata_mut().width -= 1;
+        assert!(mismatched.organized_planes(&normals, &config).is_err());
+    }
+
+    #[test]
+    fn test_trace_contour() {
+        // A 3x3 block with its center missing inside a 5x5 image
+        let mut labels = vec![None; 25];
+        for row in 1..4 {
+            for column in 1..4 {
+                labels[row * 5 + column] = Some(0);
+            }
+        }
+        labels[12] = None;
+        labels[20] = Some(1);
+
+        let contour = trace_contour(&labels, 0, 6, 5, 5);
+        assert_eq!(contour, [6, 7, 8, 13, 18, 17, 16, 11]);
+        assert_eq!(trace_contour(&labels, 1, 20, 5, 5), [20]);
+    }
 }
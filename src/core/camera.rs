@@ -0,0 +1,361 @@
+//! Pinhole camera model for depth images
+//!
+//! This module converts depth images, optionally paired with color images,
+//! into organized point clouds and projects point clouds back into depth
+//! images. Images are row-major buffers of `width * height` pixels; color
+//! images hold interleaved 8-bit RGB triplets.
+//!
+//! Depth is given either as integer units (typically millimeters, `u16`) or
+//! as meters (`f32`), see [`DepthValue`]. A depth of zero, a non-finite depth
+//! or one outside the configured range marks an invalid pixel, which becomes
+//! a point with NaN coordinates.
+
+use crate::core::{Metadata, Point, PointCloud, PointXYZRGB};
+use crate::error::{CloudError, Result};
+use rayon::prelude::*;
+
+/// Intrinsic parameters of a pinhole camera
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CameraIntrinsics {
+    /// Image width in pixels
+    pub width: u32,
+
+    /// Image height in pixels
+    pub height: u32,
+
+    /// Focal length along x in pixels
+    pub fx: f32,
+
+    /// Focal length along y in pixels
+    pub fy: f32,
+
+    /// Principal point x coordinate in pixels
+    pub cx: f32,
+
+    /// Principal point y coordinate in pixels
+    pub cy: f32,
+}
+
+/// Options for converting between depth images and point clouds
+#[derive(Clone, Debug, PartialEq)]
+pub struct DepthConfig {
+    /// Depth units per meter of integer depth images (1000 for millimeters)
+    pub depth_scale: f32,
+
+    /// Smallest valid depth in meters
+    pub min_depth: f32,
+
+    /// Largest valid depth in meters
+    pub max_depth: f32,
+}
+
+impl Default for DepthConfig {
+    fn default() -> Self {
+        Self {
+            depth_scale: 1000.0,
+            min_depth: 0.0,
+            max_depth: f32::INFINITY,
+        }
+    }
+}
+
+/// Pixel type of a depth image
+pub trait DepthValue: Copy + Send + Sync {
+    /// Depth in meters, given the depth units per meter
+    fn to_meters(self, depth_scale: f32) -> f32;
+
+    /// Pixel value of a depth in meters, given the depth units per meter
+    fn from_meters(meters: f32, depth_scale: f32) -> Self;
+}
+
+impl DepthValue for u16 {
+    fn to_meters(self, depth_scale: f32) -> f32 {
+        self as f32 / depth_scale
+    }
+
+    fn from_meters(meters: f32, depth_scale: f32) -> Self {
+        (meters * depth_scale).round().clamp(0.0, u16::MAX as f32) as u16
+    }
+}
+
+impl DepthValue for f32 {
+    fn to_meters(self, _depth_scale: f32) -> f32 {
+        self
+    }
+
+    fn from_meters(meters: f32, _depth_scale: f32) -> Self {
+        meters
+    }
+}
+
+impl CameraIntrinsics {
+    /// Create camera intrinsics
+    pub fn new(width: u32, height: u32, fx: f32, fy: f32, cx: f32, cy: f32) -> Self {
+        Self {
+            width,
+            height,
+            fx,
+            fy,
+            cx,
+            cy,
+        }
+    }
+
+    /// Number of pixels of an image
+    pub fn pixel_count(&self) -> usize {
+        self.width as usize * self.height as usize
+    }
+
+    /// Point at the given pixel and depth in meters, in the camera frame
+    ///
+    /// The camera looks along +z with x to the right and y down.
+    pub fn unproject(&self, u: f32, v: f32, depth: f32) -> [f32; 3] {
+        [
+            (u - self.cx) * depth / self.fx,
+            (v - self.cy) * depth / self.fy,
+            depth,
+        ]
+    }
+
+    /// Pixel coordinates of a point in the camera frame
+    ///
+    /// Returns `None` for points that are not in front of the camera.
+    pub fn project(&self, point: [f32; 3]) -> Option<[f32; 2]> {
+        let [x, y, z] = point;
+        if z <= 0.0 || !x.is_finite() || !y.is_finite() || !z.is_finite() {
+            return None;
+        }
+        Some([x * self.fx / z + self.cx, y * self.fy / z + self.cy])
+    }
+
+    /// Convert a depth image into an organized point cloud
+    ///
+    /// See [`depth_to_cloud_with_config`](Self::depth_to_cloud_with_config).
+    ///
+    /// # Example
+    /// ```rust
+    /// use ferrum_cloud::core::CameraIntrinsics;
+    ///
+    /// let camera = CameraIntrinsics::new(2, 1, 500.0, 500.0, 1.0, 0.5);
+    /// let depth: [u16; 2] = [1500, 0];
+    /// let rgb = [255, 0, 0, 0, 255, 0];
+    ///
+    /// let cloud = camera.depth_to_cloud(&depth, Some(&rgb))?;
+    /// assert_eq!(cloud.metadata().width, 2);
+    /// assert_eq!(cloud.get(0).unwrap().z, 1.5);
+    /// assert!(cloud.get(1).unwrap().z.is_nan());
+    /// # Ok::<(), ferrum_cloud::error::CloudError>(())
+    /// ```
+    pub fn depth_to_cloud<D: DepthValue>(
+        &self,
+        depth: &[D],
+        rgb: Option<&[u8]>,
+    ) -> Result<PointCloud<PointXYZRGB>> {
+        self.depth_to_cloud_with_config(depth, rgb, &DepthConfig::default())
+    }
+
+    /// Convert a depth image into an organized point cloud with custom options
+    ///
+    /// The cloud has one point per pixel with the image's width and height.
+    /// Invalid pixels become points with NaN coordinates, in which case the
+    /// cloud is marked as not dense. Without a color image, points are black.
+    pub fn depth_to_cloud_with_config<D: DepthValue>(
+        &self,
+        depth: &[D],
+        rgb: Option<&[u8]>,
+        config: &DepthConfig,
+    ) -> Result<PointCloud<PointXYZRGB>> {
+        self.validate(config)?;
+        let pixels = self.pixel_count();
+        if depth.len() != pixels {
+            return Err(CloudError::invalid_parameter(format!(
+                "Depth image has {} pixels, expected {}x{}",
+                depth.len(),
+                self.width,
+                self.height
+            )));
+        }
+        if let Some(rgb) = rgb
+            && rgb.len() != pixels * 3
+        {
+            return Err(CloudError::invalid_parameter(format!(
+                "Color image has {} bytes, expected {} for {}x{} RGB pixels",
+                rgb.len(),
+                pixels * 3,
+                self.width,
+                self.height
+            )));
+        }
+
+        let width = self.width as usize;
+        let points: Vec<PointXYZRGB> = depth
+            .par_iter()
+            .enumerate()
+            .map(|(index, &value)| {
+                let [r, g, b] = rgb.map_or([0; 3], |rgb| {
+                    [rgb[index * 3], rgb[index * 3 + 1], rgb[index * 3 + 2]]
+                });
+                let meters = value.to_meters(config.depth_scale);
+                if meters.is_finite()
+                    && meters > 0.0
+                    && meters >= config.min_depth
+                    && meters <= config.max_depth
+                {
+                    let (u, v) = ((index % width) as f32, (index / width) as f32);
+                    let [x, y, z] = self.unproject(u, v, meters);
+                    PointXYZRGB::new(x, y, z, r, g, b)
+                } else {
+                    PointXYZRGB::new(f32::NAN, f32::NAN, f32::NAN, r, g, b)
+                }
+            })
+            .collect();
+
+        let mut metadata = Metadata::new_organized(self.width, self.height);
+        metadata.set_dense(points.iter().all(|p| !p.x.is_nan()));
+        Ok(PointCloud::from_points_and_metadata(points, metadata))
+    }
+
+    /// Project a point cloud into a depth image
+    ///
+    /// Points are expected in the camera frame. Each point is rounded to the
+    /// nearest pixel; when several points fall on the same pixel the closest
+    /// one is kept. Pixels without a valid point have a depth of zero.
+    pub fn cloud_to_depth<D: DepthValue, P: Point>(&self, cloud: &PointCloud<P>) -> Result<Vec<D>> {
+        self.cloud_to_depth_with_config(cloud, &DepthConfig::default())
+    }
+
+    /// Project a point cloud into a depth image with custom options
+    ///
+    /// Points outside the configured depth range are skipped.
+    pub fn cloud_to_depth_with_config<D: DepthValue, P: Point>(
+        &self,
+        cloud: &PointCloud<P>,
+        config: &DepthConfig,
+    ) -> Result<Vec<D>> {
+        self.validate(config)?;
+
+        let mut depth = vec![f32::INFINITY; self.pixel_count()];
+        for point in cloud.iter() {
+            let position = point.position();
+            let z = position[2];
+            if z < config.min_depth || z > config.max_depth {
+                continue;
+            }
+            let Some([u, v]) = self.project(position) else {
+                continue;
+            };
+            let (u, v) = (u.round(), v.round());
+            if u < 0.0 || v < 0.0 || u >= self.width as f32 || v >= self.height as f32 {
+                continue;
+            }
+            let pixel = &mut depth[v as usize * self.width as usize + u as usize];
+            *pixel = pixel.min(z);
+        }
+
+        Ok(depth
+            .into_iter()
+            .map(|z| D::from_meters(if z.is_finite() { z } else { 0.0 }, config.depth_scale))
+            .collect())
+    }
+
+    fn validate(&self, config: &DepthConfig) -> Result<()> {
+        if !(self.fx > 0.0 && self.fy > 0.0) {
+            return Err(CloudError::invalid_parameter(format!(
+                "Focal lengths must be positive, got fx = {} and fy = {}",
+                self.fx, self.fy
+            )));
+        }
+        if config.depth_scale <= 0.0 || !config.depth_scale.is_finite() {
+            return Err(CloudError::invalid_parameter(format!(
+                "Depth scale must be positive, got {}",
+                config.depth_scale
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::PointXYZ;
+
+    fn camera() -> CameraIntrinsics {
+        CameraIntrinsics::new(4, 3, 100.0, 100.0, 1.5, 1.0)
+    }
+
+    #[test]
+    fn test_depth_roundtrip() {
+        let camera = camera();
+        let mut depth: Vec<u16> = (0..12).map(|i| 1000 + 10 * i).collect();
+        depth[5] = 0;
+        let rgb: Vec<u8> = (0..36).collect();
+
+        let cloud = camera.depth_to_cloud(&depth, Some(&rgb)).unwrap();
+        let metadata = cloud.metadata();
+        assert!(metadata.is_organized);
+        assert_eq!((metadata.width, metadata.height), (4, 3));
+        assert!(!metadata.is_dense());
+        assert!(cloud.get(5).unwrap().x.is_nan());
+        assert_eq!(cloud.get(5).unwrap().color(), Some([15, 16, 17]));
+
+        // Pixel (u = 3, v = 2) at 1.11 m
+        let point = cloud.get(11).unwrap();
+        assert!((point.x - 1.5 * 1.11 / 100.0).abs() < 1e-6);
+        assert!((point.y - 1.11 / 100.0).abs() < 1e-6);
+        assert!((point.z - 1.11).abs() < 1e-6);
+
+        let projected: Vec<u16> = camera.cloud_to_depth(&cloud).unwrap();
+        assert_eq!(projected, depth);
+
+        let meters: Vec<f32> = camera.cloud_to_depth(&cloud).unwrap();
+        assert_eq!(meters[5], 0.0);
+        assert!((meters[0] - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_config_and_errors() {
+        let camera = camera();
+        let depth = vec![
+            0.5f32,
+            1.0,
+            2.0,
+            f32::NAN,
+            4.0,
+            1.0,
+            1.0,
+            1.0,
+            1.0,
+            1.0,
+            1.0,
+            1.0,
+        ];
+        let config = DepthConfig {
+            min_depth: 0.8,
+            max_depth: 3.0,
+            ..Default::default()
+        };
+        let cloud = camera
+            .depth_to_cloud_with_config(&depth, None, &config)
+            .unwrap();
+        let valid: Vec<bool> = cloud.iter().take(5).map(|p| !p.z.is_nan()).collect();
+        assert_eq!(valid, [false, true, true, false, false]);
+        assert_eq!(cloud.get(1).unwrap().color(), Some([0, 0, 0]));
+
+        assert!(camera.depth_to_cloud(&depth[..11], None).is_err());
+        assert!(camera.depth_to_cloud(&depth, Some(&[0; 3])).is_err());
+        let flat = CameraIntrinsics { fx: 0.0, ..camera };
+        assert!(flat.depth_to_cloud(&depth, None).is_err());
+
+        // The closest point wins, points behind the camera are skipped
+        let cloud = PointCloud::from_points(vec![
+            PointXYZ::new(0.0, 0.0, 2.0),
+            PointXYZ::new(0.0, 0.0, 1.0),
+            PointXYZ::new(0.0, 0.0, -1.0),
+            PointXYZ::new(100.0, 0.0, 1.0),
+        ]);
+        let depth: Vec<u16> = camera.cloud_to_depth(&cloud).unwrap();
+        assert_eq!(depth[4 + 2], 1000);
+        assert_eq!(depth.iter().filter(|&&d| d != 0).count(), 1);
+    }
+}
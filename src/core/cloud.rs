@@ -3,10 +3,11 @@
 //! This module provides the main PointCloud container that owns point data
 //! and provides methods for manipulation and processing.
 
-use crate::core::{Metadata, Point};
-use crate::error::{CloudError, Result};
+use crate::core::{Metadata, Point, PointCloudView, PointNormal};
+use crate::error::Result;
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::ops::Range;
 use std::sync::Arc;
 
 /// Main point cloud container that owns point data
@@ -129,22 +130,22 @@ impl<P: Point> PointCloud<P> {
     }
 
     /// Create an iterator over the points
-    pub fn iter(&self) -> std::slice::Iter<P> {
+    pub fn iter(&self) -> std::slice::Iter<'_, P> {
         self.points.iter()
     }
 
     /// Create a mutable iterator over the points
-    pub fn iter_mut(&mut self) -> std::slice::IterMut<P> {
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, P> {
         self.points.iter_mut()
     }
 
     /// Create a parallel iterator over the points
-    pub fn par_iter(&self) -> rayon::slice::Iter<P> {
+    pub fn par_iter(&self) -> rayon::slice::Iter<'_, P> {
         self.points.par_iter()
     }
 
     /// Create a parallel mutable iterator over the points
-    pub fn par_iter_mut(&mut self) -> rayon::slice::IterMut<P> {
+    pub fn par_iter_mut(&mut self) -> rayon::slice::IterMut<'_, P> {
         self.points.par_iter_mut()
     }
 
@@ -229,6 +230,14 @@ impl<P: Point> PointCloud<P> {
         Some([sum[0] / len, sum[1] / len, sum[2] / len])
     }
 
+    /// Collect the normal vectors of all points
+    pub fn normals(&self) -> Vec<[f32; 3]>
+    where
+        P: PointNormal,
+    {
+        self.points.iter().map(|p| p.normal()).collect()
+    }
+
     /// Crop the point cloud to a bounding box
     pub fn crop(self, min_bounds: [f32; 3], max_bounds: [f32; 3]) -> Self {
         self.filter(|p| {
@@ -242,6 +251,72 @@ impl<P: Point> PointCloud<P> {
         })
     }
 
+    /// Borrow the point cloud as a view
+    pub fn view(&self) -> PointCloudView<'_, P> {
+        PointCloudView::new(&self.points, &self.metadata)
+    }
+
+    /// Width and height of the point grid, see [`PointCloudView::image_size`]
+    pub fn image_size(&self) -> (usize, usize) {
+        self.view().image_size()
+    }
+
+    /// Index of the point at the given row and column
+    pub fn index_of(&self, row: usize, column: usize) -> Option<usize> {
+        self.view().index_of(row, column)
+    }
+
+    /// Row and column of the point at the given index
+    pub fn coordinates_of(&self, index: usize) -> Option<(usize, usize)> {
+        self.view().coordinates_of(index)
+    }
+
+    /// Get the point at the given row and column of an organized cloud
+    pub fn at(&self, row: usize, column: usize) -> Option<&P> {
+        self.view().at(row, column)
+    }
+
+    /// Get a mutable reference to the point at the given row and column
+    pub fn at_mut(&mut self, row: usize, column: usize) -> Option<&mut P> {
+        let index = self.index_of(row, column)?;
+        self.points.get_mut(index)
+    }
+
+    /// Get the points of one row
+    pub fn row(&self, row: usize) -> Option<&[P]> {
+        self.view().row(row)
+    }
+
+    /// Iterate over the rows of the point grid
+    pub fn rows(&self) -> std::slice::ChunksExact<'_, P> {
+        self.view().rows()
+    }
+
+    /// Iterate over the points of one column, from top to bottom
+    pub fn column(&self, column: usize) -> std::iter::StepBy<std::slice::Iter<'_, P>> {
+        self.view().column(column)
+    }
+
+    /// Iterate over the columns of the point grid
+    pub fn columns(&self) -> impl Iterator<Item = std::iter::StepBy<std::slice::Iter<'_, P>>> {
+        self.view().columns()
+    }
+
+    /// Iterate over the square neighborhood of a point, see [`PointCloudView::window`]
+    pub fn window(
+        &self,
+        row: usize,
+        column: usize,
+        radius: usize,
+    ) -> impl Iterator<Item = (usize, &P)> {
+        self.view().window(row, column, radius)
+    }
+
+    /// Copy a rectangular part of the point grid into a new cloud
+    pub fn crop_image(&self, rows: Range<usize>, columns: Range<usize>) -> Result<PointCloud<P>> {
+        self.view().crop_image(rows, columns)
+    }
+
     /// Convert to shared ownership using Arc
     pub fn into_shared(self) -> Arc<Self> {
         Arc::new(self)
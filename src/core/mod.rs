@@ -3,13 +3,18 @@
 //! This module contains the fundamental building blocks of the FerrumCloud library,
 //! including point types, point cloud containers, and views.
 
+pub mod camera;
 pub mod cloud;
 pub mod metadata;
 pub mod point;
 pub mod view;
 
 // Re-export commonly used types
+pub use camera::{CameraIntrinsics, DepthConfig, DepthValue};
 pub use cloud::PointCloud;
 pub use metadata::Metadata;
-pub use point::{Point, PointXYZ, PointXYZRGB, PointXYZRGBNormal};
+pub use point::{
+    FieldDescriptor, FieldType, PackedPoint, Point, PointColor, PointFields, PointMut, PointNormal,
+    PointXYZ, PointXYZI, PointXYZL, PointXYZRGB, PointXYZRGBNormal,
+};
 pub use view::PointCloudView;
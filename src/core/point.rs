@@ -29,6 +29,11 @@ pub trait Point: Send + Sync + Clone + Debug {
         self.position()[2]
     }
 
+    /// Get the RGB color of the point, if the point type carries one
+    fn color(&self) -> Option<[u8; 3]> {
+        None
+    }
+
     /// Calculate squared distance to another point
     fn distance_squared_to<P: Point>(&self, other: &P) -> f32 {
         let pos1 = self.position();
@@ -45,8 +50,129 @@ pub trait Point: Send + Sync + Clone + Debug {
     }
 }
 
+/// Trait for point types whose position can be modified
+///
+/// Algorithms that produce new coordinates (transforms, voxel averaging, ...)
+/// require this trait so they can write results back into the point while
+/// preserving its other attributes.
+pub trait PointMut: Point {
+    /// Set the 3D position of the point from [x, y, z]
+    fn set_position(&mut self, position: [f32; 3]);
+
+    /// Create a point at the given position with all other attributes defaulted
+    fn from_position(position: [f32; 3]) -> Self;
+
+    /// Return a copy of this point moved to the given position
+    fn with_position(&self, position: [f32; 3]) -> Self {
+        let mut point = self.clone();
+        point.set_position(position);
+        point
+    }
+
+    /// Rotate the directional attributes of the point, such as its normal
+    ///
+    /// Called by rigid transforms after the position has been moved. Point
+    /// types without directional attributes keep the default, which does
+    /// nothing.
+    fn rotate_directions(&mut self, _rotation: &[[f32; 3]; 3]) {}
+}
+
+/// Trait for point types that carry a surface normal
+pub trait PointNormal: Point {
+    /// Get the normal vector as [nx, ny, nz]
+    fn normal(&self) -> [f32; 3];
+
+    /// Set the normal vector
+    fn set_normal(&mut self, normal: [f32; 3]);
+}
+
+/// Trait for point types that always carry an RGB color
+///
+/// Implementors should return `Some(self.rgb_color())` from [`Point::color`].
+pub trait PointColor: Point {
+    /// Get the color as [r, g, b]
+    fn rgb_color(&self) -> [u8; 3];
+
+    /// Set the color from [r, g, b]
+    fn set_rgb_color(&mut self, rgb: [u8; 3]);
+}
+
+/// Scalar data type of a point field
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FieldType {
+    I8,
+    I16,
+    I32,
+    U8,
+    U16,
+    U32,
+    F32,
+    F64,
+}
+
+impl FieldType {
+    /// Size of one value in bytes
+    pub fn size(&self) -> usize {
+        match self {
+            FieldType::I8 | FieldType::U8 => 1,
+            FieldType::I16 | FieldType::U16 => 2,
+            FieldType::I32 | FieldType::U32 | FieldType::F32 => 4,
+            FieldType::F64 => 8,
+        }
+    }
+}
+
+/// Named scalar field of a point type
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FieldDescriptor {
+    pub name: &'static str,
+    pub datatype: FieldType,
+}
+
+impl FieldDescriptor {
+    /// Create a new field descriptor
+    pub const fn new(name: &'static str, datatype: FieldType) -> Self {
+        Self { name, datatype }
+    }
+}
+
+/// Trait for point types that can be described as a list of named fields
+///
+/// File readers and writers use the field names to map point attributes to
+/// file columns. Colors are exposed as separate `r`, `g` and `b` fields and
+/// normals as `normal_x`, `normal_y` and `normal_z`. Values are passed as
+/// `f64`, which represents every supported field type exactly.
+pub trait PointFields: Point {
+    /// Fields of the point type, in storage order
+    fn fields() -> &'static [FieldDescriptor];
+
+    /// Write the value of every field, in the order of `fields()`
+    fn write_values(&self, values: &mut [f64]);
+
+    /// Build a point from the value of every field, in the order of `fields()`
+    fn from_values(values: &[f64]) -> Self;
+}
+
+/// Point types whose memory layout is exactly their fields, packed in order
+///
+/// Such points can be viewed directly in binary file data without decoding,
+/// see [`MappedPointCloud`](crate::io::MappedPointCloud).
+///
+/// # Safety
+/// Implementors must be `#[repr(C)]`, consist of exactly the fields returned
+/// by [`PointFields::fields`] in that order with no padding, and be valid for
+/// every bit pattern of those fields.
+pub unsafe trait PackedPoint: PointFields {}
+
+const XYZ_FIELDS: [FieldDescriptor; 3] = [
+    FieldDescriptor::new("x", FieldType::F32),
+    FieldDescriptor::new("y", FieldType::F32),
+    FieldDescriptor::new("z", FieldType::F32),
+];
+
 /// Basic 3D point with XYZ coordinates
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[repr(C)]
 pub struct PointXYZ {
     pub x: f32,
     pub y: f32,
@@ -76,12 +202,193 @@ impl Point for PointXYZ {
     }
 }
 
+impl PointMut for PointXYZ {
+    fn set_position(&mut self, position: [f32; 3]) {
+        self.x = position[0];
+        self.y = position[1];
+        self.z = position[2];
+    }
+
+    fn from_position(position: [f32; 3]) -> Self {
+        Self::from_array(position)
+    }
+}
+
+impl PointFields for PointXYZ {
+    fn fields() -> &'static [FieldDescriptor] {
+        &XYZ_FIELDS
+    }
+
+    fn write_values(&self, values: &mut [f64]) {
+        values[0] = self.x as f64;
+        values[1] = self.y as f64;
+        values[2] = self.z as f64;
+    }
+
+    fn from_values(values: &[f64]) -> Self {
+        Self::new(values[0] as f32, values[1] as f32, values[2] as f32)
+    }
+}
+
+// SAFETY: `#[repr(C)]` with three `f32` fields, so the 12 bytes have no
+// padding, are laid out as `x`, `y`, `z` like `XYZ_FIELDS`, and every bit
+// pattern is a valid (possibly NaN) float
+unsafe impl PackedPoint for PointXYZ {}
+
 impl Default for PointXYZ {
     fn default() -> Self {
         Self::origin()
     }
 }
 
+/// 3D point with XYZ coordinates and intensity
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[repr(C)]
+pub struct PointXYZI {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub intensity: f32,
+}
+
+impl PointXYZI {
+    /// Create a new PointXYZI
+    pub fn new(x: f32, y: f32, z: f32, intensity: f32) -> Self {
+        Self { x, y, z, intensity }
+    }
+}
+
+impl Point for PointXYZI {
+    fn position(&self) -> [f32; 3] {
+        [self.x, self.y, self.z]
+    }
+}
+
+impl PointMut for PointXYZI {
+    fn set_position(&mut self, position: [f32; 3]) {
+        self.x = position[0];
+        self.y = position[1];
+        self.z = position[2];
+    }
+
+    fn from_position(position: [f32; 3]) -> Self {
+        Self::new(position[0], position[1], position[2], 0.0)
+    }
+}
+
+impl PointFields for PointXYZI {
+    fn fields() -> &'static [FieldDescriptor] {
+        const FIELDS: [FieldDescriptor; 4] = [
+            XYZ_FIELDS[0],
+            XYZ_FIELDS[1],
+            XYZ_FIELDS[2],
+            FieldDescriptor::new("intensity", FieldType::F32),
+        ];
+        &FIELDS
+    }
+
+    fn write_values(&self, values: &mut [f64]) {
+        values[0] = self.x as f64;
+        values[1] = self.y as f64;
+        values[2] = self.z as f64;
+        values[3] = self.intensity as f64;
+    }
+
+    fn from_values(values: &[f64]) -> Self {
+        Self::new(
+            values[0] as f32,
+            values[1] as f32,
+            values[2] as f32,
+            values[3] as f32,
+        )
+    }
+}
+
+// SAFETY: `#[repr(C)]` with four `f32` fields, so the 16 bytes have no
+// padding and follow the `x`, `y`, `z`, `intensity` order of `fields()`;
+// every bit pattern is a valid float
+unsafe impl PackedPoint for PointXYZI {}
+
+impl Default for PointXYZI {
+    fn default() -> Self {
+        Self::new(0.0, 0.0, 0.0, 0.0)
+    }
+}
+
+/// 3D point with XYZ coordinates and an integer label
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[repr(C)]
+pub struct PointXYZL {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub label: u32,
+}
+
+impl PointXYZL {
+    /// Create a new PointXYZL
+    pub fn new(x: f32, y: f32, z: f32, label: u32) -> Self {
+        Self { x, y, z, label }
+    }
+}
+
+impl Point for PointXYZL {
+    fn position(&self) -> [f32; 3] {
+        [self.x, self.y, self.z]
+    }
+}
+
+impl PointMut for PointXYZL {
+    fn set_position(&mut self, position: [f32; 3]) {
+        self.x = position[0];
+        self.y = position[1];
+        self.z = position[2];
+    }
+
+    fn from_position(position: [f32; 3]) -> Self {
+        Self::new(position[0], position[1], position[2], 0)
+    }
+}
+
+impl PointFields for PointXYZL {
+    fn fields() -> &'static [FieldDescriptor] {
+        const FIELDS: [FieldDescriptor; 4] = [
+            XYZ_FIELDS[0],
+            XYZ_FIELDS[1],
+            XYZ_FIELDS[2],
+            FieldDescriptor::new("label", FieldType::U32),
+        ];
+        &FIELDS
+    }
+
+    fn write_values(&self, values: &mut [f64]) {
+        values[0] = self.x as f64;
+        values[1] = self.y as f64;
+        values[2] = self.z as f64;
+        values[3] = self.label as f64;
+    }
+
+    fn from_values(values: &[f64]) -> Self {
+        Self::new(
+            values[0] as f32,
+            values[1] as f32,
+            values[2] as f32,
+            values[3] as u32,
+        )
+    }
+}
+
+// SAFETY: `#[repr(C)]` with three `f32` fields followed by a `u32` label, all
+// 4-byte aligned, so the 16 bytes have no padding and follow the order of
+// `fields()`; every bit pattern is a valid float or integer
+unsafe impl PackedPoint for PointXYZL {}
+
+impl Default for PointXYZL {
+    fn default() -> Self {
+        Self::new(0.0, 0.0, 0.0, 0)
+    }
+}
+
 /// 3D point with XYZ coordinates and RGB color
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct PointXYZRGB {
@@ -126,6 +433,66 @@ impl Point for PointXYZRGB {
     fn position(&self) -> [f32; 3] {
         [self.x, self.y, self.z]
     }
+
+    fn color(&self) -> Option<[u8; 3]> {
+        Some(self.rgb_color())
+    }
+}
+
+impl PointMut for PointXYZRGB {
+    fn set_position(&mut self, position: [f32; 3]) {
+        self.x = position[0];
+        self.y = position[1];
+        self.z = position[2];
+    }
+
+    fn from_position(position: [f32; 3]) -> Self {
+        Self::new(position[0], position[1], position[2], 0, 0, 0)
+    }
+}
+
+impl PointColor for PointXYZRGB {
+    fn rgb_color(&self) -> [u8; 3] {
+        [self.r, self.g, self.b]
+    }
+
+    fn set_rgb_color(&mut self, rgb: [u8; 3]) {
+        [self.r, self.g, self.b] = rgb;
+    }
+}
+
+impl PointFields for PointXYZRGB {
+    fn fields() -> &'static [FieldDescriptor] {
+        const FIELDS: [FieldDescriptor; 6] = [
+            XYZ_FIELDS[0],
+            XYZ_FIELDS[1],
+            XYZ_FIELDS[2],
+            FieldDescriptor::new("r", FieldType::U8),
+            FieldDescriptor::new("g", FieldType::U8),
+            FieldDescriptor::new("b", FieldType::U8),
+        ];
+        &FIELDS
+    }
+
+    fn write_values(&self, values: &mut [f64]) {
+        values[0] = self.x as f64;
+        values[1] = self.y as f64;
+        values[2] = self.z as f64;
+        values[3] = self.r as f64;
+        values[4] = self.g as f64;
+        values[5] = self.b as f64;
+    }
+
+    fn from_values(values: &[f64]) -> Self {
+        Self::new(
+            values[0] as f32,
+            values[1] as f32,
+            values[2] as f32,
+            values[3] as u8,
+            values[4] as u8,
+            values[5] as u8,
+        )
+    }
 }
 
 impl Default for PointXYZRGB {
@@ -150,6 +517,7 @@ pub struct PointXYZRGBNormal {
 
 impl PointXYZRGBNormal {
     /// Create a new PointXYZRGBNormal
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         x: f32,
         y: f32,
@@ -189,6 +557,102 @@ impl Point for PointXYZRGBNormal {
     fn position(&self) -> [f32; 3] {
         [self.x, self.y, self.z]
     }
+
+    fn color(&self) -> Option<[u8; 3]> {
+        Some(self.rgb_color())
+    }
+}
+
+impl PointMut for PointXYZRGBNormal {
+    fn set_position(&mut self, position: [f32; 3]) {
+        self.x = position[0];
+        self.y = position[1];
+        self.z = position[2];
+    }
+
+    fn from_position(position: [f32; 3]) -> Self {
+        Self::new(
+            position[0],
+            position[1],
+            position[2],
+            0,
+            0,
+            0,
+            0.0,
+            0.0,
+            1.0,
+        )
+    }
+
+    fn rotate_directions(&mut self, rotation: &[[f32; 3]; 3]) {
+        let n = self.normal();
+        self.set_normal(rotation.map(|row| row[0] * n[0] + row[1] * n[1] + row[2] * n[2]));
+    }
+}
+
+impl PointNormal for PointXYZRGBNormal {
+    fn normal(&self) -> [f32; 3] {
+        [self.normal_x, self.normal_y, self.normal_z]
+    }
+
+    fn set_normal(&mut self, normal: [f32; 3]) {
+        self.normal_x = normal[0];
+        self.normal_y = normal[1];
+        self.normal_z = normal[2];
+    }
+}
+
+impl PointColor for PointXYZRGBNormal {
+    fn rgb_color(&self) -> [u8; 3] {
+        [self.r, self.g, self.b]
+    }
+
+    fn set_rgb_color(&mut self, rgb: [u8; 3]) {
+        [self.r, self.g, self.b] = rgb;
+    }
+}
+
+impl PointFields for PointXYZRGBNormal {
+    fn fields() -> &'static [FieldDescriptor] {
+        const FIELDS: [FieldDescriptor; 9] = [
+            XYZ_FIELDS[0],
+            XYZ_FIELDS[1],
+            XYZ_FIELDS[2],
+            FieldDescriptor::new("r", FieldType::U8),
+            FieldDescriptor::new("g", FieldType::U8),
+            FieldDescriptor::new("b", FieldType::U8),
+            FieldDescriptor::new("normal_x", FieldType::F32),
+            FieldDescriptor::new("normal_y", FieldType::F32),
+            FieldDescriptor::new("normal_z", FieldType::F32),
+        ];
+        &FIELDS
+    }
+
+    fn write_values(&self, values: &mut [f64]) {
+        values[0] = self.x as f64;
+        values[1] = self.y as f64;
+        values[2] = self.z as f64;
+        values[3] = self.r as f64;
+        values[4] = self.g as f64;
+        values[5] = self.b as f64;
+        values[6] = self.normal_x as f64;
+        values[7] = self.normal_y as f64;
+        values[8] = self.normal_z as f64;
+    }
+
+    fn from_values(values: &[f64]) -> Self {
+        Self::new(
+            values[0] as f32,
+            values[1] as f32,
+            values[2] as f32,
+            values[3] as u8,
+            values[4] as u8,
+            values[5] as u8,
+            values[6] as f32,
+            values[7] as f32,
+            values[8] as f32,
+        )
+    }
 }
 
 impl Default for PointXYZRGBNormal {
@@ -227,5 +691,43 @@ mod tests {
         let normalized = point.rgb_normalized();
         assert!((normalized[0] - 1.0).abs() < f32::EPSILON);
         assert!((normalized[1] - 0.5019608).abs() < 0.001);
+
+        let mut point = point;
+        point.set_rgb_color([1, 2, 3]);
+        assert_eq!(point.color(), Some([1, 2, 3]));
+        assert_eq!(PointXYZ::origin().color(), None);
+    }
+
+    #[test]
+    fn test_point_mut() {
+        let mut point = PointXYZRGB::new(1.0, 2.0, 3.0, 10, 20, 30);
+        point.set_position([4.0, 5.0, 6.0]);
+        assert_eq!(point.position(), [4.0, 5.0, 6.0]);
+        assert_eq!((point.r, point.g, point.b), (10, 20, 30));
+
+        let moved = PointXYZ::new(1.0, 1.0, 1.0).with_position([2.0, 3.0, 4.0]);
+        assert_eq!(moved, PointXYZ::new(2.0, 3.0, 4.0));
+
+        let built = PointXYZRGBNormal::from_position([1.0, 2.0, 3.0]);
+        assert_eq!(built.position(), [1.0, 2.0, 3.0]);
+        assert_eq!(built.normal(), [0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn test_point_fields() {
+        let point = PointXYZRGBNormal::new(1.0, 2.0, 3.0, 4, 5, 6, 0.0, 1.0, 0.0);
+        let fields = PointXYZRGBNormal::fields();
+        assert_eq!(fields[3].name, "r");
+        assert_eq!(fields[6].name, "normal_x");
+
+        let mut values = vec![0.0; fields.len()];
+        point.write_values(&mut values);
+        assert_eq!(PointXYZRGBNormal::from_values(&values), point);
+
+        let labeled = PointXYZL::new(1.0, 2.0, 3.0, 42);
+        let mut values = vec![0.0; PointXYZL::fields().len()];
+        labeled.write_values(&mut values);
+        assert_eq!(values[3], 42.0);
+        assert_eq!(PointXYZL::from_values(&values), labeled);
     }
 }
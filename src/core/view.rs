@@ -3,8 +3,10 @@
 //! This module provides PointCloudView which allows zero-copy access to
 //! point cloud data through borrowing.
 
-use crate::core::{Metadata, Point};
+use crate::core::{Metadata, Point, PointCloud};
+use crate::error::{CloudError, Result};
 use rayon::prelude::*;
+use std::ops::Range;
 
 /// Zero-copy view into a point cloud
 ///
@@ -166,12 +168,138 @@ impl<'a, P: Point> PointCloudView<'a, P> {
     }
 }
 
+/// Image-like access to organized point clouds
+///
+/// Points of an organized cloud are stored row by row, so the point at
+/// `(row, column)` has index `row * width + column`. Clouds that are not
+/// organized, or whose metadata does not match their size, are treated as a
+/// single row.
+impl<'a, P: Point> PointCloudView<'a, P> {
+    /// Width and height of the point grid
+    pub fn image_size(&self) -> (usize, usize) {
+        let (width, height) = (self.metadata.width as usize, self.metadata.height as usize);
+        if self.metadata.is_organized && width * height == self.points.len() && width > 0 {
+            (width, height)
+        } else {
+            (self.points.len(), 1)
+        }
+    }
+
+    /// Index of the point at the given row and column
+    pub fn index_of(&self, row: usize, column: usize) -> Option<usize> {
+        let (width, height) = self.image_size();
+        (row < height && column < width).then_some(row * width + column)
+    }
+
+    /// Row and column of the point at the given index
+    pub fn coordinates_of(&self, index: usize) -> Option<(usize, usize)> {
+        let (width, _) = self.image_size();
+        (index < self.points.len()).then(|| (index / width, index % width))
+    }
+
+    /// Get the point at the given row and column
+    pub fn at(&self, row: usize, column: usize) -> Option<&'a P> {
+        self.index_of(row, column).map(|index| &self.points[index])
+    }
+
+    /// Get the points of one row
+    pub fn row(&self, row: usize) -> Option<&'a [P]> {
+        let (width, height) = self.image_size();
+        (row < height).then(|| &self.points[row * width..(row + 1) * width])
+    }
+
+    /// Iterate over the rows of the point grid
+    pub fn rows(&self) -> std::slice::ChunksExact<'a, P> {
+        let (width, _) = self.image_size();
+        self.points.chunks_exact(width.max(1))
+    }
+
+    /// Iterate over the points of one column, from top to bottom
+    ///
+    /// The iterator is empty if the column is out of bounds.
+    pub fn column(&self, column: usize) -> std::iter::StepBy<std::slice::Iter<'a, P>> {
+        let (width, _) = self.image_size();
+        let points = if column < width {
+            &self.points[column..]
+        } else {
+            &[]
+        };
+        points.iter().step_by(width.max(1))
+    }
+
+    /// Iterate over the columns of the point grid
+    pub fn columns(
+        &self,
+    ) -> impl Iterator<Item = std::iter::StepBy<std::slice::Iter<'a, P>>> + use<'a, P> {
+        let view = *self;
+        (0..self.image_size().0).map(move |column| view.column(column))
+    }
+
+    /// Iterate over the square neighborhood of a point
+    ///
+    /// Yields the index and point of every cell within `radius` rows and
+    /// columns of `(row, column)`, including the center, clipped to the
+    /// borders of the grid. The iterator is empty if the center is out of
+    /// bounds.
+    pub fn window(
+        &self,
+        row: usize,
+        column: usize,
+        radius: usize,
+    ) -> impl Iterator<Item = (usize, &'a P)> + use<'a, P> {
+        let (width, height) = self.image_size();
+        let (rows, columns) = if row < height && column < width {
+            (
+                row.saturating_sub(radius)
+                    ..row.saturating_add(radius).saturating_add(1).min(height),
+                column.saturating_sub(radius)
+                    ..column.saturating_add(radius).saturating_add(1).min(width),
+            )
+        } else {
+            (0..0, 0..0)
+        };
+        let points = self.points;
+        rows.flat_map(move |r| columns.clone().map(move |c| r * width + c))
+            .map(move |index| (index, &points[index]))
+    }
+
+    /// Copy a rectangular part of the point grid into a new cloud
+    ///
+    /// The metadata is kept apart from the new width and height. The crop is
+    /// organized only if the source cloud is.
+    pub fn crop_image(&self, rows: Range<usize>, columns: Range<usize>) -> Result<PointCloud<P>> {
+        let (width, height) = self.image_size();
+        if rows.start > rows.end
+            || columns.start > columns.end
+            || rows.end > height
+            || columns.end > width
+        {
+            return Err(CloudError::invalid_parameter(format!(
+                "Crop of rows {:?} and columns {:?} exceeds the {}x{} point grid",
+                rows, columns, width, height
+            )));
+        }
+
+        let mut points = Vec::with_capacity(rows.len() * columns.len());
+        for row in rows.clone() {
+            points.extend_from_slice(
+                &self.points[row * width + columns.start..row * width + columns.end],
+            );
+        }
+
+        let organized = self.metadata.is_organized
+            && (self.metadata.width as usize, self.metadata.height as usize) == (width, height);
+        let mut metadata = self.metadata.clone();
+        metadata.width = columns.len() as u32;
+        metadata.height = rows.len() as u32;
+        metadata.is_organized = organized;
+        Ok(PointCloud::from_points_and_metadata(points, metadata))
+    }
+}
+
 impl<'a, P: Point> Clone for PointCloudView<'a, P> {
     fn clone(&self) -> Self {
-        Self {
-            points: self.points,
-            metadata: self.metadata,
-        }
+        *self
     }
 }
 
@@ -198,7 +326,7 @@ impl<'a, P: Point> IntoIterator for &PointCloudView<'a, P> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::core::{PointCloud, PointXYZ};
+    use crate::core::PointXYZ;
 
     #[test]
     fn test_point_cloud_view_creation() {
@@ -241,4 +369,45 @@ mod tests {
         let count = view.count_where(|p| p.x() >= 1.0);
         assert_eq!(count, 2);
     }
+
+    #[test]
+    fn test_organized_access() {
+        let points = (0..12).map(|i| PointXYZ::new(i as f32, 0.0, 0.0)).collect();
+        let cloud = PointCloud::from_points_and_metadata(points, Metadata::new_organized(4, 3));
+        let view = cloud.view();
+
+        assert_eq!(view.image_size(), (4, 3));
+        assert_eq!(view.at(2, 1).unwrap().x, 9.0);
+        assert!(view.at(3, 0).is_none());
+        assert!(view.at(0, 4).is_none());
+        assert_eq!(view.coordinates_of(9), Some((2, 1)));
+        assert_eq!(view.row(1).unwrap()[0].x, 4.0);
+        assert_eq!(view.rows().count(), 3);
+
+        let column: Vec<f32> = view.column(2).map(|p| p.x).collect();
+        assert_eq!(column, [2.0, 6.0, 10.0]);
+        assert_eq!(view.column(4).count(), 0);
+        assert_eq!(view.columns().count(), 4);
+
+        let window: Vec<usize> = view.window(0, 3, 1).map(|(i, _)| i).collect();
+        assert_eq!(window, [2, 3, 6, 7]);
+        assert_eq!(view.window(1, 1, 1).count(), 9);
+
+        let crop = view.crop_image(1..3, 1..3).unwrap();
+        assert_eq!((crop.metadata().width, crop.metadata().height), (2, 2));
+        assert!(crop.metadata().is_organized);
+        let xs: Vec<f32> = crop.iter().map(|p| p.x).collect();
+        assert_eq!(xs, [5.0, 6.0, 9.0, 10.0]);
+        assert!(view.crop_image(0..4, 0..1).is_err());
+
+        // Unorganized clouds are a single row
+        let unorganized = PointCloud::from_points(vec![PointXYZ::new(1.0, 2.0, 3.0); 5]);
+        assert_eq!(unorganized.image_size(), (5, 1));
+        assert!(unorganized.at(0, 4).is_some());
+        assert!(unorganized.at(1, 0).is_none());
+        assert_eq!(unorganized.view().window(0, 2, usize::MAX).count(), 5);
+        let crop = unorganized.view().crop_image(0..1, 1..3).unwrap();
+        assert_eq!((crop.metadata().width, crop.metadata().height), (2, 1));
+        assert!(!crop.metadata().is_organized);
+    }
 }
@@ -0,0 +1,541 @@
+//! E57 file format support
+//!
+//! This module reads ASTM E57 files, the vendor-neutral exchange format of
+//! terrestrial laser scanners. An E57 file holds an XML section describing
+//! any number of scans plus binary sections with their points; both are
+//! decoded through the `e57` crate.
+//!
+//! Every scan becomes one point cloud. Spherical coordinates are converted
+//! to Cartesian ones, intensities are kept as stored and colors are scaled
+//! to 8 bits. Scans recorded on a row/column grid are loaded as organized
+//! clouds with invalid or missing cells set to NaN.
+
+use super::PointCloudReader;
+use crate::core::{FieldDescriptor, Metadata, PointCloud, PointFields};
+use crate::error::{CloudError, Result};
+use e57::{CartesianCoordinate, E57Reader};
+use std::io::{Read, Seek};
+use std::path::Path;
+
+/// File signature at the start of every E57 file
+const E57_SIGNATURE: &[u8; 8] = b"ASTM-E57";
+
+/// Largest number of grid cells per point of an organized scan; sparser
+/// grids are rejected rather than allocating mostly empty cells
+const MAX_CELLS_PER_POINT: usize = 16;
+
+/// Options for reading E57 scans
+#[derive(Clone, Debug, PartialEq)]
+pub struct E57Config {
+    /// Transform points into the file's coordinate system
+    ///
+    /// When disabled, points stay in the local frame of their scan and the
+    /// scan pose is stored in [`Metadata::sensor_origin`] and
+    /// [`Metadata::sensor_orientation`]. When enabled, the pose is applied
+    /// to the points and the metadata keeps the identity pose.
+    pub apply_pose: bool,
+
+    /// Load scans that carry row and column indices as organized clouds
+    ///
+    /// Fails for grids with more than 16 cells per point.
+    pub organized: bool,
+}
+
+impl Default for E57Config {
+    fn default() -> Self {
+        Self {
+            apply_pose: false,
+            organized: true,
+        }
+    }
+}
+
+/// Load all points of an E57 file into one point cloud
+///
+/// The poses of all scans are applied, so the points of every scan are in
+/// the file's coordinate system. A file with a single grid scan keeps its
+/// organization; the points of several scans are concatenated into an
+/// unorganized cloud.
+///
+/// # Example
+/// ```rust,no_run
+/// use ferrum_cloud::io::e57::load_e57;
+/// use ferrum_cloud::prelude::*;
+///
+/// let cloud: PointCloud<PointXYZRGB> = load_e57("site.e57")?;
+/// # Ok::<(), CloudError>(())
+/// ```
+pub fn load_e57<T: PointFields, P: AsRef<Path>>(path: P) -> Result<PointCloud<T>> {
+    let mut points = Vec::new();
+    let metadata = read_e57_rows(path.as_ref(), T::fields(), &mut |values| {
+        points.push(T::from_values(values))
+    })?;
+    Ok(PointCloud::from_points_and_metadata(points, metadata))
+}
+
+/// Load every scan of an E57 file as a separate point cloud
+///
+/// Points stay in the local frame of their scan, with the scan pose stored
+/// in the metadata. See [`load_e57_scans_with_config`].
+pub fn load_e57_scans<T: PointFields, P: AsRef<Path>>(path: P) -> Result<Vec<PointCloud<T>>> {
+    load_e57_scans_with_config(path, &E57Config::default())
+}
+
+/// Load every scan of an E57 file as a separate point cloud with custom options
+///
+/// The scan's name and sensor description, when present, are stored as the
+/// custom metadata fields `name`, `sensor_vendor`, `sensor_model` and
+/// `sensor_serial`.
+pub fn load_e57_scans_with_config<T: PointFields, P: AsRef<Path>>(
+    path: P,
+    config: &E57Config,
+) -> Result<Vec<PointCloud<T>>> {
+    let fields = T::fields();
+    let mut reader = E57Reader::from_file(path.as_ref()).map_err(e57_error)?;
+    let file_size = std::fs::metadata(path.as_ref())?.len();
+
+    let mut clouds = Vec::new();
+    for scan in reader.pointclouds() {
+        let (metadata, values) = read_scan(&mut reader, &scan, fields, config, file_size)?;
+        let points = values
+            .chunks_exact(fields.len().max(1))
+            .map(T::from_values)
+            .collect();
+        clouds.push(PointCloud::from_points_and_metadata(points, metadata));
+    }
+    Ok(clouds)
+}
+
+/// E57 format for the [`io::load`](super::load) registry
+///
+/// Loads all scans in file coordinates, like [`load_e57`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct E57Format;
+
+impl PointCloudReader for E57Format {
+    fn name(&self) -> &str {
+        "e57"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["e57"]
+    }
+
+    fn matches_magic(&self, bytes: &[u8]) -> bool {
+        bytes.starts_with(E57_SIGNATURE)
+    }
+
+    fn read(
+        &self,
+        path: &Path,
+        fields: &[FieldDescriptor],
+        sink: &mut dyn FnMut(&[f64]),
+    ) -> Result<Metadata> {
+        read_e57_rows(path, fields, sink)
+    }
+}
+
+/// Point attribute a requested field is taken from
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum FieldSource {
+    X,
+    Y,
+    Z,
+    Intensity,
+    Red,
+    Green,
+    Blue,
+}
+
+/// Read all scans with their poses applied, returning the merged metadata
+fn read_e57_rows(
+    path: &Path,
+    fields: &[FieldDescriptor],
+    sink: &mut dyn FnMut(&[f64]),
+) -> Result<Metadata> {
+    let mut reader = E57Reader::from_file(path).map_err(e57_error)?;
+    let file_size = std::fs::metadata(path)?.len();
+    let scans = reader.pointclouds();
+    let config = E57Config {
+        apply_pose: true,
+        organized: scans.len() == 1,
+    };
+
+    let mut merged = None;
+    let mut count = 0;
+    for scan in &scans {
+        let (metadata, values) = read_scan(&mut reader, scan, fields, &config, file_size)?;
+        for row in values.chunks_exact(fields.len().max(1)) {
+            sink(row);
+        }
+        count += metadata.point_count();
+        merged = Some(metadata);
+    }
+
+    Ok(match merged {
+        Some(metadata) if scans.len() == 1 => metadata,
+        _ => Metadata::new_unorganized(count).with_custom_field("scans", scans.len().to_string()),
+    })
+}
+
+/// Read one scan as rows of field values laid out one point after another
+///
+/// `file_size` bounds the memory reserved up front, since the record count
+/// in the XML section is not checked against the binary data.
+fn read_scan<R: Read + Seek>(
+    reader: &mut E57Reader<R>,
+    scan: &e57::PointCloud,
+    fields: &[FieldDescriptor],
+    config: &E57Config,
+    file_size: u64,
+) -> Result<(Metadata, Vec<f64>)> {
+    let sources = resolve_field_sources(scan, fields)?;
+    let stride = fields.len();
+
+    let mut points = reader.pointcloud_simple(scan).map_err(e57_error)?;
+    points.apply_pose(config.apply_pose);
+    points.spherical_to_cartesian(true);
+    points.intensity_to_color(false);
+    points.normalize_intensity(false);
+    points.normalize_color(true);
+
+    let grid = config.organized && scan.has_row_column();
+    // Every record takes at least a byte of the binary section
+    let section_size = file_size.saturating_sub(scan.file_offset);
+    let capacity = scan.records.min(section_size) as usize;
+    let mut values = Vec::with_capacity(capacity.saturating_mul(stride));
+    let mut cells = Vec::new();
+    for point in points {
+        let point = point.map_err(e57_error)?;
+        let (x, y, z) = match point.cartesian {
+            CartesianCoordinate::Valid { x, y, z } => (x, y, z),
+            _ => (f64::NAN, f64::NAN, f64::NAN),
+        };
+        let color = point.color.as_ref();
+        for source in &sources {
+            values.push(match source {
+                FieldSource::X => x,
+                FieldSource::Y => y,
+                FieldSource::Z => z,
+                FieldSource::Intensity => point.intensity.map_or(f64::NAN, f64::from),
+                FieldSource::Red => color.map_or(0.0, |c| unit_to_u8(c.red)),
+                FieldSource::Green => color.map_or(0.0, |c| unit_to_u8(c.green)),
+                FieldSource::Blue => color.map_or(0.0, |c| unit_to_u8(c.blue)),
+            });
+        }
+        if grid {
+            cells.push((point.row, point.column));
+        }
+    }
+
+    let mut metadata = if grid && !cells.is_empty() {
+        let (metadata, organized) = organize(&cells, &values, &sources)?;
+        values = organized;
+        metadata
+    } else {
+        Metadata::new_unorganized(values.len() / stride.max(1))
+    };
+
+    if !config.apply_pose
+        && let Some(transform) = &scan.transform
+    {
+        let t = &transform.translation;
+        let q = &transform.rotation;
+        metadata = metadata
+            .with_sensor_origin([t.x as f32, t.y as f32, t.z as f32])
+            .with_sensor_orientation([q.w as f32, q.x as f32, q.y as f32, q.z as f32]);
+    }
+
+    let descriptions = [
+        ("name", &scan.name),
+        ("sensor_vendor", &scan.sensor_vendor),
+        ("sensor_model", &scan.sensor_model),
+        ("sensor_serial", &scan.sensor_serial),
+    ];
+    for (key, value) in descriptions {
+        if let Some(value) = value {
+            metadata = metadata.with_custom_field(key, value.as_str());
+        }
+    }
+
+    Ok((metadata, values))
+}
+
+/// Place the rows of a grid scan at their row and column
+///
+/// Cells without a point are filled with NaN coordinates. When several
+/// points share a cell (multiple returns), the first one is kept.
+fn organize(
+    cells: &[(i64, i64)],
+    values: &[f64],
+    sources: &[FieldSource],
+) -> Result<(Metadata, Vec<f64>)> {
+    let stride = sources.len();
+    let (mut row_min, mut row_max) = (i64::MAX, i64::MIN);
+    let (mut column_min, mut column_max) = (i64::MAX, i64::MIN);
+    for &(row, column) in cells {
+        row_min = row_min.min(row);
+        row_max = row_max.max(row);
+        column_min = column_min.min(column);
+        column_max = column_max.max(column);
+    }
+
+    let extent = |min: i64, max: i64| {
+        max.checked_sub(min)
+            .and_then(|d| d.checked_add(1))
+            .and_then(|d| u32::try_from(d).ok())
+    };
+    let (width, height) = match (extent(column_min, column_max), extent(row_min, row_max)) {
+        (Some(width), Some(height))
+            if (width as usize)
+                .checked_mul(height as usize)
+                .is_some_and(|cells| {
+                    cells / MAX_CELLS_PER_POINT <= values.len() / stride.max(1)
+                }) =>
+        {
+            (width, height)
+        }
+        _ => {
+            return Err(CloudError::format_error(format!(
+                "E57 grid of rows {}..={} and columns {}..={} is too large for {} points",
+                row_min,
+                row_max,
+                column_min,
+                column_max,
+                cells.len()
+            )));
+        }
+    };
+
+    let empty: Vec<f64> = sources
+        .iter()
+        .map(|source| match source {
+            FieldSource::X | FieldSource::Y | FieldSource::Z | FieldSource::Intensity => f64::NAN,
+            _ => 0.0,
+        })
+        .collect();
+    let cell_count = width as usize * height as usize;
+    let mut grid = empty.repeat(cell_count);
+    let mut filled = vec![false; cell_count];
+
+    for (&(row, column), point) in cells.iter().zip(values.chunks_exact(stride.max(1))) {
+        let cell = (row - row_min) as usize * width as usize + (column - column_min) as usize;
+        if !filled[cell] {
+            filled[cell] = true;
+            grid[cell * stride..(cell + 1) * stride].copy_from_slice(point);
+        }
+    }
+
+    Ok((Metadata::new_organized(width, height), grid))
+}
+
+/// Match the requested fields against the attributes of a scan
+fn resolve_field_sources(
+    scan: &e57::PointCloud,
+    fields: &[FieldDescriptor],
+) -> Result<Vec<FieldSource>> {
+    let has_coordinates = scan.has_cartesian() || scan.has_spherical();
+    let mut sources = Vec::with_capacity(fields.len());
+    let mut missing = Vec::new();
+
+    for field in fields {
+        let source = match field.name {
+            "x" if has_coordinates => Some(FieldSource::X),
+            "y" if has_coordinates => Some(FieldSource::Y),
+            "z" if has_coordinates => Some(FieldSource::Z),
+            "intensity" if scan.has_intensity() => Some(FieldSource::Intensity),
+            "r" if scan.has_color() => Some(FieldSource::Red),
+            "g" if scan.has_color() => Some(FieldSource::Green),
+            "b" if scan.has_color() => Some(FieldSource::Blue),
+            _ => None,
+        };
+        match source {
+            Some(source) => sources.push(source),
+            None => missing.push(field.name),
+        }
+    }
+
+    if !missing.is_empty() {
+        return Err(CloudError::format_error(format!(
+            "E57 scan is missing required fields: {}",
+            missing.join(", ")
+        )));
+    }
+    Ok(sources)
+}
+
+/// Scale a normalized color channel to 0..=255
+fn unit_to_u8(value: f32) -> f64 {
+    (f64::from(value) * 255.0).round().clamp(0.0, 255.0)
+}
+
+fn e57_error(error: e57::Error) -> CloudError {
+    CloudError::format_error(format!("Invalid E57 file: {}", error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{Point, PointXYZ, PointXYZI, PointXYZRGB};
+    use e57::{
+        E57Writer, Quaternion, Record, RecordDataType, RecordName, RecordValue, Transform,
+        Translation,
+    };
+    use tempfile::NamedTempFile;
+
+    fn index_record(name: RecordName) -> Record {
+        Record {
+            name,
+            data_type: RecordDataType::U8,
+        }
+    }
+
+    /// A 2x2 grid scan with one missing cell and a spherical scan
+    fn write_scans(path: &Path) {
+        let mut writer = E57Writer::from_file(path, "file-guid").unwrap();
+
+        let prototype = vec![
+            Record::CARTESIAN_X_F64,
+            Record::CARTESIAN_Y_F64,
+            Record::CARTESIAN_Z_F64,
+            Record::INTENSITY_U16,
+            Record::COLOR_RED_U8,
+            Record::COLOR_GREEN_U8,
+            Record::COLOR_BLUE_U8,
+            index_record(RecordName::RowIndex),
+            index_record(RecordName::ColumnIndex),
+        ];
+        let mut grid = writer.add_pointcloud("grid-guid", prototype).unwrap();
+        grid.set_name(Some("grid".to_string()));
+        grid.set_transform(Some(Transform {
+            rotation: Quaternion {
+                w: 0.0,
+                x: 0.0,
+                y: 0.0,
+                z: 1.0,
+            },
+            translation: Translation {
+                x: 10.0,
+                y: 0.0,
+                z: 0.0,
+            },
+        }));
+        for (row, column, x) in [(0, 0, 1.0), (0, 1, 2.0), (1, 1, 3.0)] {
+            grid.add_point(vec![
+                RecordValue::Double(x),
+                RecordValue::Double(0.0),
+                RecordValue::Double(0.5),
+                RecordValue::Integer(100 * column + 7),
+                RecordValue::Integer(255),
+                RecordValue::Integer(0),
+                RecordValue::Integer(128),
+                RecordValue::Integer(row),
+                RecordValue::Integer(column),
+            ])
+            .unwrap();
+        }
+        grid.finalize().unwrap();
+
+        let prototype = vec![
+            Record::SPHERICAL_RANGE_F64,
+            Record::SPHERICAL_AZIMUTH_F64,
+            Record::SPHERICAL_ELEVATION_F64,
+        ];
+        let mut spherical = writer.add_pointcloud("spherical-guid", prototype).unwrap();
+        spherical
+            .add_point(vec![
+                RecordValue::Double(2.0),
+                RecordValue::Double(std::f64::consts::FRAC_PI_2),
+                RecordValue::Double(0.0),
+            ])
+            .unwrap();
+        spherical.finalize().unwrap();
+
+        writer.finalize().unwrap();
+    }
+
+    fn assert_close(actual: [f32; 3], expected: [f32; 3]) {
+        for (a, e) in actual.iter().zip(expected.iter()) {
+            assert!((a - e).abs() < 1e-5, "{:?} != {:?}", actual, expected);
+        }
+    }
+
+    #[test]
+    fn test_load_scans() {
+        let file = NamedTempFile::new().unwrap();
+        write_scans(file.path());
+
+        let scans: Vec<PointCloud<PointXYZ>> = load_e57_scans(file.path()).unwrap();
+        assert_eq!(scans.len(), 2);
+
+        let grid = &scans[0];
+        let metadata = grid.metadata();
+        assert!(metadata.is_organized);
+        assert_eq!((metadata.width, metadata.height), (2, 2));
+        assert_eq!(metadata.sensor_origin, [10.0, 0.0, 0.0]);
+        assert_eq!(metadata.sensor_orientation, [0.0, 0.0, 0.0, 1.0]);
+        assert_eq!(metadata.custom_fields.get("name").unwrap(), "grid");
+        assert_eq!(grid.get(1).unwrap().position(), [2.0, 0.0, 0.5]);
+        assert!(grid.get(2).unwrap().x.is_nan());
+        assert_eq!(grid.get(3).unwrap().position(), [3.0, 0.0, 0.5]);
+
+        assert_close(scans[1].get(0).unwrap().position(), [0.0, 2.0, 0.0]);
+    }
+
+    #[test]
+    fn test_attributes_and_missing_fields() {
+        let file = NamedTempFile::new().unwrap();
+        write_scans(file.path());
+
+        // The spherical scan has no colors
+        let error = load_e57_scans::<PointXYZRGB, _>(file.path()).unwrap_err();
+        assert!(error.to_string().contains("r, g, b"));
+
+        let config = E57Config {
+            apply_pose: true,
+            organized: false,
+        };
+        let mut reader = E57Reader::from_file(file.path()).unwrap();
+        let grid = reader.pointclouds().remove(0);
+        let (metadata, values) =
+            read_scan(&mut reader, &grid, PointXYZRGB::fields(), &config, u64::MAX).unwrap();
+        assert!(!metadata.is_organized);
+        assert_eq!(metadata.sensor_origin, [0.0, 0.0, 0.0]);
+        let points: Vec<PointXYZRGB> = values
+            .chunks_exact(6)
+            .map(PointXYZRGB::from_values)
+            .collect();
+        assert_eq!(points.len(), 3);
+        assert_close(points[0].position(), [9.0, 0.0, 0.5]);
+        assert_eq!(points[0].color(), Some([255, 0, 128]));
+
+        let (_, values) =
+            read_scan(&mut reader, &grid, PointXYZI::fields(), &config, u64::MAX).unwrap();
+        assert_eq!(values[3], 7.0);
+        assert_eq!(values[7], 107.0);
+    }
+
+    #[test]
+    fn test_load_merged() {
+        let file = NamedTempFile::new().unwrap();
+        write_scans(file.path());
+
+        let cloud: PointCloud<PointXYZ> = super::super::load(file.path()).unwrap();
+        assert_eq!(cloud.len(), 4);
+        assert!(!cloud.metadata().is_organized);
+        assert_close(cloud.get(0).unwrap().position(), [9.0, 0.0, 0.5]);
+        assert_close(cloud.get(3).unwrap().position(), [0.0, 2.0, 0.0]);
+    }
+
+    #[test]
+    fn test_organize_rejects_sparse_grids() {
+        let sources = [FieldSource::X, FieldSource::Y, FieldSource::Z];
+        let values = [0.0; 6];
+        let (metadata, grid) = organize(&[(0, 0), (1, 3)], &values, &sources).unwrap();
+        assert_eq!((metadata.width, metadata.height), (4, 2));
+        assert_eq!(grid.len(), 8 * 3);
+
+        assert!(organize(&[(0, 0), (100_000, 100_000)], &values, &sources).is_err());
+        assert!(organize(&[(i64::MIN, 0), (i64::MAX, 0)], &values, &sources).is_err());
+    }
+}
@@ -2,32 +2,1278 @@
 //!
 //! This module provides functionality for reading and writing LAS files,
 //! commonly used for LiDAR point cloud data.
+//!
+//! LAS versions 1.0 to 1.4 with point data record formats 0 to 10 are
+//! supported. LAZ (LASzip compressed LAS) files are handled through the
+//! `laz` crate and are detected from the file header when loading, or
+//! selected by the `.laz` extension when saving.
 
-use crate::core::{Point, PointCloud, PointXYZ};
+use super::{PointCloudReader, PointCloudWriter, PointRows};
+use crate::core::{
+    FieldDescriptor, Metadata, Point, PointCloud, PointMut, PointXYZ, PointXYZRGB,
+    PointXYZRGBNormal,
+};
 use crate::error::{CloudError, Result};
+use byteorder::{ByteOrder, LittleEndian};
+use laz::{LasZipCompressor, LasZipDecompressor, LazVlr, LazVlrBuilder};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
 use std::path::Path;
 
-/// Load a point cloud from a LAS file
-pub fn load_las<P: AsRef<Path>>(_path: P) -> Result<PointCloud<PointXYZ>> {
-    // TODO: Implement LAS file reading
-    // This would require parsing the binary LAS format
-    Err(CloudError::format_error("LAS format not yet implemented"))
+/// File signature of every LAS file
+const LAS_SIGNATURE: &[u8; 4] = b"LASF";
+
+/// Size of a variable length record header
+const VLR_HEADER_SIZE: usize = 54;
+
+/// User ID and record ID of the VLR describing LASzip compression
+const LASZIP_USER_ID: &str = "laszip encoded";
+const LASZIP_RECORD_ID: u16 = 22204;
+
+/// Global encoding bit marking the coordinate reference system as WKT,
+/// required by LAS 1.4 for point formats 6-10
+const GLOBAL_ENCODING_WKT: u16 = 1 << 4;
+
+/// Number of LAZ points decompressed at a time
+const LAZ_BATCH_POINTS: usize = 50_000;
+
+/// Point carrying the attributes of a LAS point data record
+///
+/// Coordinates are stored in double precision so that georeferenced
+/// coordinates survive a load/save cycle unchanged. Attributes that only
+/// exist in some point data record formats are optional.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LasPoint {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+
+    /// Pulse return magnitude
+    pub intensity: u16,
+
+    /// Return number of this point within its pulse (1-based)
+    pub return_number: u8,
+
+    /// Total number of returns of the pulse
+    pub number_of_returns: u8,
+
+    /// Direction of the scan mirror when the point was recorded
+    pub scan_direction_flag: bool,
+
+    /// Whether the point is at the end of a scan line
+    pub edge_of_flight_line: bool,
+
+    /// ASPRS classification code
+    pub classification: u8,
+
+    /// Bit 0: synthetic, bit 1: key-point, bit 2: withheld, bit 3: overlap
+    pub classification_flags: u8,
+
+    /// Scanner channel of multi-channel systems (formats 6-10)
+    pub scanner_channel: u8,
+
+    /// Scan angle in degrees
+    pub scan_angle: f32,
+
+    /// User-defined byte
+    pub user_data: u8,
+
+    /// Flight line or source the point originates from
+    pub point_source_id: u16,
+
+    /// GPS time of the point (formats 1 and 3-10)
+    pub gps_time: Option<f64>,
+
+    /// 16-bit red, green and blue (formats 2, 3, 5, 7, 8 and 10)
+    pub rgb: Option<[u16; 3]>,
+
+    /// 16-bit near infrared (formats 8 and 10)
+    pub nir: Option<u16>,
+}
+
+impl LasPoint {
+    /// Create a new LasPoint at the given position with default attributes
+    pub fn new(x: f64, y: f64, z: f64) -> Self {
+        Self {
+            x,
+            y,
+            z,
+            ..Self::default()
+        }
+    }
+
+    /// Set the 16-bit color of the point
+    pub fn with_rgb(mut self, rgb: [u16; 3]) -> Self {
+        self.rgb = Some(rgb);
+        self
+    }
+
+    /// Set the GPS time of the point
+    pub fn with_gps_time(mut self, gps_time: f64) -> Self {
+        self.gps_time = Some(gps_time);
+        self
+    }
+
+    /// Value of an attribute by its point field name
+    ///
+    /// Colors are reported as 8-bit `r`, `g` and `b` and the classification
+    /// is also available as `label`. Returns `None` for unknown names and
+    /// for optional attributes the point does not carry.
+    pub fn field(&self, name: &str) -> Option<f64> {
+        match name {
+            "x" => Some(self.x),
+            "y" => Some(self.y),
+            "z" => Some(self.z),
+            "intensity" => Some(self.intensity as f64),
+            "r" => self.rgb.map(|c| (c[0] >> 8) as f64),
+            "g" => self.rgb.map(|c| (c[1] >> 8) as f64),
+            "b" => self.rgb.map(|c| (c[2] >> 8) as f64),
+            "classification" | "label" => Some(self.classification as f64),
+            "return_number" => Some(self.return_number as f64),
+            "number_of_returns" => Some(self.number_of_returns as f64),
+            "scan_angle" => Some(self.scan_angle as f64),
+            "user_data" => Some(self.user_data as f64),
+            "point_source_id" => Some(self.point_source_id as f64),
+            "gps_time" => self.gps_time,
+            "nir" => self.nir.map(f64::from),
+            _ => None,
+        }
+    }
+
+    /// Set an attribute by its point field name, see [`LasPoint::field`]
+    ///
+    /// Returns `false` if the name does not correspond to a LAS attribute.
+    pub fn set_field(&mut self, name: &str, value: f64) -> bool {
+        let channel = |rgb: &mut Option<[u16; 3]>, index: usize| {
+            rgb.get_or_insert([0; 3])[index] = (value as u8) as u16 * 257;
+        };
+        match name {
+            "x" => self.x = value,
+            "y" => self.y = value,
+            "z" => self.z = value,
+            "intensity" => self.intensity = value as u16,
+            "r" => channel(&mut self.rgb, 0),
+            "g" => channel(&mut self.rgb, 1),
+            "b" => channel(&mut self.rgb, 2),
+            "classification" | "label" => self.classification = value as u8,
+            "return_number" => self.return_number = value as u8,
+            "number_of_returns" => self.number_of_returns = value as u8,
+            "scan_angle" => self.scan_angle = value as f32,
+            "user_data" => self.user_data = value as u8,
+            "point_source_id" => self.point_source_id = value as u16,
+            "gps_time" => self.gps_time = Some(value),
+            "nir" => self.nir = Some(value as u16),
+            _ => return false,
+        }
+        true
+    }
+}
+
+impl Point for LasPoint {
+    fn position(&self) -> [f32; 3] {
+        [self.x as f32, self.y as f32, self.z as f32]
+    }
+
+    fn color(&self) -> Option<[u8; 3]> {
+        self.rgb
+            .map(|[r, g, b]| [(r >> 8) as u8, (g >> 8) as u8, (b >> 8) as u8])
+    }
+}
+
+impl PointMut for LasPoint {
+    fn set_position(&mut self, position: [f32; 3]) {
+        self.x = position[0] as f64;
+        self.y = position[1] as f64;
+        self.z = position[2] as f64;
+    }
+
+    fn from_position(position: [f32; 3]) -> Self {
+        Self::new(position[0] as f64, position[1] as f64, position[2] as f64)
+    }
+}
+
+impl Default for LasPoint {
+    fn default() -> Self {
+        Self {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+            intensity: 0,
+            return_number: 1,
+            number_of_returns: 1,
+            scan_direction_flag: false,
+            edge_of_flight_line: false,
+            classification: 0,
+            classification_flags: 0,
+            scanner_channel: 0,
+            scan_angle: 0.0,
+            user_data: 0,
+            point_source_id: 0,
+            gps_time: None,
+            rgb: None,
+            nir: None,
+        }
+    }
+}
+
+impl From<PointXYZ> for LasPoint {
+    fn from(point: PointXYZ) -> Self {
+        Self::new(point.x as f64, point.y as f64, point.z as f64)
+    }
+}
+
+impl From<PointXYZRGB> for LasPoint {
+    fn from(point: PointXYZRGB) -> Self {
+        Self::new(point.x as f64, point.y as f64, point.z as f64)
+            .with_rgb(widen_color([point.r, point.g, point.b]))
+    }
+}
+
+impl From<PointXYZRGBNormal> for LasPoint {
+    fn from(point: PointXYZRGBNormal) -> Self {
+        Self::new(point.x as f64, point.y as f64, point.z as f64)
+            .with_rgb(widen_color([point.r, point.g, point.b]))
+    }
+}
+
+/// Scale an 8-bit color to the full 16-bit LAS range
+fn widen_color([r, g, b]: [u8; 3]) -> [u16; 3] {
+    [r as u16 * 257, g as u16 * 257, b as u16 * 257]
+}
+
+/// Public header block of a LAS file
+#[derive(Clone, Debug, PartialEq)]
+pub struct LasHeader {
+    /// Format version as (major, minor)
+    pub version: (u8, u8),
+
+    /// Point data record format (0-10)
+    pub point_format: u8,
+
+    /// Size of one point record in bytes, including extra bytes
+    pub point_record_length: u16,
+
+    /// Number of point records
+    pub point_count: u64,
+
+    /// Number of points by return number (1-15)
+    pub points_by_return: [u64; 15],
+
+    /// Scale factors applied to the integer coordinates
+    pub scale: [f64; 3],
+
+    /// Offsets added to the scaled coordinates
+    pub offset: [f64; 3],
+
+    /// Minimum coordinates of all points
+    pub min: [f64; 3],
+
+    /// Maximum coordinates of all points
+    pub max: [f64; 3],
+
+    /// Flight line or source ID of the file
+    pub file_source_id: u16,
+
+    /// Global encoding bit field
+    pub global_encoding: u16,
+
+    /// Hardware or process that created the data
+    pub system_identifier: String,
+
+    /// Software that wrote the file
+    pub generating_software: String,
+
+    /// Day of the year the file was created
+    pub creation_day: u16,
+
+    /// Year the file was created
+    pub creation_year: u16,
+
+    /// Whether the point records are LASzip compressed
+    pub compressed: bool,
+
+    /// Byte offset of the first point record
+    offset_to_point_data: u32,
+
+    /// LASzip description, if the file is compressed
+    laszip_vlr: Option<Vec<u8>>,
+}
+
+/// Options for writing LAS and LAZ files
+#[derive(Clone, Debug, Default)]
+pub struct LasWriteOptions {
+    /// Point data record format; `None` picks the smallest format that
+    /// holds the attributes of the first point
+    pub point_format: Option<u8>,
+
+    /// Coordinate scale; `None` uses the scale stored in the cloud's
+    /// metadata by `load_las`, or 0.001
+    pub scale: Option<[f64; 3]>,
+
+    /// Coordinate offset; `None` uses the offset stored in the cloud's
+    /// metadata by `load_las`, or the floored minimum of the points
+    pub offset: Option<[f64; 3]>,
+
+    /// Compress the points with LASzip; `None` compresses when the file
+    /// extension is `.laz`
+    pub compress: Option<bool>,
+}
+
+/// Read only the header of a LAS or LAZ file
+pub fn read_las_header<P: AsRef<Path>>(path: P) -> Result<LasHeader> {
+    let bytes = std::fs::read(path.as_ref())?;
+    parse_header(&bytes)
+}
+
+/// Load a point cloud from a LAS or LAZ file
+///
+/// The header's version, point format, scale and offset are stored in the
+/// cloud's metadata custom fields (`las_version`, `las_point_format`,
+/// `las_scale`, `las_offset`) so that `save_las` can reproduce them.
+pub fn load_las<P: AsRef<Path>>(path: P) -> Result<PointCloud<LasPoint>> {
+    let bytes = std::fs::read(path.as_ref())?;
+    let header = parse_header(&bytes)?;
+
+    let record_length = header.point_record_length as usize;
+    let point_count = header.point_count as usize;
+    let base_length = format_length(header.point_format)? as usize;
+    if record_length < base_length {
+        return Err(CloudError::format_error(format!(
+            "Point record length {} is too short for point format {}",
+            record_length, header.point_format
+        )));
+    }
+
+    let total_size = point_count.checked_mul(record_length).ok_or_else(|| {
+        CloudError::format_error(format!("LAS point count {} is too large", point_count))
+    })?;
+
+    let records = match &header.laszip_vlr {
+        Some(vlr_data) => {
+            let vlr = LazVlr::from_buffer(vlr_data)
+                .map_err(|e| CloudError::format_error(format!("Invalid LASzip VLR: {}", e)))?;
+            let mut source = Cursor::new(bytes.as_slice());
+            source.set_position(header.offset_to_point_data as u64);
+
+            let mut decompressor = LasZipDecompressor::new(source, vlr)
+                .map_err(|e| CloudError::format_error(format!("Failed to open LAZ data: {}", e)))?;
+            // The point count comes from the header, so the buffer only
+            // grows as points are actually decompressed
+            let mut records = Vec::new();
+            let mut remaining = total_size;
+            while remaining > 0 {
+                let batch = remaining.min(LAZ_BATCH_POINTS * record_length);
+                let start = records.len();
+                records.resize(start + batch, 0);
+                decompressor
+                    .decompress_many(&mut records[start..])
+                    .map_err(|e| {
+                        CloudError::format_error(format!("Failed to decompress LAZ: {}", e))
+                    })?;
+                remaining -= batch;
+            }
+            std::borrow::Cow::Owned(records)
+        }
+        None => {
+            let start = header.offset_to_point_data as usize;
+            let records = start
+                .checked_add(total_size)
+                .and_then(|end| bytes.get(start..end))
+                .ok_or_else(|| {
+                    CloudError::format_error("LAS file is shorter than its point records")
+                })?;
+            std::borrow::Cow::Borrowed(records)
+        }
+    };
+
+    let points: Vec<LasPoint> = records
+        .par_chunks_exact(record_length)
+        .map(|record| decode_point(record, header.point_format, &header.scale, &header.offset))
+        .collect();
+
+    let mut metadata = Metadata::new_unorganized(points.len());
+    metadata.custom_fields.insert(
+        "las_version".to_string(),
+        format!("{}.{}", header.version.0, header.version.1),
+    );
+    metadata.custom_fields.insert(
+        "las_point_format".to_string(),
+        header.point_format.to_string(),
+    );
+    metadata
+        .custom_fields
+        .insert("las_scale".to_string(), join_triple(&header.scale));
+    metadata
+        .custom_fields
+        .insert("las_offset".to_string(), join_triple(&header.offset));
+    if !header.system_identifier.is_empty() {
+        metadata.custom_fields.insert(
+            "las_system_identifier".to_string(),
+            header.system_identifier.clone(),
+        );
+    }
+
+    Ok(PointCloud::from_points_and_metadata(points, metadata))
 }
 
 /// Save a point cloud to a LAS file
-pub fn save_las<P: Point, Q: AsRef<Path>>(_cloud: &PointCloud<P>, _path: Q) -> Result<()> {
-    // TODO: Implement LAS file writing
-    // This would require writing the binary LAS format
-    Err(CloudError::format_error("LAS format not yet implemented"))
+///
+/// Files with a `.laz` extension are LASzip compressed.
+pub fn save_las<P, Q>(cloud: &PointCloud<P>, path: Q) -> Result<()>
+where
+    P: Point + Into<LasPoint>,
+    Q: AsRef<Path>,
+{
+    save_las_with_options(cloud, path, &LasWriteOptions::default())
+}
+
+/// Save a point cloud to a LAS or LAZ file using the given options
+pub fn save_las_with_options<P, Q>(
+    cloud: &PointCloud<P>,
+    path: Q,
+    options: &LasWriteOptions,
+) -> Result<()>
+where
+    P: Point + Into<LasPoint>,
+    Q: AsRef<Path>,
+{
+    let path = path.as_ref();
+    let points: Vec<LasPoint> = cloud.par_iter().map(|p| p.clone().into()).collect();
+    let metadata = cloud.metadata();
+
+    let point_format = match options.point_format {
+        Some(format) => format,
+        None => match metadata
+            .get_custom_field("las_point_format")
+            .and_then(|f| f.parse().ok())
+        {
+            Some(format) => format,
+            None => smallest_point_format(&points),
+        },
+    };
+    let record_length = format_length(point_format)?;
+
+    let scale = options
+        .scale
+        .or_else(|| parse_triple(metadata.get_custom_field("las_scale")?))
+        .unwrap_or([0.001; 3]);
+    if scale.iter().any(|&s| s <= 0.0 || !s.is_finite()) {
+        return Err(CloudError::invalid_parameter(
+            "LAS scale factors must be positive",
+        ));
+    }
+
+    let (min, max) = bounds(&points);
+    let offset = options
+        .offset
+        .or_else(|| parse_triple(metadata.get_custom_field("las_offset")?))
+        .unwrap_or_else(|| {
+            if points.is_empty() {
+                [0.0; 3]
+            } else {
+                [min[0].floor(), min[1].floor(), min[2].floor()]
+            }
+        });
+
+    let compress = options.compress.unwrap_or_else(|| {
+        path.extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| e.eq_ignore_ascii_case("laz"))
+    });
+
+    // Encode the uncompressed point records
+    let mut records = vec![0u8; points.len() * record_length as usize];
+    records
+        .par_chunks_exact_mut(record_length as usize)
+        .zip(points.par_iter())
+        .try_for_each(|(record, point)| {
+            encode_point(point, point_format, &scale, &offset, record)
+        })?;
+
+    let mut points_by_return = [0u64; 15];
+    for point in &points {
+        if (1..=15).contains(&point.return_number) {
+            points_by_return[point.return_number as usize - 1] += 1;
+        }
+    }
+
+    let laszip_vlr = if compress {
+        let vlr = LazVlrBuilder::default()
+            .with_point_format(point_format, 0)
+            .map_err(|e| CloudError::format_error(format!("Unsupported LAZ format: {}", e)))?
+            .build();
+        let mut data = Vec::new();
+        vlr.write_to(&mut data)?;
+        Some((vlr, data))
+    } else {
+        None
+    };
+
+    let version_minor = match point_format {
+        0..=3 => 2,
+        4 | 5 => 3,
+        _ => 4,
+    };
+    let header_size = header_size(version_minor);
+    let vlr_size = laszip_vlr
+        .as_ref()
+        .map_or(0, |(_, data)| VLR_HEADER_SIZE + data.len());
+    let offset_to_point_data = header_size + vlr_size;
+
+    let mut output = vec![0u8; offset_to_point_data];
+    {
+        let h = &mut output[..header_size];
+        h[0..4].copy_from_slice(LAS_SIGNATURE);
+        if point_format >= 6 {
+            LittleEndian::write_u16(&mut h[6..8], GLOBAL_ENCODING_WKT);
+        }
+        h[24] = 1;
+        h[25] = version_minor;
+        write_string(&mut h[26..58], "OTHER");
+        write_string(
+            &mut h[58..90],
+            concat!(env!("CARGO_PKG_NAME"), " ", env!("CARGO_PKG_VERSION")),
+        );
+        LittleEndian::write_u16(&mut h[94..96], header_size as u16);
+        LittleEndian::write_u32(&mut h[96..100], offset_to_point_data as u32);
+        LittleEndian::write_u32(&mut h[100..104], laszip_vlr.is_some() as u32);
+        h[104] = if compress {
+            point_format | 0x80
+        } else {
+            point_format
+        };
+        LittleEndian::write_u16(&mut h[105..107], record_length);
+
+        // Legacy counts are zero when they cannot represent the data
+        let legacy = point_format < 6 && points.len() <= u32::MAX as usize;
+        if legacy {
+            LittleEndian::write_u32(&mut h[107..111], points.len() as u32);
+            for (i, &count) in points_by_return[..5].iter().enumerate() {
+                LittleEndian::write_u32(&mut h[111 + i * 4..115 + i * 4], count as u32);
+            }
+        }
+
+        for axis in 0..3 {
+            LittleEndian::write_f64(&mut h[131 + axis * 8..139 + axis * 8], scale[axis]);
+            LittleEndian::write_f64(&mut h[155 + axis * 8..163 + axis * 8], offset[axis]);
+            LittleEndian::write_f64(&mut h[179 + axis * 16..187 + axis * 16], max[axis]);
+            LittleEndian::write_f64(&mut h[187 + axis * 16..195 + axis * 16], min[axis]);
+        }
+
+        if version_minor >= 4 {
+            LittleEndian::write_u64(&mut h[247..255], points.len() as u64);
+            for (i, &count) in points_by_return.iter().enumerate() {
+                LittleEndian::write_u64(&mut h[255 + i * 8..263 + i * 8], count);
+            }
+        }
+    }
+
+    match laszip_vlr {
+        Some((vlr, data)) => {
+            let v = &mut output[header_size..];
+            write_string(&mut v[2..18], LASZIP_USER_ID);
+            LittleEndian::write_u16(&mut v[18..20], LASZIP_RECORD_ID);
+            LittleEndian::write_u16(&mut v[20..22], data.len() as u16);
+            write_string(&mut v[22..54], "LASzip compression");
+            v[VLR_HEADER_SIZE..].copy_from_slice(&data);
+
+            let mut cursor = Cursor::new(output);
+            cursor.set_position(offset_to_point_data as u64);
+            let mut compressor = LasZipCompressor::new(cursor, vlr)
+                .map_err(|e| CloudError::format_error(format!("Failed to compress LAZ: {}", e)))?;
+            compressor.compress_many(&records)?;
+            compressor.done()?;
+            output = compressor.into_inner().into_inner();
+        }
+        None => output.extend_from_slice(&records),
+    }
+
+    std::fs::write(path, output)?;
+    Ok(())
+}
+
+/// LAS/LAZ format for the [`io::load`](super::load) and [`io::save`](super::save) registry
+///
+/// Point fields map to LAS attributes through [`LasPoint::field`]. Fields
+/// without a LAS counterpart, such as normals, cannot be read and are
+/// dropped when writing.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LasFormat;
+
+impl PointCloudReader for LasFormat {
+    fn name(&self) -> &str {
+        "las"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["las", "laz"]
+    }
+
+    fn matches_magic(&self, bytes: &[u8]) -> bool {
+        bytes.starts_with(LAS_SIGNATURE)
+    }
+
+    fn read(
+        &self,
+        path: &Path,
+        fields: &[FieldDescriptor],
+        sink: &mut dyn FnMut(&[f64]),
+    ) -> Result<Metadata> {
+        let cloud = load_las(path)?;
+
+        if let Some(first) = cloud.points().first() {
+            let missing: Vec<&str> = fields
+                .iter()
+                .filter(|f| first.field(f.name).is_none())
+                .map(|f| f.name)
+                .collect();
+            if !missing.is_empty() {
+                return Err(CloudError::format_error(format!(
+                    "LAS file is missing required fields: {}",
+                    missing.join(", ")
+                )));
+            }
+        }
+
+        let mut values = vec![0.0f64; fields.len()];
+        for point in cloud.points() {
+            for (value, field) in values.iter_mut().zip(fields) {
+                *value = point.field(field.name).unwrap_or(0.0);
+            }
+            sink(&values);
+        }
+
+        Ok(cloud.metadata().clone())
+    }
+}
+
+impl PointCloudWriter for LasFormat {
+    fn name(&self) -> &str {
+        "las"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["las", "laz"]
+    }
+
+    fn write(&self, path: &Path, rows: &PointRows<'_>) -> Result<()> {
+        let fields = rows.fields();
+        let mut values = vec![0.0f64; fields.len()];
+        let points = (0..rows.len())
+            .map(|index| {
+                rows.write_row(index, &mut values);
+                let mut point = LasPoint::default();
+                for (field, &value) in fields.iter().zip(values.iter()) {
+                    point.set_field(field.name, value);
+                }
+                point
+            })
+            .collect();
+
+        let cloud = PointCloud::from_points_and_metadata(points, rows.metadata().clone());
+        save_las(&cloud, path)
+    }
+}
+
+/// Parse the public header block and locate the LASzip VLR
+fn parse_header(bytes: &[u8]) -> Result<LasHeader> {
+    if bytes.len() < 227 || &bytes[0..4] != LAS_SIGNATURE {
+        return Err(CloudError::format_error(
+            "Not a LAS file (missing LASF signature)",
+        ));
+    }
+
+    let version = (bytes[24], bytes[25]);
+    let header_size = LittleEndian::read_u16(&bytes[94..96]) as usize;
+    let offset_to_point_data = LittleEndian::read_u32(&bytes[96..100]);
+    let vlr_count = LittleEndian::read_u32(&bytes[100..104]) as usize;
+    let raw_format = bytes[104];
+    let point_format = raw_format & 0x3F;
+    let point_record_length = LittleEndian::read_u16(&bytes[105..107]);
+    if header_size > bytes.len() || header_size < 227 {
+        return Err(CloudError::format_error("Invalid LAS header size"));
+    }
+
+    let mut points_by_return = [0u64; 15];
+    for (i, count) in points_by_return[..5].iter_mut().enumerate() {
+        *count = LittleEndian::read_u32(&bytes[111 + i * 4..115 + i * 4]) as u64;
+    }
+    let mut point_count = LittleEndian::read_u32(&bytes[107..111]) as u64;
+    if version >= (1, 4) && header_size >= 375 {
+        let extended_count = LittleEndian::read_u64(&bytes[247..255]);
+        if extended_count > 0 {
+            point_count = extended_count;
+            for (i, count) in points_by_return.iter_mut().enumerate() {
+                *count = LittleEndian::read_u64(&bytes[255 + i * 8..263 + i * 8]);
+            }
+        }
+    }
+
+    let read_triple = |start: usize, stride: usize| {
+        [
+            LittleEndian::read_f64(&bytes[start..]),
+            LittleEndian::read_f64(&bytes[start + stride..]),
+            LittleEndian::read_f64(&bytes[start + 2 * stride..]),
+        ]
+    };
+
+    // Variable length records, looking for the LASzip description
+    let mut laszip_vlr = None;
+    let mut position = header_size;
+    for _ in 0..vlr_count {
+        if position + VLR_HEADER_SIZE > bytes.len() {
+            return Err(CloudError::format_error(
+                "Truncated LAS variable length record",
+            ));
+        }
+        let vlr = &bytes[position..];
+        let user_id = read_string(&vlr[2..18]);
+        let record_id = LittleEndian::read_u16(&vlr[18..20]);
+        let length = LittleEndian::read_u16(&vlr[20..22]) as usize;
+        let data_start = position + VLR_HEADER_SIZE;
+        if data_start + length > bytes.len() {
+            return Err(CloudError::format_error(
+                "Truncated LAS variable length record",
+            ));
+        }
+        if user_id == LASZIP_USER_ID && record_id == LASZIP_RECORD_ID {
+            laszip_vlr = Some(bytes[data_start..data_start + length].to_vec());
+        }
+        position = data_start + length;
+    }
+
+    let compressed = raw_format & 0x80 != 0 || laszip_vlr.is_some();
+    if compressed && laszip_vlr.is_none() {
+        return Err(CloudError::format_error(
+            "LAZ file is missing its LASzip variable length record",
+        ));
+    }
+
+    Ok(LasHeader {
+        version,
+        point_format,
+        point_record_length,
+        point_count,
+        points_by_return,
+        scale: read_triple(131, 8),
+        offset: read_triple(155, 8),
+        min: read_triple(187, 16),
+        max: read_triple(179, 16),
+        file_source_id: LittleEndian::read_u16(&bytes[4..6]),
+        global_encoding: LittleEndian::read_u16(&bytes[6..8]),
+        system_identifier: read_string(&bytes[26..58]),
+        generating_software: read_string(&bytes[58..90]),
+        creation_day: LittleEndian::read_u16(&bytes[90..92]),
+        creation_year: LittleEndian::read_u16(&bytes[92..94]),
+        compressed,
+        offset_to_point_data,
+        laszip_vlr,
+    })
+}
+
+/// Size of the public header block for a LAS 1.x minor version
+fn header_size(version_minor: u8) -> usize {
+    match version_minor {
+        0..=2 => 227,
+        3 => 235,
+        _ => 375,
+    }
+}
+
+/// Size in bytes of a point record of the given format, without extra bytes
+fn format_length(format: u8) -> Result<u16> {
+    match format {
+        0 => Ok(20),
+        1 => Ok(28),
+        2 => Ok(26),
+        3 => Ok(34),
+        4 => Ok(57),
+        5 => Ok(63),
+        6 => Ok(30),
+        7 => Ok(36),
+        8 => Ok(38),
+        9 => Ok(59),
+        10 => Ok(67),
+        _ => Err(CloudError::format_error(format!(
+            "Unsupported LAS point data record format {}",
+            format
+        ))),
+    }
+}
+
+/// Byte offsets of the optional fields of a point record format
+struct FieldOffsets {
+    gps_time: Option<usize>,
+    rgb: Option<usize>,
+    nir: Option<usize>,
+}
+
+fn field_offsets(format: u8) -> FieldOffsets {
+    let (gps_time, rgb, nir) = match format {
+        0 => (None, None, None),
+        1 | 4 => (Some(20), None, None),
+        2 => (None, Some(20), None),
+        3 | 5 => (Some(20), Some(28), None),
+        6 | 9 => (Some(22), None, None),
+        7 => (Some(22), Some(30), None),
+        _ => (Some(22), Some(30), Some(36)),
+    };
+    FieldOffsets { gps_time, rgb, nir }
+}
+
+/// Choose the smallest point format that keeps the attributes of all points
+fn smallest_point_format(points: &[LasPoint]) -> u8 {
+    let (mut extended, mut gps_time, mut rgb, mut nir) = (false, false, false, false);
+    for point in points {
+        extended |= point.return_number > 7
+            || point.number_of_returns > 7
+            || point.classification > 31
+            || point.scanner_channel != 0
+            || point.classification_flags & 0x08 != 0;
+        gps_time |= point.gps_time.is_some();
+        rgb |= point.rgb.is_some();
+        nir |= point.nir.is_some();
+    }
+
+    match (extended, gps_time, rgb) {
+        (_, _, _) if nir => 8,
+        (true, _, true) => 7,
+        (true, _, false) => 6,
+        (false, true, true) => 3,
+        (false, false, true) => 2,
+        (false, true, false) => 1,
+        (false, false, false) => 0,
+    }
+}
+
+/// Decode a single point record
+fn decode_point(record: &[u8], format: u8, scale: &[f64; 3], offset: &[f64; 3]) -> LasPoint {
+    let coordinate = |axis: usize| {
+        LittleEndian::read_i32(&record[axis * 4..]) as f64 * scale[axis] + offset[axis]
+    };
+
+    let mut point = LasPoint {
+        x: coordinate(0),
+        y: coordinate(1),
+        z: coordinate(2),
+        intensity: LittleEndian::read_u16(&record[12..14]),
+        ..LasPoint::default()
+    };
+
+    if format < 6 {
+        let returns = record[14];
+        point.return_number = returns & 0x07;
+        point.number_of_returns = (returns >> 3) & 0x07;
+        point.scan_direction_flag = returns & 0x40 != 0;
+        point.edge_of_flight_line = returns & 0x80 != 0;
+        point.classification = record[15] & 0x1F;
+        point.classification_flags = record[15] >> 5;
+        point.scan_angle = record[16] as i8 as f32;
+        point.user_data = record[17];
+        point.point_source_id = LittleEndian::read_u16(&record[18..20]);
+    } else {
+        let returns = record[14];
+        let flags = record[15];
+        point.return_number = returns & 0x0F;
+        point.number_of_returns = returns >> 4;
+        point.classification_flags = flags & 0x0F;
+        point.scanner_channel = (flags >> 4) & 0x03;
+        point.scan_direction_flag = flags & 0x40 != 0;
+        point.edge_of_flight_line = flags & 0x80 != 0;
+        point.classification = record[16];
+        point.user_data = record[17];
+        point.scan_angle = LittleEndian::read_i16(&record[18..20]) as f32 * 0.006;
+        point.point_source_id = LittleEndian::read_u16(&record[20..22]);
+    }
+
+    let offsets = field_offsets(format);
+    point.gps_time = offsets
+        .gps_time
+        .map(|o| LittleEndian::read_f64(&record[o..o + 8]));
+    point.rgb = offsets.rgb.map(|o| {
+        [
+            LittleEndian::read_u16(&record[o..o + 2]),
+            LittleEndian::read_u16(&record[o + 2..o + 4]),
+            LittleEndian::read_u16(&record[o + 4..o + 6]),
+        ]
+    });
+    point.nir = offsets
+        .nir
+        .map(|o| LittleEndian::read_u16(&record[o..o + 2]));
+
+    point
+}
+
+/// Encode a single point record
+///
+/// Waveform packet fields of formats 4, 5, 9 and 10 are written as zero,
+/// which marks the point as having no waveform data.
+fn encode_point(
+    point: &LasPoint,
+    format: u8,
+    scale: &[f64; 3],
+    offset: &[f64; 3],
+    record: &mut [u8],
+) -> Result<()> {
+    for (axis, value) in [point.x, point.y, point.z].into_iter().enumerate() {
+        let quantized = ((value - offset[axis]) / scale[axis]).round();
+        if !(quantized >= i32::MIN as f64 && quantized <= i32::MAX as f64) {
+            return Err(CloudError::format_error(format!(
+                "Coordinate {} cannot be stored with scale {} and offset {}",
+                value, scale[axis], offset[axis]
+            )));
+        }
+        LittleEndian::write_i32(&mut record[axis * 4..axis * 4 + 4], quantized as i32);
+    }
+    LittleEndian::write_u16(&mut record[12..14], point.intensity);
+
+    if format < 6 {
+        if point.return_number > 7 || point.number_of_returns > 7 || point.classification > 31 {
+            return Err(CloudError::format_error(format!(
+                "Point attributes exceed the range of point format {}",
+                format
+            )));
+        }
+        record[14] = point.return_number
+            | (point.number_of_returns << 3)
+            | ((point.scan_direction_flag as u8) << 6)
+            | ((point.edge_of_flight_line as u8) << 7);
+        record[15] = point.classification | ((point.classification_flags & 0x07) << 5);
+        record[16] = point.scan_angle.round().clamp(-128.0, 127.0) as i8 as u8;
+        record[17] = point.user_data;
+        LittleEndian::write_u16(&mut record[18..20], point.point_source_id);
+    } else {
+        record[14] = (point.return_number & 0x0F) | (point.number_of_returns << 4);
+        record[15] = (point.classification_flags & 0x0F)
+            | ((point.scanner_channel & 0x03) << 4)
+            | ((point.scan_direction_flag as u8) << 6)
+            | ((point.edge_of_flight_line as u8) << 7);
+        record[16] = point.classification;
+        record[17] = point.user_data;
+        let scan_angle = (point.scan_angle / 0.006)
+            .round()
+            .clamp(-30_000.0, 30_000.0);
+        LittleEndian::write_i16(&mut record[18..20], scan_angle as i16);
+        LittleEndian::write_u16(&mut record[20..22], point.point_source_id);
+    }
+
+    let offsets = field_offsets(format);
+    if let Some(o) = offsets.gps_time {
+        LittleEndian::write_f64(&mut record[o..o + 8], point.gps_time.unwrap_or(0.0));
+    }
+    if let Some(o) = offsets.rgb {
+        let [r, g, b] = point.rgb.unwrap_or([0; 3]);
+        LittleEndian::write_u16(&mut record[o..o + 2], r);
+        LittleEndian::write_u16(&mut record[o + 2..o + 4], g);
+        LittleEndian::write_u16(&mut record[o + 4..o + 6], b);
+    }
+    if let Some(o) = offsets.nir {
+        LittleEndian::write_u16(&mut record[o..o + 2], point.nir.unwrap_or(0));
+    }
+
+    Ok(())
+}
+
+/// Minimum and maximum coordinates of the points
+fn bounds(points: &[LasPoint]) -> ([f64; 3], [f64; 3]) {
+    if points.is_empty() {
+        return ([0.0; 3], [0.0; 3]);
+    }
+
+    let mut min = [f64::INFINITY; 3];
+    let mut max = [f64::NEG_INFINITY; 3];
+    for point in points {
+        for (axis, value) in [point.x, point.y, point.z].into_iter().enumerate() {
+            min[axis] = min[axis].min(value);
+            max[axis] = max[axis].max(value);
+        }
+    }
+    (min, max)
+}
+
+/// Read a NUL-padded string field
+fn read_string(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).trim().to_string()
+}
+
+/// Write a string into a NUL-padded field, truncating if necessary
+fn write_string(field: &mut [u8], value: &str) {
+    let length = value.len().min(field.len());
+    field[..length].copy_from_slice(&value.as_bytes()[..length]);
+}
+
+fn join_triple(values: &[f64; 3]) -> String {
+    format!("{} {} {}", values[0], values[1], values[2])
+}
+
+fn parse_triple(value: &str) -> Option<[f64; 3]> {
+    let values: Vec<f64> = value
+        .split_whitespace()
+        .map(|v| v.parse().ok())
+        .collect::<Option<_>>()?;
+    values.try_into().ok()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use tempfile::NamedTempFile;
+
+    fn survey_points() -> Vec<LasPoint> {
+        (0..50)
+            .map(|i| {
+                let t = i as f64;
+                LasPoint {
+                    intensity: (i * 100) as u16,
+                    return_number: (i % 3 + 1) as u8,
+                    number_of_returns: 3,
+                    scan_direction_flag: i % 2 == 0,
+                    edge_of_flight_line: i % 7 == 0,
+                    classification: (i % 10) as u8,
+                    classification_flags: (i % 4) as u8,
+                    scan_angle: (i as f32) - 20.0,
+                    user_data: i as u8,
+                    point_source_id: 7,
+                    gps_time: Some(1000.0 + t * 0.5),
+                    rgb: Some([i as u16 * 1000, 65535 - i as u16, 256]),
+                    nir: None,
+                    ..LasPoint::new(
+                        500_000.0 + t * 0.25,
+                        4_200_000.0 - t * 0.125,
+                        120.0 + t * 0.01,
+                    )
+                }
+            })
+            .collect()
+    }
+
+    fn roundtrip(
+        cloud: &PointCloud<LasPoint>,
+        extension: &str,
+        options: &LasWriteOptions,
+    ) -> PointCloud<LasPoint> {
+        let file = tempfile::Builder::new()
+            .suffix(extension)
+            .tempfile()
+            .unwrap();
+        save_las_with_options(cloud, file.path(), options).unwrap();
+        load_las(file.path()).unwrap()
+    }
+
+    fn assert_same(original: &LasPoint, loaded: &LasPoint, format: u8) {
+        assert!((original.x - loaded.x).abs() < 1e-6);
+        assert!((original.y - loaded.y).abs() < 1e-6);
+        assert!((original.z - loaded.z).abs() < 1e-6);
+        assert_eq!(original.intensity, loaded.intensity);
+        assert_eq!(original.return_number, loaded.return_number);
+        assert_eq!(original.number_of_returns, loaded.number_of_returns);
+        assert_eq!(original.classification, loaded.classification);
+        assert_eq!(original.scan_direction_flag, loaded.scan_direction_flag);
+        assert_eq!(original.edge_of_flight_line, loaded.edge_of_flight_line);
+        assert_eq!(original.user_data, loaded.user_data);
+        assert_eq!(original.point_source_id, loaded.point_source_id);
+        assert!((original.scan_angle - loaded.scan_angle).abs() < 0.01);
+
+        let offsets = field_offsets(format);
+        assert_eq!(loaded.gps_time.is_some(), offsets.gps_time.is_some());
+        if offsets.gps_time.is_some() {
+            assert_eq!(original.gps_time, loaded.gps_time);
+        }
+        if offsets.rgb.is_some() {
+            assert_eq!(original.rgb, loaded.rgb);
+        } else {
+            assert_eq!(loaded.rgb, None);
+        }
+    }
 
     #[test]
-    fn test_las_not_implemented() {
+    fn test_las_roundtrip_all_formats() {
+        let cloud = PointCloud::from_points(survey_points());
+
+        for format in 0..=10 {
+            let options = LasWriteOptions {
+                point_format: Some(format),
+                ..LasWriteOptions::default()
+            };
+            let loaded = roundtrip(&cloud, ".las", &options);
+            assert_eq!(loaded.len(), cloud.len());
+            assert_eq!(
+                loaded.metadata().get_custom_field("las_point_format"),
+                Some(&format.to_string())
+            );
+            for (original, loaded) in cloud.iter().zip(loaded.iter()) {
+                assert_same(original, loaded, format);
+            }
+        }
+    }
+
+    #[test]
+    fn test_laz_roundtrip() {
+        let cloud = PointCloud::from_points(survey_points());
+
+        for format in [1, 3, 6, 8] {
+            let options = LasWriteOptions {
+                point_format: Some(format),
+                ..LasWriteOptions::default()
+            };
+            let file = tempfile::Builder::new().suffix(".laz").tempfile().unwrap();
+            save_las_with_options(&cloud, file.path(), &options).unwrap();
+
+            let header = read_las_header(file.path()).unwrap();
+            assert!(header.compressed);
+            assert_eq!(header.point_format, format);
+            assert_eq!(header.point_count, 50);
+
+            let loaded = load_las(file.path()).unwrap();
+            for (original, loaded) in cloud.iter().zip(loaded.iter()) {
+                assert_same(original, loaded, format);
+            }
+        }
+    }
+
+    #[test]
+    fn test_las_header() {
+        let cloud = PointCloud::from_points(survey_points());
+        let file = NamedTempFile::new().unwrap();
+        let options = LasWriteOptions {
+            scale: Some([0.01, 0.01, 0.001]),
+            offset: Some([500_000.0, 4_200_000.0, 0.0]),
+            ..LasWriteOptions::default()
+        };
+        save_las_with_options(&cloud, file.path(), &options).unwrap();
+
+        let header = read_las_header(file.path()).unwrap();
+        assert_eq!(header.version, (1, 2));
+        assert_eq!(header.point_format, 3);
+        assert_eq!(header.point_count, 50);
+        assert_eq!(header.scale, [0.01, 0.01, 0.001]);
+        assert_eq!(header.offset, [500_000.0, 4_200_000.0, 0.0]);
+        assert_eq!(header.points_by_return[..3], [17, 17, 16]);
+        assert_eq!(header.global_encoding, 0);
+        assert!((header.min[0] - 500_000.0).abs() < 1e-9);
+        assert!((header.max[2] - 120.49).abs() < 1e-9);
+        assert!(!header.compressed);
+
+        // Scale and offset are kept when the cloud is written again
+        let loaded = load_las(file.path()).unwrap();
+        save_las(&loaded, file.path()).unwrap();
+        let again = read_las_header(file.path()).unwrap();
+        assert_eq!(again.scale, header.scale);
+        assert_eq!(again.offset, header.offset);
+    }
+
+    #[test]
+    fn test_save_generic_points() {
+        let cloud = PointCloud::from_points(vec![
+            PointXYZRGB::new(1.0, 2.0, 3.0, 255, 0, 128),
+            PointXYZRGB::new(4.0, 5.0, 6.0, 0, 255, 0),
+        ]);
+        let file = NamedTempFile::new().unwrap();
+        save_las(&cloud, file.path()).unwrap();
+
+        let loaded = load_las(file.path()).unwrap();
+        assert_eq!(read_las_header(file.path()).unwrap().point_format, 2);
+        assert_eq!(loaded.get(0).unwrap().color(), Some([255, 0, 128]));
+        assert_eq!(loaded.get(1).unwrap().position(), [4.0, 5.0, 6.0]);
+    }
+
+    #[test]
+    fn test_extended_attributes_select_format() {
+        let mut point = LasPoint::new(0.0, 0.0, 0.0).with_gps_time(5.0);
+        point.return_number = 9;
+        point.number_of_returns = 12;
+        point.classification = 64;
+        point.scanner_channel = 2;
+        point.nir = Some(4000);
+        point.rgb = Some([1, 2, 3]);
+        let cloud = PointCloud::from_points(vec![point.clone()]);
+
+        let loaded = roundtrip(&cloud, ".las", &LasWriteOptions::default());
+        assert_eq!(
+            loaded.metadata().get_custom_field("las_version"),
+            Some(&"1.4".to_string())
+        );
+        assert_eq!(loaded.get(0), Some(&point));
+
+        let file = NamedTempFile::new().unwrap();
+        save_las(&cloud, file.path()).unwrap();
+        let header = read_las_header(file.path()).unwrap();
+        assert_eq!(
+            header.global_encoding & GLOBAL_ENCODING_WKT,
+            GLOBAL_ENCODING_WKT
+        );
+
+        let legacy = LasWriteOptions {
+            point_format: Some(1),
+            ..LasWriteOptions::default()
+        };
+        let file = NamedTempFile::new().unwrap();
+        assert!(save_las_with_options(&cloud, file.path(), &legacy).is_err());
+    }
+
+    #[test]
+    fn test_format_covers_later_points() {
+        let plain = LasPoint::new(0.0, 0.0, 0.0);
+        let timed = LasPoint::new(1.0, 0.0, 0.0).with_gps_time(7.5);
+        let colored = LasPoint::new(2.0, 0.0, 0.0).with_rgb([100, 200, 300]);
+        let cloud = PointCloud::from_points(vec![plain.clone(), timed, colored]);
+
+        let loaded = roundtrip(&cloud, ".las", &LasWriteOptions::default());
+        assert_eq!(
+            loaded.metadata().get_custom_field("las_point_format"),
+            Some(&"3".to_string())
+        );
+        assert_eq!(loaded.get(1).unwrap().gps_time, Some(7.5));
+        assert_eq!(loaded.get(2).unwrap().rgb, Some([100, 200, 300]));
+
+        let mut extended = LasPoint::new(1.0, 0.0, 0.0);
+        extended.return_number = 9;
+        extended.number_of_returns = 9;
+        let cloud = PointCloud::from_points(vec![plain, extended.clone()]);
+        let loaded = roundtrip(&cloud, ".las", &LasWriteOptions::default());
+        assert_eq!(
+            loaded.metadata().get_custom_field("las_point_format"),
+            Some(&"6".to_string())
+        );
+        assert_eq!(loaded.get(1).unwrap().return_number, 9);
+    }
+
+    #[test]
+    fn test_load_missing_file() {
         let result = load_las("test.las");
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_rejects_oversized_point_count() {
+        let cloud = PointCloud::from_points(vec![LasPoint::new(0.0, 0.0, 0.0).with_gps_time(1.0)]);
+        let options = LasWriteOptions {
+            point_format: Some(6),
+            ..LasWriteOptions::default()
+        };
+        for compress in [false, true] {
+            let file = NamedTempFile::new().unwrap();
+            let options = LasWriteOptions {
+                compress: Some(compress),
+                ..options.clone()
+            };
+            save_las_with_options(&cloud, file.path(), &options).unwrap();
+
+            let mut bytes = std::fs::read(file.path()).unwrap();
+            for count in [u64::MAX, 1 << 40] {
+                LittleEndian::write_u64(&mut bytes[247..255], count);
+                std::fs::write(file.path(), &bytes).unwrap();
+                assert!(load_las(file.path()).is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn test_rejects_non_las_data() {
+        let file = NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), b"ply\nformat ascii 1.0\nend_header\n").unwrap();
+        assert!(load_las(file.path()).is_err());
+    }
 }
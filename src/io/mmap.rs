@@ -0,0 +1,498 @@
+//! Memory-mapped point clouds
+//!
+//! This module opens binary point cloud files through a memory map instead of
+//! reading them into a `Vec`. Points are decoded on access, so clouds much
+//! larger than the available memory can be inspected and processed; the
+//! operating system pages the file in and out as needed.
+//!
+//! When the records in the file have exactly the memory layout of a
+//! [`PackedPoint`] type, the mapped data can also be borrowed as a slice or a
+//! [`PointCloudView`] without any decoding. The native format always stores
+//! its records aligned for this; binary PCD and PLY files qualify when their
+//! fields match the point type and their header length happens to keep the
+//! data aligned.
+
+use super::native::{self, NATIVE_MAGIC};
+use super::pcd::{self, FieldSource, PcdDataFormat};
+use super::ply::{self, PlyEncoding};
+use crate::core::{FieldType, Metadata, PackedPoint, PointCloud, PointCloudView, PointFields};
+use crate::error::{CloudError, Result};
+use byteorder::{BigEndian, LittleEndian};
+use memmap2::Mmap;
+use rayon::prelude::*;
+use std::fs::File;
+use std::marker::PhantomData;
+use std::path::Path;
+
+/// Number of field values decoded without a heap allocation
+const INLINE_FIELDS: usize = 16;
+
+/// Read-only point cloud backed by a memory-mapped file
+///
+/// The file must not be modified or truncated while it is mapped; doing so
+/// is undefined behavior, as with any memory map.
+///
+/// # Example
+/// ```rust,no_run
+/// use ferrum_cloud::io::MappedPointCloud;
+/// use ferrum_cloud::prelude::*;
+///
+/// let mapped: MappedPointCloud<PointXYZ> = MappedPointCloud::open("survey.fpc")?;
+/// let view = mapped.view()?;
+/// println!("{} points, centroid {:?}", view.len(), view.centroid());
+/// # Ok::<(), CloudError>(())
+/// ```
+pub struct MappedPointCloud<P: PointFields> {
+    map: Mmap,
+    metadata: Metadata,
+    data_offset: usize,
+    record_size: usize,
+    len: usize,
+    fields: Vec<MappedField>,
+    _point: PhantomData<fn() -> P>,
+}
+
+/// Location of a point field inside a mapped record
+#[derive(Clone, Copy, Debug)]
+enum MappedField {
+    Pcd(FieldSource),
+    Binary {
+        offset: usize,
+        datatype: FieldType,
+        big_endian: bool,
+    },
+}
+
+impl MappedField {
+    fn read(&self, record: &[u8]) -> f64 {
+        match *self {
+            MappedField::Pcd(source) => source.read(record),
+            MappedField::Binary {
+                offset,
+                datatype,
+                big_endian: false,
+            } => ply::read_binary::<LittleEndian>(datatype, &record[offset..]),
+            MappedField::Binary {
+                offset,
+                datatype,
+                big_endian: true,
+            } => ply::read_binary::<BigEndian>(datatype, &record[offset..]),
+        }
+    }
+
+    /// Offset and type of a field stored as a plain little-endian value
+    fn little_endian_scalar(&self) -> Option<(usize, FieldType)> {
+        match *self {
+            MappedField::Pcd(FieldSource::Scalar { offset, datatype })
+            | MappedField::Binary {
+                offset,
+                datatype,
+                big_endian: false,
+            } => Some((offset, datatype)),
+            _ => None,
+        }
+    }
+}
+
+impl<P: PointFields> MappedPointCloud<P> {
+    /// Map a binary PCD, PLY or native file, detecting the format from its header
+    pub fn open<Q: AsRef<Path>>(path: Q) -> Result<Self> {
+        let map = map_file(path.as_ref())?;
+        if map.starts_with(NATIVE_MAGIC) {
+            Self::from_native(map)
+        } else if map.starts_with(b"ply\n") || map.starts_with(b"ply\r\n") {
+            Self::from_ply(map)
+        } else if map.starts_with(b"# .PCD") || map.starts_with(b"VERSION") {
+            Self::from_pcd(map)
+        } else {
+            Err(CloudError::format_error(format!(
+                "Unrecognized point cloud format: {}",
+                path.as_ref().display()
+            )))
+        }
+    }
+
+    /// Map a PCD file with `binary` data
+    pub fn open_pcd<Q: AsRef<Path>>(path: Q) -> Result<Self> {
+        Self::from_pcd(map_file(path.as_ref())?)
+    }
+
+    /// Map a binary little- or big-endian PLY file
+    pub fn open_ply<Q: AsRef<Path>>(path: Q) -> Result<Self> {
+        Self::from_ply(map_file(path.as_ref())?)
+    }
+
+    /// Map a native point cloud file
+    pub fn open_native<Q: AsRef<Path>>(path: Q) -> Result<Self> {
+        Self::from_native(map_file(path.as_ref())?)
+    }
+
+    fn from_pcd(map: Mmap) -> Result<Self> {
+        let header = pcd::parse_header(&mut &map[..])?;
+        if header.data != PcdDataFormat::Binary {
+            return Err(CloudError::format_error(format!(
+                "Memory mapping requires binary PCD data, found {}",
+                header.data.keyword()
+            )));
+        }
+
+        let fields = pcd::resolve_field_sources(P::fields(), &header)?
+            .into_iter()
+            .map(MappedField::Pcd)
+            .collect();
+        Self::new(
+            map,
+            pcd::metadata_from_header(&header),
+            header.data_offset,
+            header.point_size(),
+            header.points,
+            fields,
+        )
+    }
+
+    fn from_ply(map: Mmap) -> Result<Self> {
+        let header = ply::parse_header(&mut &map[..])?;
+        let big_endian = match header.encoding {
+            PlyEncoding::BinaryLittleEndian => false,
+            PlyEncoding::BinaryBigEndian => true,
+            PlyEncoding::Ascii => {
+                return Err(CloudError::format_error(
+                    "Memory mapping requires binary PLY data, found ascii",
+                ));
+            }
+        };
+
+        // Vertex records start after all preceding elements, which must
+        // therefore have a fixed size
+        let mut data_offset = header.data_offset;
+        let mut vertex = None;
+        for element in &header.elements {
+            if element.name == "vertex" {
+                vertex = Some(element);
+                break;
+            }
+            let size = element.record_size().ok_or_else(|| {
+                CloudError::format_error(format!(
+                    "Cannot map PLY vertices stored after the variable-size element {}",
+                    element.name
+                ))
+            })?;
+            data_offset = size
+                .checked_mul(element.count)
+                .and_then(|size| data_offset.checked_add(size))
+                .ok_or_else(|| {
+                    CloudError::format_error(format!("PLY element {} is too large", element.name))
+                })?;
+        }
+        let vertex = vertex
+            .ok_or_else(|| CloudError::format_error("PLY file does not contain vertex data"))?;
+        let record_size = vertex.record_size().ok_or_else(|| {
+            CloudError::format_error("Cannot map PLY vertices with list properties")
+        })?;
+
+        let mut offsets = Vec::with_capacity(vertex.properties.len());
+        let mut offset = 0;
+        for property in &vertex.properties {
+            offsets.push(offset);
+            if let ply::PlyPropertyKind::Scalar(datatype) = property.kind {
+                offset += datatype.size();
+            }
+        }
+
+        let mut fields = Vec::with_capacity(P::fields().len());
+        let mut missing = Vec::new();
+        for field in P::fields() {
+            let aliases = ply::property_aliases(field.name);
+            let found = aliases
+                .iter()
+                .find_map(|name| vertex.property_index(name))
+                .and_then(|index| match vertex.properties[index].kind {
+                    ply::PlyPropertyKind::Scalar(datatype) => Some(MappedField::Binary {
+                        offset: offsets[index],
+                        datatype,
+                        big_endian,
+                    }),
+                    ply::PlyPropertyKind::List { .. } => None,
+                });
+            match found {
+                Some(field) => fields.push(field),
+                None => missing.push(aliases[0]),
+            }
+        }
+        if !missing.is_empty() {
+            return Err(CloudError::format_error(format!(
+                "PLY vertex element is missing required properties: {}",
+                missing.join(", ")
+            )));
+        }
+
+        let count = vertex.count;
+        Self::new(
+            map,
+            Metadata::new_unorganized(count),
+            data_offset,
+            record_size,
+            count,
+            fields,
+        )
+    }
+
+    fn from_native(map: Mmap) -> Result<Self> {
+        let header = native::parse_header(&map)?;
+        if header.compressed {
+            return Err(CloudError::format_error(
+                "Compressed native files cannot be memory mapped",
+            ));
+        }
+        let fields = native::resolve_field_sources(&header, P::fields())?
+            .into_iter()
+            .map(|field| MappedField::Binary {
+                offset: field.offset,
+                datatype: field.datatype,
+                big_endian: false,
+            })
+            .collect();
+        Self::new(
+            map,
+            header.metadata.clone(),
+            header.data_offset,
+            header.record_size,
+            header.points,
+            fields,
+        )
+    }
+
+    fn new(
+        map: Mmap,
+        metadata: Metadata,
+        data_offset: usize,
+        record_size: usize,
+        len: usize,
+        fields: Vec<MappedField>,
+    ) -> Result<Self> {
+        if data_offset > map.len() {
+            return Err(CloudError::format_error(format!(
+                "Point data starts at byte {} past the end of the {} byte file",
+                data_offset,
+                map.len()
+            )));
+        }
+        let available = map.len() - data_offset;
+        if len
+            .checked_mul(record_size)
+            .is_none_or(|size| size > available)
+        {
+            return Err(CloudError::format_error(format!(
+                "Point data is truncated: {} points of {} bytes do not fit in {} bytes",
+                len, record_size, available
+            )));
+        }
+
+        Ok(Self {
+            map,
+            metadata,
+            data_offset,
+            record_size,
+            len,
+            fields,
+            _point: PhantomData,
+        })
+    }
+
+    /// Get the number of points
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Check if the cloud is empty
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Get a reference to the metadata
+    pub fn metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+
+    /// Decode the point at an index
+    pub fn get(&self, index: usize) -> Option<P> {
+        (index < self.len).then(|| self.decode(index))
+    }
+
+    /// Iterate over the points, decoding them one at a time
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = P> + '_ {
+        (0..self.len).map(|index| self.decode(index))
+    }
+
+    /// Iterate over the points in parallel
+    pub fn par_iter(&self) -> impl IndexedParallelIterator<Item = P> + '_ {
+        (0..self.len)
+            .into_par_iter()
+            .map(|index| self.decode(index))
+    }
+
+    /// Decode all points into an owned point cloud
+    pub fn to_cloud(&self) -> PointCloud<P> {
+        PointCloud::from_points_and_metadata(self.par_iter().collect(), self.metadata.clone())
+    }
+
+    fn decode(&self, index: usize) -> P {
+        let start = self.data_offset + index * self.record_size;
+        let record = &self.map[start..start + self.record_size];
+
+        let mut inline = [0.0f64; INLINE_FIELDS];
+        let mut heap = Vec::new();
+        let values = if self.fields.len() <= INLINE_FIELDS {
+            &mut inline[..self.fields.len()]
+        } else {
+            heap.resize(self.fields.len(), 0.0);
+            &mut heap[..]
+        };
+
+        for (value, field) in values.iter_mut().zip(self.fields.iter()) {
+            *value = field.read(record);
+        }
+        P::from_values(values)
+    }
+}
+
+impl<P: PackedPoint> MappedPointCloud<P> {
+    /// Borrow the mapped records as points without decoding
+    ///
+    /// Fails unless the records consist of exactly the fields of `P` in
+    /// order, stored little-endian on a little-endian machine, with the data
+    /// aligned for `P`.
+    pub fn as_slice(&self) -> Result<&[P]> {
+        let incompatible = |reason: &str| {
+            Err(CloudError::format_error(format!(
+                "Mapped records cannot be viewed as {}: {}",
+                std::any::type_name::<P>(),
+                reason
+            )))
+        };
+
+        if cfg!(target_endian = "big") {
+            return incompatible("the machine is big-endian");
+        }
+        if self.record_size != std::mem::size_of::<P>() {
+            return incompatible("the records contain other fields");
+        }
+        let mut offset = 0;
+        for (descriptor, field) in P::fields().iter().zip(self.fields.iter()) {
+            if field.little_endian_scalar() != Some((offset, descriptor.datatype)) {
+                return incompatible("the field layout or byte order differs");
+            }
+            offset += descriptor.datatype.size();
+        }
+        let data = self.map[self.data_offset..].as_ptr();
+        if data.align_offset(std::mem::align_of::<P>()) != 0 {
+            return incompatible("the point data is not aligned");
+        }
+
+        // SAFETY: the records hold `len` values laid out exactly like `P`
+        // (checked above and guaranteed by `PackedPoint`), the data is
+        // aligned and in bounds (checked in `new`), and the map is borrowed
+        // for the lifetime of the slice.
+        Ok(unsafe { std::slice::from_raw_parts(data.cast::<P>(), self.len) })
+    }
+
+    /// Borrow the mapped records as a point cloud view
+    ///
+    /// See [`as_slice`](Self::as_slice) for the requirements.
+    pub fn view(&self) -> Result<PointCloudView<'_, P>> {
+        Ok(PointCloudView::new(self.as_slice()?, &self.metadata))
+    }
+}
+
+/// Map a file read-only into memory
+///
+/// The file must not be modified while the mapping is alive; every reader
+/// that maps files documents this requirement to its callers.
+pub(crate) fn map_file(path: &Path) -> Result<Mmap> {
+    let file = File::open(path)?;
+    // SAFETY: mapping is sound as long as no other process truncates or
+    // writes the file while it is mapped, which callers must guarantee
+    Ok(unsafe { Mmap::map(&file)? })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{Point, PointXYZ, PointXYZI, PointXYZRGB, PointXYZRGBNormal};
+    use crate::io::{save_native, save_pcd, save_pcd_with_format, save_ply_with_encoding};
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_native_view() {
+        let mut cloud = PointCloud::from_points(
+            (0..100)
+                .map(|i| PointXYZI::new(i as f32, -(i as f32), 0.5, i as f32 / 100.0))
+                .collect(),
+        );
+        cloud.metadata_mut().sensor_origin = [1.0, 2.0, 3.0];
+        let file = NamedTempFile::new().unwrap();
+        save_native(&cloud, file.path()).unwrap();
+
+        let mapped: MappedPointCloud<PointXYZI> = MappedPointCloud::open(file.path()).unwrap();
+        let view = mapped.view().unwrap();
+        assert_eq!(view.points(), cloud.points());
+        assert_eq!(view.metadata(), cloud.metadata());
+        assert_eq!(view.centroid(), cloud.centroid());
+
+        // Decoding still works for point types that are a subset of the file
+        let positions: MappedPointCloud<PointXYZ> = MappedPointCloud::open(file.path()).unwrap();
+        assert!(positions.view().is_err());
+        assert_eq!(positions.get(3), Some(PointXYZ::new(3.0, -3.0, 0.5)));
+        assert_eq!(positions.get(100), None);
+        assert_eq!(positions.par_iter().filter(|p| p.x >= 50.0).count(), 50);
+    }
+
+    #[test]
+    fn test_pcd_and_ply() {
+        let cloud = PointCloud::from_points(vec![
+            PointXYZRGBNormal::new(1.0, 2.0, 3.0, 255, 0, 10, 0.0, 0.0, 1.0),
+            PointXYZRGBNormal::new(-1.0, 0.5, 8.0, 1, 2, 3, 0.0, 1.0, 0.0),
+        ]);
+
+        let pcd = NamedTempFile::new().unwrap();
+        save_pcd_with_format(&cloud, pcd.path(), PcdDataFormat::Binary).unwrap();
+        let mapped: MappedPointCloud<PointXYZRGB> = MappedPointCloud::open_pcd(pcd.path()).unwrap();
+        assert_eq!(mapped.len(), 2);
+        assert_eq!(mapped.get(0).unwrap().color(), Some([255, 0, 10]));
+
+        for encoding in [
+            PlyEncoding::BinaryLittleEndian,
+            PlyEncoding::BinaryBigEndian,
+        ] {
+            let ply = NamedTempFile::new().unwrap();
+            save_ply_with_encoding(&cloud, ply.path(), encoding).unwrap();
+            let mapped: MappedPointCloud<PointXYZRGBNormal> =
+                MappedPointCloud::open(ply.path()).unwrap();
+            assert_eq!(mapped.to_cloud().points(), cloud.points());
+        }
+
+        let ascii = NamedTempFile::new().unwrap();
+        save_pcd(&cloud, ascii.path()).unwrap();
+        let error = MappedPointCloud::<PointXYZ>::open(ascii.path())
+            .err()
+            .unwrap();
+        assert!(error.to_string().contains("binary"));
+    }
+
+    #[test]
+    fn test_rejects_crafted_ply_offsets() {
+        let header = |count: usize| {
+            format!(
+                "ply\nformat binary_little_endian 1.0\nelement camera {}\n\
+                 property double k\nelement vertex 0\nproperty float x\n\
+                 property float y\nproperty float z\nend_header\n",
+                count
+            )
+        };
+        let file = NamedTempFile::new().unwrap();
+        for count in [usize::MAX, 1000] {
+            std::fs::write(file.path(), header(count)).unwrap();
+            assert!(MappedPointCloud::<PointXYZ>::open(file.path()).is_err());
+        }
+    }
+}
@@ -1,13 +1,493 @@
 //! Input/Output operations for point clouds
 //!
 //! This module provides functionality for reading and writing point clouds
-//! in various formats including PCD, PLY, and LAS.
+//! in various formats including PCD, PLY, LAS, E57, delimited text and a
+//! native binary format with a JSON variant for debugging.
+//!
+//! [`load`] and [`save`] pick the format from the file's magic bytes and
+//! extension. Formats are provided by [`PointCloudReader`] and
+//! [`PointCloudWriter`] implementations held in a global registry; additional
+//! formats can be added with [`register_reader`] and [`register_writer`].
+//!
+//! Binary files too large to load can be opened as a [`MappedPointCloud`],
+//! or read and written in chunks with [`ChunkReader`] and [`ChunkWriter`].
+//! ROS `PointCloud2` messages and recordings are handled by [`ros`].
 
+pub mod e57;
 pub mod las;
+pub mod mmap;
+pub mod native;
 pub mod pcd;
 pub mod ply;
+pub mod ros;
+pub mod stream;
+pub mod text;
 
 // Re-export commonly used functions
-pub use las::{load_las, save_las};
-pub use pcd::{load_pcd, save_pcd};
-pub use ply::{load_ply, save_ply};
+pub use e57::{E57Config, E57Format, load_e57, load_e57_scans, load_e57_scans_with_config};
+pub use las::{LasFormat, LasPoint, load_las, save_las};
+pub use mmap::MappedPointCloud;
+pub use native::{
+    NativeConfig, NativeFormat, load_native, load_native_json, save_native, save_native_json,
+    save_native_with_config,
+};
+pub use pcd::{PcdDataFormat, PcdFormat, load_pcd, load_pcd_as, save_pcd, save_pcd_with_format};
+pub use ply::{
+    PlyData, PlyEncoding, PlyFormat, load_ply, load_ply_as, load_ply_data, save_ply, save_ply_data,
+    save_ply_with_encoding,
+};
+pub use ros::{PointCloud2, load_bag, load_mcap};
+pub use stream::{ChunkReader, ChunkWriter};
+pub use text::{TextConfig, TextFormat, load_text, save_text};
+
+use crate::core::{FieldDescriptor, FieldType, Metadata, PointCloud, PointFields};
+use crate::error::{CloudError, Result};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::sync::{Arc, LazyLock, RwLock};
+
+/// Number of leading bytes passed to [`PointCloudReader::matches_magic`]
+pub const MAGIC_LENGTH: usize = 64;
+
+/// Reader for one point cloud file format
+///
+/// Readers work on named fields rather than a concrete point type: `read`
+/// receives the fields of the requested point type and reports every point
+/// as a row of values in that order.
+pub trait PointCloudReader: Send + Sync {
+    /// Short name of the format, e.g. `"pcd"`
+    fn name(&self) -> &str;
+
+    /// Lowercase file extensions (without the dot) handled by the reader
+    fn extensions(&self) -> &[&str];
+
+    /// Check whether the first bytes of a file belong to this format
+    ///
+    /// At most [`MAGIC_LENGTH`] bytes are given. Formats without a signature
+    /// keep the default, which relies on the file extension alone.
+    fn matches_magic(&self, _bytes: &[u8]) -> bool {
+        false
+    }
+
+    /// Read every point of a file
+    ///
+    /// `sink` is called once per point with the values of `fields`. Returns
+    /// the metadata of the cloud, or a format error if the file does not
+    /// provide all requested fields.
+    fn read(
+        &self,
+        path: &Path,
+        fields: &[FieldDescriptor],
+        sink: &mut dyn FnMut(&[f64]),
+    ) -> Result<Metadata>;
+}
+
+/// Writer for one point cloud file format
+pub trait PointCloudWriter: Send + Sync {
+    /// Short name of the format, e.g. `"pcd"`
+    fn name(&self) -> &str;
+
+    /// Lowercase file extensions (without the dot) handled by the writer
+    fn extensions(&self) -> &[&str];
+
+    /// Write all points to a file
+    fn write(&self, path: &Path, rows: &PointRows<'_>) -> Result<()>;
+}
+
+/// Point cloud seen as rows of named field values
+///
+/// This is how point clouds are handed to a [`PointCloudWriter`], which does
+/// not know the concrete point type.
+pub struct PointRows<'a> {
+    fields: &'static [FieldDescriptor],
+    metadata: &'a Metadata,
+    len: usize,
+    row: Box<RowWriter<'a>>,
+}
+
+/// Callback filling the field values of the point at an index
+type RowWriter<'a> = dyn Fn(usize, &mut [f64]) + 'a;
+
+impl<'a> PointRows<'a> {
+    /// View a point cloud as rows of its point fields
+    pub fn from_cloud<T: PointFields>(cloud: &'a PointCloud<T>) -> Self {
+        Self {
+            fields: T::fields(),
+            metadata: cloud.metadata(),
+            len: cloud.len(),
+            row: Box::new(move |index, values| cloud.points()[index].write_values(values)),
+        }
+    }
+
+    /// Fields of every row
+    pub fn fields(&self) -> &'static [FieldDescriptor] {
+        self.fields
+    }
+
+    /// Metadata of the cloud
+    pub fn metadata(&self) -> &Metadata {
+        self.metadata
+    }
+
+    /// Number of points
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Check whether there are no points
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Write the field values of the point at `index` into `values`
+    ///
+    /// `values` must hold at least `fields().len()` elements.
+    pub fn write_row(&self, index: usize, values: &mut [f64]) {
+        (self.row)(index, values)
+    }
+}
+
+struct Registry {
+    readers: Vec<Arc<dyn PointCloudReader>>,
+    writers: Vec<Arc<dyn PointCloudWriter>>,
+}
+
+impl Registry {
+    /// Registry holding the built-in formats
+    fn builtin() -> Self {
+        Self {
+            readers: vec![
+                Arc::new(PcdFormat::default()),
+                Arc::new(PlyFormat::default()),
+                Arc::new(LasFormat),
+                Arc::new(E57Format),
+                Arc::new(NativeFormat),
+                Arc::new(TextFormat::xyz()),
+                Arc::new(TextFormat::csv()),
+                Arc::new(TextFormat::pts()),
+            ],
+            writers: vec![
+                Arc::new(PcdFormat::default()),
+                Arc::new(PlyFormat::default()),
+                Arc::new(LasFormat),
+                Arc::new(NativeFormat),
+                Arc::new(TextFormat::xyz()),
+                Arc::new(TextFormat::csv()),
+                Arc::new(TextFormat::pts()),
+            ],
+        }
+    }
+
+    /// Reader for a file, chosen by magic bytes first and by extension otherwise
+    fn reader_for(&self, magic: &[u8], extension: &str) -> Option<Arc<dyn PointCloudReader>> {
+        let by_magic = self.readers.iter().rev().find(|r| r.matches_magic(magic));
+        by_magic
+            .or_else(|| {
+                self.readers
+                    .iter()
+                    .rev()
+                    .find(|r| handles_extension(r.extensions(), extension))
+            })
+            .cloned()
+    }
+
+    /// Writer for a file extension
+    fn writer_for(&self, extension: &str) -> Option<Arc<dyn PointCloudWriter>> {
+        self.writers
+            .iter()
+            .rev()
+            .find(|w| handles_extension(w.extensions(), extension))
+            .cloned()
+    }
+}
+
+static REGISTRY: LazyLock<RwLock<Registry>> = LazyLock::new(|| RwLock::new(Registry::builtin()));
+
+/// Register a reader used by [`load`]
+///
+/// Readers registered later take precedence over earlier ones and over the
+/// built-in formats, so a format can be overridden by registering a new
+/// reader for the same extension.
+pub fn register_reader<R: PointCloudReader + 'static>(reader: R) {
+    REGISTRY
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .readers
+        .push(Arc::new(reader));
+}
+
+/// Register a writer used by [`save`]
+///
+/// Writers registered later take precedence over earlier ones and over the
+/// built-in formats.
+pub fn register_writer<W: PointCloudWriter + 'static>(writer: W) {
+    REGISTRY
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .writers
+        .push(Arc::new(writer));
+}
+
+/// Load a point cloud, detecting the format
+///
+/// The format is chosen by the file's magic bytes first and by its extension
+/// otherwise. Point fields are matched by name, see [`PointFields`].
+///
+/// # Example
+/// ```rust,no_run
+/// use ferrum_cloud::prelude::*;
+///
+/// let cloud: PointCloud<PointXYZRGB> = io::load("scan.ply")?;
+/// io::save(&cloud, "scan.pcd")?;
+/// # Ok::<(), CloudError>(())
+/// ```
+pub fn load<T: PointFields, P: AsRef<Path>>(path: P) -> Result<PointCloud<T>> {
+    load_with(&REGISTRY, path.as_ref())
+}
+
+fn load_with<T: PointFields>(registry: &RwLock<Registry>, path: &Path) -> Result<PointCloud<T>> {
+    let mut magic = Vec::with_capacity(MAGIC_LENGTH);
+    File::open(path)?
+        .take(MAGIC_LENGTH as u64)
+        .read_to_end(&mut magic)?;
+    let extension = extension_of(path);
+
+    let reader = registry
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .reader_for(&magic, &extension)
+        .ok_or_else(|| {
+            CloudError::format_error(format!(
+                "Unrecognized point cloud format: {}",
+                path.display()
+            ))
+        })?;
+
+    let mut points = Vec::new();
+    let metadata = reader.read(path, T::fields(), &mut |values| {
+        points.push(T::from_values(values))
+    })?;
+    Ok(PointCloud::from_points_and_metadata(points, metadata))
+}
+
+/// Save a point cloud, choosing the format from the file extension
+///
+/// The built-in writers produce binary PCD, little-endian binary PLY,
+/// LAS/LAZ, native (`.fpc`) and text (`.xyz`, `.txt`, `.csv`, `.pts`) files.
+/// Use the format-specific functions for other encodings, or
+/// register a writer configured differently.
+pub fn save<T: PointFields, P: AsRef<Path>>(cloud: &PointCloud<T>, path: P) -> Result<()> {
+    save_with(&REGISTRY, cloud, path.as_ref())
+}
+
+fn save_with<T: PointFields>(
+    registry: &RwLock<Registry>,
+    cloud: &PointCloud<T>,
+    path: &Path,
+) -> Result<()> {
+    let extension = extension_of(path);
+
+    let writer = registry
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .writer_for(&extension)
+        .ok_or_else(|| {
+            CloudError::format_error(format!(
+                "No writer registered for file extension '{}'",
+                extension
+            ))
+        })?;
+
+    writer.write(path, &PointRows::from_cloud(cloud))
+}
+
+fn extension_of(path: &Path) -> String {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+        .unwrap_or_default()
+}
+
+fn handles_extension(extensions: &[&str], extension: &str) -> bool {
+    !extension.is_empty() && extensions.contains(&extension)
+}
+
+/// Format a float for ASCII output so that it reads back exactly
+pub(crate) fn format_ascii_float(value: f64, datatype: FieldType) -> String {
+    if value.is_nan() {
+        "nan".to_string()
+    } else if value == 0.0 || (1e-4..1e7).contains(&value.abs()) {
+        match datatype {
+            FieldType::F32 => (value as f32).to_string(),
+            _ => value.to_string(),
+        }
+    } else {
+        match datatype {
+            FieldType::F32 => format!("{:e}", value as f32),
+            _ => format!("{:e}", value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{Point, PointXYZ, PointXYZRGB, PointXYZRGBNormal};
+    use std::io::{BufRead, BufReader, Write};
+    use tempfile::TempDir;
+
+    fn colored_cloud() -> PointCloud<PointXYZRGB> {
+        PointCloud::from_points(vec![
+            PointXYZRGB::new(1.0, 2.0, 3.0, 255, 0, 0),
+            PointXYZRGB::new(-4.5, 5.25, 6.0, 10, 20, 30),
+        ])
+    }
+
+    #[test]
+    fn test_dispatch_on_extension() {
+        let dir = TempDir::new().unwrap();
+        let cloud = colored_cloud();
+
+        for name in [
+            "cloud.pcd",
+            "cloud.PLY",
+            "cloud.las",
+            "cloud.laz",
+            "cloud.csv",
+            "cloud.fpc",
+        ] {
+            let path = dir.path().join(name);
+            save(&cloud, &path).unwrap();
+
+            let loaded: PointCloud<PointXYZRGB> = load(&path).unwrap();
+            assert_eq!(loaded.points(), cloud.points(), "{}", name);
+        }
+
+        // LAS has no normals
+        let path = dir.path().join("cloud.las");
+        assert!(load::<PointXYZRGBNormal, _>(&path).is_err());
+    }
+
+    #[test]
+    fn test_magic_bytes_override_extension() {
+        let dir = TempDir::new().unwrap();
+        let cloud = colored_cloud();
+
+        let ply_path = dir.path().join("cloud.ply");
+        save(&cloud, &ply_path).unwrap();
+        let misnamed = dir.path().join("cloud.pcd");
+        std::fs::rename(&ply_path, &misnamed).unwrap();
+        let loaded: PointCloud<PointXYZ> = load(&misnamed).unwrap();
+        assert_eq!(loaded.len(), 2);
+
+        let las_path = dir.path().join("cloud.las");
+        save(&cloud, &las_path).unwrap();
+        let unnamed = dir.path().join("cloud");
+        std::fs::rename(&las_path, &unnamed).unwrap();
+        let loaded: PointCloud<PointXYZ> = load(&unnamed).unwrap();
+        assert_eq!(loaded.get(1).unwrap().position(), [-4.5, 5.25, 6.0]);
+    }
+
+    #[test]
+    fn test_unknown_format() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("cloud.unknown");
+        assert!(save(&colored_cloud(), &path).is_err());
+
+        std::fs::write(&path, b"not a point cloud").unwrap();
+        assert!(load::<PointXYZ, _>(&path).is_err());
+    }
+
+    /// One point per line, values separated by spaces, under the given extension
+    struct LineFormat(&'static str);
+
+    impl PointCloudReader for LineFormat {
+        fn name(&self) -> &str {
+            "line"
+        }
+
+        fn extensions(&self) -> &[&str] {
+            std::slice::from_ref(&self.0)
+        }
+
+        fn read(
+            &self,
+            path: &Path,
+            fields: &[FieldDescriptor],
+            sink: &mut dyn FnMut(&[f64]),
+        ) -> Result<Metadata> {
+            let mut count = 0;
+            for line in BufReader::new(File::open(path)?).lines() {
+                let values: Vec<f64> = line?
+                    .split_whitespace()
+                    .map(|v| v.parse().unwrap())
+                    .collect();
+                sink(&values[..fields.len()]);
+                count += 1;
+            }
+            Ok(Metadata::new_unorganized(count))
+        }
+    }
+
+    impl PointCloudWriter for LineFormat {
+        fn name(&self) -> &str {
+            "line"
+        }
+
+        fn extensions(&self) -> &[&str] {
+            std::slice::from_ref(&self.0)
+        }
+
+        fn write(&self, path: &Path, rows: &PointRows<'_>) -> Result<()> {
+            let mut file = File::create(path)?;
+            let mut values = vec![0.0; rows.fields().len()];
+            for index in 0..rows.len() {
+                rows.write_row(index, &mut values);
+                let line: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+                writeln!(file, "{}", line.join(" "))?;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_registered_format() {
+        // Register into a local registry so other tests see only the built-ins
+        let mut registry = Registry::builtin();
+        registry.readers.push(Arc::new(LineFormat("line")));
+        registry.writers.push(Arc::new(LineFormat("line")));
+        let registry = RwLock::new(registry);
+
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("cloud.line");
+        let cloud = colored_cloud();
+        assert!(save(&cloud, &path).is_err());
+        save_with(&registry, &cloud, &path).unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(contents.lines().next(), Some("1 2 3 255 0 0"));
+
+        let loaded: PointCloud<PointXYZRGB> = load_with(&registry, &path).unwrap();
+        assert_eq!(loaded.points(), cloud.points());
+    }
+
+    #[test]
+    fn test_register_public_format() {
+        // The extension is used by no other test, so registering globally
+        // does not change how their files are dispatched
+        register_reader(LineFormat("publicline"));
+        register_writer(LineFormat("publicline"));
+
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("cloud.publicline");
+        let cloud = colored_cloud();
+        save(&cloud, &path).unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(contents.lines().next(), Some("1 2 3 255 0 0"));
+
+        let loaded: PointCloud<PointXYZRGB> = load(&path).unwrap();
+        assert_eq!(loaded.points(), cloud.points());
+    }
+}
@@ -0,0 +1,825 @@
+//! Native binary point cloud format
+//!
+//! A simple binary format that stores the fields of a point type exactly as
+//! laid out in memory, together with the full cloud [`Metadata`]. The point
+//! data starts at an aligned offset, so uncompressed files can be memory
+//! mapped and viewed without decoding, see
+//! [`MappedPointCloud`](super::MappedPointCloud).
+//!
+//! The point data can optionally be LZF compressed and protected by a CRC-32
+//! checksum, which makes the format suitable for caching intermediate results
+//! between pipeline stages. For debugging, the same content can be written as
+//! JSON with [`save_native_json`].
+//!
+//! Layout (all integers little-endian):
+//!
+//! | Offset | Size | Content                                   |
+//! |--------|------|-------------------------------------------|
+//! | 0      | 8    | magic `FERRUMPC`                          |
+//! | 8      | 4    | format version                            |
+//! | 12     | 4    | flags: 1 = compressed, 2 = checksum       |
+//! | 16     | 8    | number of points                          |
+//! | 24     | 4    | record size in bytes                      |
+//! | 28     | 4    | number of fields                          |
+//! | 32     | 4    | length of the metadata JSON               |
+//! | 36     | 8    | size of the stored point data             |
+//! | 44     | 4    | CRC-32 of the stored point data, or 0     |
+//! | 48     |      | fields: type code (u8), name length (u8), name |
+//! |        |      | metadata as JSON                          |
+//! |        |      | zero padding up to a multiple of 64 bytes |
+//! |        |      | point records, fields packed in order     |
+
+use super::pcd::{LZF_MAX_EXPANSION, lzf_compress, read_value, write_value};
+use super::{PointCloudReader, PointCloudWriter, PointRows};
+use crate::core::{FieldDescriptor, FieldType, Metadata, PointCloud, PointFields};
+use crate::error::{CloudError, Result};
+use byteorder::{ByteOrder, LittleEndian};
+use serde::ser::{SerializeSeq, Serializer};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::fs::{self, File};
+use std::io::{BufWriter, Read, Write};
+use std::path::Path;
+
+/// File signature of native point cloud files
+pub const NATIVE_MAGIC: &[u8; 8] = b"FERRUMPC";
+
+/// Version written by this implementation
+pub const NATIVE_VERSION: u32 = 1;
+
+/// Alignment of the point data within the file
+pub const NATIVE_DATA_ALIGNMENT: usize = 64;
+
+/// Point data is LZF compressed
+const FLAG_COMPRESSED: u32 = 1;
+
+/// Point data is protected by a CRC-32 checksum
+const FLAG_CHECKSUM: u32 = 2;
+
+/// Size of the fixed part of the header
+const FIXED_HEADER_SIZE: usize = 48;
+
+/// Options for writing native files
+#[derive(Clone, Debug, PartialEq)]
+pub struct NativeConfig {
+    /// Compress the point data with LZF
+    ///
+    /// Compressed files are smaller but cannot be memory mapped.
+    pub compress: bool,
+
+    /// Store a CRC-32 checksum of the point data, verified when loading
+    pub checksum: bool,
+}
+
+impl Default for NativeConfig {
+    fn default() -> Self {
+        Self {
+            compress: false,
+            checksum: true,
+        }
+    }
+}
+
+/// Field stored in a native file
+#[derive(Clone, Debug, PartialEq)]
+pub struct NativeField {
+    pub name: String,
+    pub datatype: FieldType,
+    /// Byte offset of the field within a record
+    pub offset: usize,
+}
+
+/// Header of a native point cloud file
+#[derive(Clone, Debug, PartialEq)]
+pub struct NativeHeader {
+    pub version: u32,
+    pub fields: Vec<NativeField>,
+    pub points: usize,
+    pub record_size: usize,
+    pub metadata: Metadata,
+    /// Whether the point data is LZF compressed
+    pub compressed: bool,
+    /// CRC-32 of the stored point data, if the file has one
+    pub checksum: Option<u32>,
+    /// Byte offset of the point records within the file
+    pub data_offset: usize,
+    /// Size of the stored point data in bytes
+    pub data_size: usize,
+}
+
+impl NativeHeader {
+    /// Field with the given name
+    pub fn field(&self, name: &str) -> Option<&NativeField> {
+        self.fields.iter().find(|field| field.name == name)
+    }
+}
+
+/// Read the header of a native point cloud file
+///
+/// Only the header itself is read, not the point data.
+pub fn read_native_header<P: AsRef<Path>>(path: P) -> Result<NativeHeader> {
+    let mut file = File::open(path.as_ref())?;
+    let file_len = usize::try_from(file.metadata()?.len()).unwrap_or(usize::MAX);
+
+    let mut bytes = Vec::with_capacity(FIXED_HEADER_SIZE);
+    (&mut file)
+        .take(FIXED_HEADER_SIZE as u64)
+        .read_to_end(&mut bytes)?;
+    if bytes.len() == FIXED_HEADER_SIZE {
+        // Field names are at most 255 bytes, so this bounds the rest of the
+        // header; reading stops early at the end of the file
+        let field_count = LittleEndian::read_u32(&bytes[28..]) as u64;
+        let metadata_length = LittleEndian::read_u32(&bytes[32..]) as u64;
+        file.take(field_count * 257 + metadata_length)
+            .read_to_end(&mut bytes)?;
+    }
+    parse_header_prefix(&bytes, file_len)
+}
+
+/// Load a point cloud from a native file
+///
+/// Fields are matched by name, so a file can be loaded into any point type
+/// whose fields it contains. Compressed data is decompressed and the
+/// checksum, if present, is verified.
+///
+/// # Example
+/// ```rust,no_run
+/// use ferrum_cloud::io::native::{load_native, save_native};
+/// use ferrum_cloud::prelude::*;
+///
+/// let cloud: PointCloud<PointXYZI> = load_native("cache.fpc")?;
+/// save_native(&cloud, "copy.fpc")?;
+/// # Ok::<(), CloudError>(())
+/// ```
+pub fn load_native<T: PointFields, P: AsRef<Path>>(path: P) -> Result<PointCloud<T>> {
+    let mut points = Vec::new();
+    let metadata = read_native_rows(path.as_ref(), T::fields(), &mut |values| {
+        points.push(T::from_values(values))
+    })?;
+    Ok(PointCloud::from_points_and_metadata(points, metadata))
+}
+
+/// Save a point cloud with its metadata to a native file
+///
+/// The point data is stored uncompressed with a checksum. See
+/// [`save_native_with_config`].
+pub fn save_native<T: PointFields, P: AsRef<Path>>(cloud: &PointCloud<T>, path: P) -> Result<()> {
+    save_native_with_config(cloud, path, &NativeConfig::default())
+}
+
+/// Save a point cloud with its metadata to a native file with custom options
+///
+/// # Example
+/// ```rust,no_run
+/// use ferrum_cloud::io::native::{NativeConfig, save_native_with_config};
+/// use ferrum_cloud::prelude::*;
+///
+/// let cloud = PointCloud::from_points(vec![PointXYZ::new(1.0, 2.0, 3.0)]);
+/// let config = NativeConfig {
+///     compress: true,
+///     ..Default::default()
+/// };
+/// save_native_with_config(&cloud, "stage1.fpc", &config)?;
+/// # Ok::<(), CloudError>(())
+/// ```
+pub fn save_native_with_config<T: PointFields, P: AsRef<Path>>(
+    cloud: &PointCloud<T>,
+    path: P,
+    config: &NativeConfig,
+) -> Result<()> {
+    write_native_rows(path.as_ref(), &PointRows::from_cloud(cloud), config)
+}
+
+/// Load a point cloud from the JSON variant of the native format
+///
+/// `null` values are read as NaN.
+pub fn load_native_json<T: PointFields, P: AsRef<Path>>(path: P) -> Result<PointCloud<T>> {
+    let document: JsonDocument = serde_json::from_slice(&fs::read(path.as_ref())?)?;
+    if document.format != "ferrum_cloud" || document.version > NATIVE_VERSION {
+        return Err(CloudError::format_error(format!(
+            "Unsupported native JSON document: format {:?}, version {}",
+            document.format, document.version
+        )));
+    }
+
+    let fields = T::fields();
+    let mut sources = Vec::with_capacity(fields.len());
+    let mut missing = Vec::new();
+    for descriptor in fields {
+        match document
+            .fields
+            .iter()
+            .position(|f| f.name == descriptor.name)
+        {
+            Some(index) => sources.push(index),
+            None => missing.push(descriptor.name),
+        }
+    }
+    if !missing.is_empty() {
+        return Err(CloudError::format_error(format!(
+            "Native JSON document is missing required fields: {}",
+            missing.join(", ")
+        )));
+    }
+
+    let mut values = vec![0.0f64; fields.len()];
+    let mut points = Vec::with_capacity(document.points.len());
+    for (index, row) in document.points.iter().enumerate() {
+        if row.len() != document.fields.len() {
+            return Err(CloudError::format_error(format!(
+                "Native JSON point {} has {} values, expected {}",
+                index,
+                row.len(),
+                document.fields.len()
+            )));
+        }
+        for (value, &source) in values.iter_mut().zip(sources.iter()) {
+            *value = row[source].unwrap_or(f64::NAN);
+        }
+        points.push(T::from_values(&values));
+    }
+
+    Ok(PointCloud::from_points_and_metadata(
+        points,
+        document.metadata,
+    ))
+}
+
+/// Save a point cloud with its metadata as JSON, for inspection and debugging
+///
+/// Every point is written on its own line as an array of field values, with
+/// NaN and infinite values written as `null`.
+pub fn save_native_json<T: PointFields, P: AsRef<Path>>(
+    cloud: &PointCloud<T>,
+    path: P,
+) -> Result<()> {
+    let rows = PointRows::from_cloud(cloud);
+    let fields: Vec<JsonField> = rows
+        .fields()
+        .iter()
+        .map(|field| JsonField {
+            name: Cow::Borrowed(field.name),
+            datatype: Cow::Borrowed(type_name(field.datatype)),
+        })
+        .collect();
+
+    let mut writer = BufWriter::new(File::create(path.as_ref())?);
+    writeln!(writer, "{{")?;
+    writeln!(writer, "  \"format\": \"ferrum_cloud\",")?;
+    writeln!(writer, "  \"version\": {},", NATIVE_VERSION)?;
+    writeln!(writer, "  \"fields\": {},", serde_json::to_string(&fields)?)?;
+    writeln!(
+        writer,
+        "  \"metadata\": {},",
+        serde_json::to_string(rows.metadata())?
+    )?;
+    write!(writer, "  \"points\": [")?;
+
+    let mut values = vec![0.0f64; rows.fields().len()];
+    for index in 0..rows.len() {
+        rows.write_row(index, &mut values);
+        let row = JsonRow {
+            fields: rows.fields(),
+            values: &values,
+        };
+        let separator = if index == 0 { "" } else { "," };
+        write!(
+            writer,
+            "{}\n    {}",
+            separator,
+            serde_json::to_string(&row)?
+        )?;
+    }
+    if !rows.is_empty() {
+        write!(writer, "\n  ")?;
+    }
+    writeln!(writer, "]")?;
+    writeln!(writer, "}}")?;
+
+    writer.flush()?;
+    Ok(())
+}
+
+/// Native format for the [`io::load`](super::load) and [`io::save`](super::save) registry
+///
+/// Writes with the default [`NativeConfig`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NativeFormat;
+
+impl PointCloudReader for NativeFormat {
+    fn name(&self) -> &str {
+        "native"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["fpc"]
+    }
+
+    fn matches_magic(&self, bytes: &[u8]) -> bool {
+        bytes.starts_with(NATIVE_MAGIC)
+    }
+
+    fn read(
+        &self,
+        path: &Path,
+        fields: &[FieldDescriptor],
+        sink: &mut dyn FnMut(&[f64]),
+    ) -> Result<Metadata> {
+        read_native_rows(path, fields, sink)
+    }
+}
+
+impl PointCloudWriter for NativeFormat {
+    fn name(&self) -> &str {
+        "native"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["fpc"]
+    }
+
+    fn write(&self, path: &Path, rows: &PointRows<'_>) -> Result<()> {
+        write_native_rows(path, rows, &NativeConfig::default())
+    }
+}
+
+/// Field of the JSON variant of the native format
+#[derive(Serialize, Deserialize)]
+struct JsonField<'a> {
+    name: Cow<'a, str>,
+    #[serde(rename = "type")]
+    datatype: Cow<'a, str>,
+}
+
+/// Document of the JSON variant of the native format
+#[derive(Deserialize)]
+struct JsonDocument {
+    format: String,
+    version: u32,
+    fields: Vec<JsonField<'static>>,
+    metadata: Metadata,
+    points: Vec<Vec<Option<f64>>>,
+}
+
+/// One point of the JSON variant, written with the precision of its fields
+struct JsonRow<'a> {
+    fields: &'a [FieldDescriptor],
+    values: &'a [f64],
+}
+
+impl Serialize for JsonRow<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.values.len()))?;
+        for (field, &value) in self.fields.iter().zip(self.values.iter()) {
+            if !value.is_finite() {
+                seq.serialize_element(&None::<f64>)?;
+                continue;
+            }
+            match field.datatype {
+                FieldType::F32 => seq.serialize_element(&(value as f32))?,
+                FieldType::F64 => seq.serialize_element(&value)?,
+                _ => seq.serialize_element(&(value as i64))?,
+            }
+        }
+        seq.end()
+    }
+}
+
+/// Read the requested fields of every point in a native file
+fn read_native_rows(
+    path: &Path,
+    fields: &[FieldDescriptor],
+    sink: &mut dyn FnMut(&[f64]),
+) -> Result<Metadata> {
+    let bytes = fs::read(path)?;
+    let header = parse_header(&bytes)?;
+    let sources = resolve_field_sources(&header, fields)?;
+
+    // parse_header checked that the stored data lies within the file
+    let stored = &bytes[header.data_offset..header.data_offset + header.data_size];
+    if let Some(expected) = header.checksum {
+        let actual = crc32fast::hash(stored);
+        if actual != expected {
+            return Err(CloudError::format_error(format!(
+                "Native point data checksum mismatch: expected {:#010x}, found {:#010x}",
+                expected, actual
+            )));
+        }
+    }
+
+    let data = if header.compressed {
+        // parse_header checked that this fits in usize and that the
+        // compressed data can expand to it
+        let size = header.points * header.record_size;
+        let data = if size == 0 {
+            Vec::new()
+        } else {
+            lzf::decompress(stored, size).map_err(|e| {
+                CloudError::format_error(format!("Failed to decompress native point data: {}", e))
+            })?
+        };
+        if data.len() != size {
+            return Err(CloudError::format_error(
+                "Native compressed point data has an unexpected size",
+            ));
+        }
+        Cow::Owned(data)
+    } else {
+        Cow::Borrowed(stored)
+    };
+
+    let mut values = vec![0.0f64; fields.len()];
+    for record in data.chunks_exact(header.record_size).take(header.points) {
+        for (value, field) in values.iter_mut().zip(sources.iter()) {
+            *value = read_value(field.datatype, &record[field.offset..]);
+        }
+        sink(&values);
+    }
+
+    Ok(header.metadata)
+}
+
+/// Write rows of point fields to a native file
+fn write_native_rows(path: &Path, rows: &PointRows<'_>, config: &NativeConfig) -> Result<()> {
+    let fields = rows.fields();
+    let record_size: usize = fields.iter().map(|f| f.datatype.size()).sum();
+    let metadata = serde_json::to_vec(rows.metadata())?;
+
+    let mut data = vec![0u8; rows.len() * record_size];
+    let mut values = vec![0.0f64; fields.len()];
+    for (index, record) in data.chunks_exact_mut(record_size.max(1)).enumerate() {
+        rows.write_row(index, &mut values);
+        let mut offset = 0;
+        for (field, &value) in fields.iter().zip(values.iter()) {
+            write_value(field.datatype, value, &mut record[offset..]);
+            offset += field.datatype.size();
+        }
+    }
+
+    let mut flags = 0;
+    if config.compress && !data.is_empty() {
+        data = lzf_compress(&data);
+        flags |= FLAG_COMPRESSED;
+    }
+    let mut checksum = 0;
+    if config.checksum {
+        checksum = crc32fast::hash(&data);
+        flags |= FLAG_CHECKSUM;
+    }
+
+    let mut header = Vec::with_capacity(FIXED_HEADER_SIZE + metadata.len());
+    header.extend_from_slice(NATIVE_MAGIC);
+    header.extend_from_slice(&NATIVE_VERSION.to_le_bytes());
+    header.extend_from_slice(&flags.to_le_bytes());
+    header.extend_from_slice(&(rows.len() as u64).to_le_bytes());
+    header.extend_from_slice(&(record_size as u32).to_le_bytes());
+    header.extend_from_slice(&(fields.len() as u32).to_le_bytes());
+    header.extend_from_slice(&(metadata.len() as u32).to_le_bytes());
+    header.extend_from_slice(&(data.len() as u64).to_le_bytes());
+    header.extend_from_slice(&checksum.to_le_bytes());
+    for field in fields {
+        header.push(type_code(field.datatype));
+        header.push(field.name.len() as u8);
+        header.extend_from_slice(field.name.as_bytes());
+    }
+    header.extend_from_slice(&metadata);
+    header.resize(header.len().next_multiple_of(NATIVE_DATA_ALIGNMENT), 0);
+
+    let mut writer = BufWriter::new(File::create(path)?);
+    writer.write_all(&header)?;
+    writer.write_all(&data)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Parse the header at the start of a native file
+pub(crate) fn parse_header(bytes: &[u8]) -> Result<NativeHeader> {
+    parse_header_prefix(bytes, bytes.len())
+}
+
+/// Parse a native header from the leading `bytes` of a file of `file_len` bytes
+fn parse_header_prefix(bytes: &[u8], file_len: usize) -> Result<NativeHeader> {
+    let truncated = || CloudError::format_error("Native point cloud header is truncated");
+
+    if !bytes.starts_with(NATIVE_MAGIC) {
+        return Err(CloudError::format_error(
+            "Not a native point cloud file (missing FERRUMPC signature)",
+        ));
+    }
+    let version = LittleEndian::read_u32(bytes.get(8..12).ok_or_else(truncated)?);
+    if version != NATIVE_VERSION {
+        return Err(CloudError::format_error(format!(
+            "Unsupported native point cloud version: {}",
+            version
+        )));
+    }
+    let fixed = bytes.get(..FIXED_HEADER_SIZE).ok_or_else(truncated)?;
+
+    let flags = LittleEndian::read_u32(&fixed[12..]);
+    if flags & !(FLAG_COMPRESSED | FLAG_CHECKSUM) != 0 {
+        return Err(CloudError::format_error(format!(
+            "Unsupported native point cloud flags: {:#x}",
+            flags
+        )));
+    }
+
+    let points = LittleEndian::read_u64(&fixed[16..]) as usize;
+    let record_size = LittleEndian::read_u32(&fixed[24..]) as usize;
+    let field_count = LittleEndian::read_u32(&fixed[28..]) as usize;
+    let metadata_length = LittleEndian::read_u32(&fixed[32..]) as usize;
+    let uncompressed_size = points
+        .checked_mul(record_size)
+        .ok_or_else(|| CloudError::format_error("Native point count is too large"))?;
+    let data_size = LittleEndian::read_u64(&fixed[36..]) as usize;
+    let checksum = (flags & FLAG_CHECKSUM != 0).then(|| LittleEndian::read_u32(&fixed[44..]));
+    let compressed = flags & FLAG_COMPRESSED != 0;
+    if !compressed && data_size != uncompressed_size {
+        return Err(CloudError::format_error(format!(
+            "Native point data size {} does not match {} points of {} bytes",
+            data_size, points, record_size
+        )));
+    }
+    if compressed && uncompressed_size / LZF_MAX_EXPANSION > data_size {
+        return Err(CloudError::format_error(format!(
+            "Native compressed data of {} bytes cannot hold {} points of {} bytes",
+            data_size, points, record_size
+        )));
+    }
+    if record_size == 0 {
+        return Err(CloudError::format_error(
+            "Native point records have no fields",
+        ));
+    }
+
+    let mut position = FIXED_HEADER_SIZE;
+    let mut fields = Vec::new();
+    let mut offset = 0;
+    for _ in 0..field_count {
+        let code = *bytes.get(position).ok_or_else(truncated)?;
+        let length = *bytes.get(position + 1).ok_or_else(truncated)? as usize;
+        position += 2;
+        let name = bytes
+            .get(position..position + length)
+            .ok_or_else(truncated)?;
+        position += length;
+
+        let datatype = type_from_code(code)?;
+        let name = std::str::from_utf8(name)
+            .map_err(|_| CloudError::format_error("Native field name is not valid UTF-8"))?;
+        fields.push(NativeField {
+            name: name.to_string(),
+            datatype,
+            offset,
+        });
+        offset += datatype.size();
+    }
+    if offset != record_size {
+        return Err(CloudError::format_error(format!(
+            "Native record size {} does not match its fields ({} bytes)",
+            record_size, offset
+        )));
+    }
+
+    let metadata = bytes
+        .get(position..position + metadata_length)
+        .ok_or_else(truncated)?;
+    let metadata: Metadata = serde_json::from_slice(metadata)?;
+    position += metadata_length;
+
+    let data_offset = position.next_multiple_of(NATIVE_DATA_ALIGNMENT);
+    if data_offset
+        .checked_add(data_size)
+        .is_none_or(|end| end > file_len)
+    {
+        return Err(CloudError::format_error(format!(
+            "Native point data is truncated: expected {} bytes, found {}",
+            data_size,
+            file_len.saturating_sub(data_offset)
+        )));
+    }
+
+    Ok(NativeHeader {
+        version,
+        fields,
+        points,
+        record_size,
+        metadata,
+        compressed,
+        checksum,
+        data_offset,
+        data_size,
+    })
+}
+
+/// Map every requested field to its location in the native records
+pub(crate) fn resolve_field_sources<'a>(
+    header: &'a NativeHeader,
+    fields: &[FieldDescriptor],
+) -> Result<Vec<&'a NativeField>> {
+    let mut sources = Vec::with_capacity(fields.len());
+    let mut missing = Vec::new();
+    for descriptor in fields {
+        match header.field(descriptor.name) {
+            Some(field) => sources.push(field),
+            None => missing.push(descriptor.name),
+        }
+    }
+
+    if !missing.is_empty() {
+        let available: Vec<&str> = header.fields.iter().map(|f| f.name.as_str()).collect();
+        return Err(CloudError::format_error(format!(
+            "Native file is missing required fields: {} (available fields: {})",
+            missing.join(", "),
+            available.join(", ")
+        )));
+    }
+    Ok(sources)
+}
+
+fn type_code(datatype: FieldType) -> u8 {
+    match datatype {
+        FieldType::I8 => 1,
+        FieldType::I16 => 2,
+        FieldType::I32 => 3,
+        FieldType::U8 => 4,
+        FieldType::U16 => 5,
+        FieldType::U32 => 6,
+        FieldType::F32 => 7,
+        FieldType::F64 => 8,
+    }
+}
+
+fn type_from_code(code: u8) -> Result<FieldType> {
+    Ok(match code {
+        1 => FieldType::I8,
+        2 => FieldType::I16,
+        3 => FieldType::I32,
+        4 => FieldType::U8,
+        5 => FieldType::U16,
+        6 => FieldType::U32,
+        7 => FieldType::F32,
+        8 => FieldType::F64,
+        _ => {
+            return Err(CloudError::format_error(format!(
+                "Unknown native field type code: {}",
+                code
+            )));
+        }
+    })
+}
+
+fn type_name(datatype: FieldType) -> &'static str {
+    match datatype {
+        FieldType::I8 => "i8",
+        FieldType::I16 => "i16",
+        FieldType::I32 => "i32",
+        FieldType::U8 => "u8",
+        FieldType::U16 => "u16",
+        FieldType::U32 => "u32",
+        FieldType::F32 => "f32",
+        FieldType::F64 => "f64",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{PointXYZ, PointXYZRGBNormal};
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_roundtrip_with_metadata() {
+        let mut cloud = PointCloud::from_points(vec![
+            PointXYZRGBNormal::new(1.0, -2.0, 3.5, 10, 20, 30, 0.0, 0.0, 1.0),
+            PointXYZRGBNormal::new(f32::NAN, 0.0, 1e-9, 255, 0, 128, 1.0, 0.0, 0.0),
+        ]);
+        *cloud.metadata_mut() = Metadata::new_organized(2, 1)
+            .with_sensor_origin([1.0, 2.0, 3.0])
+            .with_custom_field("stage", "filtered");
+
+        let file = NamedTempFile::new().unwrap();
+        save_native(&cloud, file.path()).unwrap();
+
+        let header = read_native_header(file.path()).unwrap();
+        assert_eq!(header.data_offset % NATIVE_DATA_ALIGNMENT, 0);
+        assert_eq!(header.record_size, 27);
+        assert_eq!(header.field("normal_z").unwrap().offset, 23);
+
+        let loaded: PointCloud<PointXYZRGBNormal> = load_native(file.path()).unwrap();
+        assert_eq!(loaded.metadata(), cloud.metadata());
+        assert!(loaded.get(1).unwrap().x.is_nan());
+        assert_eq!(loaded.get(0), cloud.get(0));
+
+        let positions: PointCloud<PointXYZ> = load_native(file.path()).unwrap();
+        assert_eq!(positions.get(0).unwrap(), &PointXYZ::new(1.0, -2.0, 3.5));
+    }
+
+    #[test]
+    fn test_invalid_files() {
+        let file = NamedTempFile::new().unwrap();
+        save_native(
+            &PointCloud::from_points(vec![PointXYZ::new(1.0, 2.0, 3.0)]),
+            file.path(),
+        )
+        .unwrap();
+        let bytes = fs::read(file.path()).unwrap();
+
+        assert!(parse_header(&bytes[..bytes.len() - 1]).is_err());
+        assert!(parse_header(&bytes[..20]).is_err());
+
+        let mut future = bytes.clone();
+        future[8] = 99;
+        let error = parse_header(&future).unwrap_err();
+        assert!(error.to_string().contains("version"));
+
+        let error = load_native::<PointXYZRGBNormal, _>(file.path()).unwrap_err();
+        assert!(error.to_string().contains("normal_x"));
+
+        // The header reader still checks that the point data is present
+        let truncated = NamedTempFile::new().unwrap();
+        fs::write(truncated.path(), &bytes[..bytes.len() - 1]).unwrap();
+        assert!(read_native_header(truncated.path()).is_err());
+        fs::write(truncated.path(), &bytes[..20]).unwrap();
+        assert!(read_native_header(truncated.path()).is_err());
+    }
+
+    #[test]
+    fn test_compression_and_checksum() {
+        let points: Vec<PointXYZ> = (0..500)
+            .map(|i| PointXYZ::new((i % 10) as f32, 0.0, 1.0))
+            .collect();
+        let cloud = PointCloud::from_points(points);
+        let file = NamedTempFile::new().unwrap();
+        let config = NativeConfig {
+            compress: true,
+            checksum: true,
+        };
+        save_native_with_config(&cloud, file.path(), &config).unwrap();
+
+        let header = read_native_header(file.path()).unwrap();
+        assert!(header.compressed);
+        assert!(header.checksum.is_some());
+        assert!(header.data_size < 500 * 12);
+
+        let loaded: PointCloud<PointXYZ> = load_native(file.path()).unwrap();
+        assert_eq!(loaded.points(), cloud.points());
+        assert!(crate::io::MappedPointCloud::<PointXYZ>::open(file.path()).is_err());
+
+        // Corrupted point data is detected by the checksum
+        let mut bytes = fs::read(file.path()).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+        fs::write(file.path(), &bytes).unwrap();
+        let error = load_native::<PointXYZ, _>(file.path()).unwrap_err();
+        assert!(error.to_string().contains("checksum"));
+    }
+
+    #[test]
+    fn test_rejects_crafted_sizes() {
+        let cloud = PointCloud::from_points(vec![PointXYZ::new(1.0, 2.0, 3.0); 100]);
+        let file = NamedTempFile::new().unwrap();
+        let config = NativeConfig {
+            compress: true,
+            checksum: false,
+        };
+        save_native_with_config(&cloud, file.path(), &config).unwrap();
+        let bytes = fs::read(file.path()).unwrap();
+        assert!(parse_header(&bytes).is_ok());
+
+        let mut oversized = bytes.clone();
+        LittleEndian::write_u64(&mut oversized[36..44], u64::MAX);
+        assert!(parse_header(&oversized).is_err());
+
+        let mut inflated = bytes.clone();
+        LittleEndian::write_u64(&mut inflated[16..24], 1 << 40);
+        assert!(parse_header(&inflated).is_err());
+    }
+
+    #[test]
+    fn test_json_roundtrip() {
+        let mut cloud = PointCloud::from_points(vec![
+            PointXYZRGBNormal::new(0.1, -2.0, 3.5, 10, 20, 30, 0.0, 0.0, 1.0),
+            PointXYZRGBNormal::new(f32::NAN, 0.0, 1e-9, 255, 0, 128, 1.0, 0.0, 0.0),
+        ]);
+        *cloud.metadata_mut() = Metadata::new_organized(2, 1).with_custom_field("stage", "raw");
+
+        let file = NamedTempFile::new().unwrap();
+        save_native_json(&cloud, file.path()).unwrap();
+        let text = fs::read_to_string(file.path()).unwrap();
+        assert!(text.contains("[0.1,-2.0,3.5,10,20,30,0.0,0.0,1.0]"));
+        assert!(text.contains("[null,"));
+
+        let loaded: PointCloud<PointXYZRGBNormal> = load_native_json(file.path()).unwrap();
+        assert_eq!(loaded.metadata(), cloud.metadata());
+        assert_eq!(loaded.get(0), cloud.get(0));
+        assert!(loaded.get(1).unwrap().x.is_nan());
+
+        let empty = PointCloud::<PointXYZ>::new();
+        save_native_json(&empty, file.path()).unwrap();
+        assert!(
+            load_native_json::<PointXYZ, _>(file.path())
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
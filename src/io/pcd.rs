@@ -3,156 +3,1164 @@
 //! This module provides functionality for reading and writing PCD files,
 //! which is the native format of the Point Cloud Library (PCL).
 //!
-//! Uses the `pcd-rs` crate for efficient and robust PCD file handling.
+//! All three PCD data encodings are supported: `ascii`, `binary` and
+//! `binary_compressed`. Point attributes are mapped to PCD fields by name
+//! through the [`PointFields`] trait.
 
-use crate::core::{Metadata, Point, PointCloud, PointXYZ};
+use super::stream::{RowSink, RowSource, TokenReader, pad_count_lines};
+use super::{PointCloudReader, PointCloudWriter, PointRows, format_ascii_float};
+use crate::core::{FieldDescriptor, FieldType, Metadata, PointCloud, PointFields, PointXYZ};
 use crate::error::{CloudError, Result};
-use pcd_rs::{DataKind, DynReader, DynRecord, DynWriter, Field, Schema, ValueKind, WriterInit};
+use byteorder::{ByteOrder, LittleEndian};
+use std::borrow::Cow;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, ErrorKind, Read, Seek, SeekFrom, Write};
 use std::path::Path;
 
+/// Data encoding of a PCD file
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PcdDataFormat {
+    /// Whitespace separated text, one point per line
+    #[default]
+    Ascii,
+
+    /// Little-endian point records stored one after another
+    Binary,
+
+    /// LZF-compressed point data stored field by field
+    BinaryCompressed,
+}
+
+impl PcdDataFormat {
+    /// Keyword used on the `DATA` header line
+    pub fn keyword(&self) -> &'static str {
+        match self {
+            PcdDataFormat::Ascii => "ascii",
+            PcdDataFormat::Binary => "binary",
+            PcdDataFormat::BinaryCompressed => "binary_compressed",
+        }
+    }
+
+    fn from_keyword(keyword: &str) -> Option<Self> {
+        match keyword.to_ascii_lowercase().as_str() {
+            "ascii" => Some(PcdDataFormat::Ascii),
+            "binary" => Some(PcdDataFormat::Binary),
+            "binary_compressed" => Some(PcdDataFormat::BinaryCompressed),
+            _ => None,
+        }
+    }
+}
+
+/// Field declared in a PCD header
+#[derive(Clone, Debug, PartialEq)]
+pub struct PcdField {
+    /// Field name from the `FIELDS` line
+    pub name: String,
+
+    /// Scalar type from the `TYPE` and `SIZE` lines
+    pub datatype: FieldType,
+
+    /// Number of elements from the `COUNT` line
+    pub count: usize,
+}
+
+impl PcdField {
+    /// Size of the field in bytes, over all of its elements
+    pub fn size(&self) -> usize {
+        self.datatype.size() * self.count
+    }
+}
+
+/// Parsed header of a PCD file
+#[derive(Clone, Debug, PartialEq)]
+pub struct PcdHeader {
+    /// Format version, e.g. `0.7`
+    pub version: String,
+
+    /// Fields of every point, in storage order
+    pub fields: Vec<PcdField>,
+
+    /// Width of the cloud (number of points for unorganized clouds)
+    pub width: usize,
+
+    /// Height of the cloud (1 for unorganized clouds)
+    pub height: usize,
+
+    /// Sensor pose as `[tx, ty, tz, qw, qx, qy, qz]`
+    pub viewpoint: [f64; 7],
+
+    /// Number of points in the file
+    pub points: usize,
+
+    /// Encoding of the point data
+    pub data: PcdDataFormat,
+
+    /// Byte offset of the point data from the start of the file
+    pub data_offset: usize,
+}
+
+impl PcdHeader {
+    /// Size of one point record in bytes
+    pub fn point_size(&self) -> usize {
+        self.fields.iter().map(PcdField::size).sum()
+    }
+
+    /// Byte offset of every field within a point record
+    pub fn field_offsets(&self) -> Vec<usize> {
+        self.fields
+            .iter()
+            .scan(0, |offset, field| {
+                let current = *offset;
+                *offset += field.size();
+                Some(current)
+            })
+            .collect()
+    }
+}
+
+/// Read only the header of a PCD file
+pub fn read_pcd_header<P: AsRef<Path>>(path: P) -> Result<PcdHeader> {
+    let file = File::open(path.as_ref())?;
+    parse_header(&mut BufReader::new(file))
+}
+
 /// Load a point cloud from a PCD file
 ///
+/// Only the `x`, `y` and `z` fields are read; use [`load_pcd_as`] to load
+/// colors, normals or other fields.
+///
 /// # Arguments
 /// * `path` - Path to the PCD file
 ///
 /// # Returns
 /// A Result containing the loaded PointCloud or an error
 pub fn load_pcd<P: AsRef<Path>>(path: P) -> Result<PointCloud<PointXYZ>> {
-    let reader = DynReader::open(path.as_ref())
-        .map_err(|e| CloudError::format_error(format!("Failed to open PCD file: {}", e)))?;
+    load_pcd_as(path)
+}
 
+/// Load a point cloud from a PCD file into any point type with named fields
+///
+/// Every field of `T` is looked up by name in the PCD header, so the order
+/// of the fields in the file does not matter and extra fields are ignored.
+/// The `r`, `g` and `b` fields are also read from a packed `rgb` or `rgba`
+/// field as written by PCL. Organized clouds keep their width and height and
+/// the `VIEWPOINT` is stored as the sensor origin and orientation.
+///
+/// # Errors
+/// Returns a format error naming the missing fields if the file does not
+/// provide every field of `T`.
+pub fn load_pcd_as<T: PointFields, P: AsRef<Path>>(path: P) -> Result<PointCloud<T>> {
     let mut points = Vec::new();
-    let mut metadata = Metadata::default();
+    let metadata = read_pcd_rows(path.as_ref(), T::fields(), &mut |values| {
+        points.push(T::from_values(values))
+    })?;
+    Ok(PointCloud::from_points_and_metadata(points, metadata))
+}
 
-    // Extract metadata from PCD header
-    let pcd_meta = reader.meta();
-    metadata.width = pcd_meta.width as u32;
-    metadata.height = pcd_meta.height as u32;
-    metadata.is_organized = metadata.height > 1;
+/// Save a point cloud to an ASCII PCD file
+///
+/// # Arguments
+/// * `cloud` - The point cloud to save
+/// * `path` - Path where to save the PCD file
+///
+/// # Returns
+/// A Result indicating success or failure
+pub fn save_pcd<T: PointFields, P: AsRef<Path>>(cloud: &PointCloud<T>, path: P) -> Result<()> {
+    save_pcd_with_format(cloud, path, PcdDataFormat::Ascii)
+}
 
-    // Store additional metadata
-    metadata
-        .custom_fields
-        .insert("version".to_string(), "0.7".to_string());
+/// Save a point cloud to a PCD file with the given data encoding
+///
+/// All fields of the point type are written. Colors are packed into a single
+/// `rgb` field as PCL expects. Organized clouds keep their width and height,
+/// and the sensor origin and orientation are written as the `VIEWPOINT`.
+pub fn save_pcd_with_format<T: PointFields, P: AsRef<Path>>(
+    cloud: &PointCloud<T>,
+    path: P,
+    format: PcdDataFormat,
+) -> Result<()> {
+    write_pcd_rows(path.as_ref(), &PointRows::from_cloud(cloud), format)
+}
+
+/// PCD format for the [`io::load`](super::load) and [`io::save`](super::save) registry
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PcdFormat {
+    /// Encoding used when writing
+    pub data: PcdDataFormat,
+}
 
-    // Read point data
-    for record_result in reader {
-        let record = record_result
-            .map_err(|e| CloudError::format_error(format!("Failed to read PCD record: {}", e)))?;
+impl PcdFormat {
+    /// Create a PCD format writing the given encoding
+    pub fn new(data: PcdDataFormat) -> Self {
+        Self { data }
+    }
+}
 
-        // Extract x, y, z coordinates from the record
-        let (x, y, z) = extract_xyz_from_record(&record)?;
-        points.push(PointXYZ::new(x, y, z));
+impl Default for PcdFormat {
+    fn default() -> Self {
+        Self::new(PcdDataFormat::Binary)
     }
+}
 
-    // Update metadata with actual point count
-    metadata.width = points.len() as u32;
-    if metadata.height == 0 {
-        metadata.height = 1;
+impl PointCloudReader for PcdFormat {
+    fn name(&self) -> &str {
+        "pcd"
     }
 
-    Ok(PointCloud::from_points_and_metadata(points, metadata))
+    fn extensions(&self) -> &[&str] {
+        &["pcd"]
+    }
+
+    fn matches_magic(&self, bytes: &[u8]) -> bool {
+        bytes.starts_with(b"# .PCD") || bytes.starts_with(b"VERSION")
+    }
+
+    fn read(
+        &self,
+        path: &Path,
+        fields: &[FieldDescriptor],
+        sink: &mut dyn FnMut(&[f64]),
+    ) -> Result<Metadata> {
+        read_pcd_rows(path, fields, sink)
+    }
 }
 
-/// Extract x, y, z coordinates from a DynRecord
-fn extract_xyz_from_record(record: &DynRecord) -> Result<(f32, f32, f32)> {
-    let fields = &record.0;
+impl PointCloudWriter for PcdFormat {
+    fn name(&self) -> &str {
+        "pcd"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["pcd"]
+    }
 
-    if fields.len() < 3 {
-        return Err(CloudError::format_error(
-            "PCD record must have at least 3 fields (x, y, z)",
-        ));
+    fn write(&self, path: &Path, rows: &PointRows<'_>) -> Result<()> {
+        write_pcd_rows(path, rows, self.data)
     }
+}
+
+/// Read the requested fields of every point in a PCD file
+fn read_pcd_rows(
+    path: &Path,
+    fields: &[FieldDescriptor],
+    sink: &mut dyn FnMut(&[f64]),
+) -> Result<Metadata> {
+    let bytes = fs::read(path)?;
+    let header = parse_header(&mut bytes.as_slice())?;
+    let sources = resolve_field_sources(fields, &header)?;
 
-    let x = extract_f32_from_field(&fields[0])?;
-    let y = extract_f32_from_field(&fields[1])?;
-    let z = extract_f32_from_field(&fields[2])?;
+    let records = decode_records(&header, &bytes[header.data_offset..])?;
+    let point_size = header.point_size();
 
-    Ok((x, y, z))
+    let mut values = vec![0.0f64; sources.len()];
+    for record in records.chunks_exact(point_size) {
+        for (value, source) in values.iter_mut().zip(sources.iter()) {
+            *value = source.read(record);
+        }
+        sink(&values);
+    }
+
+    Ok(metadata_from_header(&header))
 }
 
-/// Extract f32 value from a Field
-fn extract_f32_from_field(field: &Field) -> Result<f32> {
-    match field {
-        Field::F32(values) => {
-            if values.is_empty() {
-                Err(CloudError::format_error("Empty F32 field"))
-            } else {
-                Ok(values[0])
+/// Write rows of point fields to a PCD file
+fn write_pcd_rows(path: &Path, rows: &PointRows<'_>, format: PcdDataFormat) -> Result<()> {
+    let columns = output_columns(rows.fields());
+    let header = output_header(&columns, rows.metadata(), rows.len(), format);
+
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_header(&mut writer, &header)?;
+
+    let mut values = vec![0.0f64; rows.fields().len()];
+    match format {
+        PcdDataFormat::Ascii => {
+            let mut line = String::new();
+            for index in 0..rows.len() {
+                rows.write_row(index, &mut values);
+                format_ascii_line(&columns, &values, &mut line);
+                writer.write_all(line.as_bytes())?;
             }
         }
-        Field::F64(values) => {
-            if values.is_empty() {
-                Err(CloudError::format_error("Empty F64 field"))
+        PcdDataFormat::Binary | PcdDataFormat::BinaryCompressed => {
+            let point_size = header.point_size();
+            let offsets = header.field_offsets();
+            let mut records = vec![0u8; point_size * rows.len()];
+            for (index, record) in records.chunks_exact_mut(point_size).enumerate() {
+                rows.write_row(index, &mut values);
+                for (column, &offset) in columns.iter().zip(offsets.iter()) {
+                    column.encode(&values, &mut record[offset..]);
+                }
+            }
+
+            if format == PcdDataFormat::Binary {
+                writer.write_all(&records)?;
             } else {
-                Ok(values[0] as f32)
+                let field_major = records_to_field_major(&header, &records);
+                let compressed = lzf_compress(&field_major);
+                let mut sizes = [0u8; 8];
+                LittleEndian::write_u32(&mut sizes[..4], compressed.len() as u32);
+                LittleEndian::write_u32(&mut sizes[4..], field_major.len() as u32);
+                writer.write_all(&sizes)?;
+                writer.write_all(&compressed)?;
+            }
+        }
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+/// Open a PCD file for reading rows one at a time
+///
+/// Returns the row source, the metadata and the number of points.
+pub(crate) fn open_row_source(
+    path: &Path,
+    fields: &[FieldDescriptor],
+) -> Result<(Box<dyn RowSource>, Metadata, usize)> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let header = parse_header(&mut reader)?;
+    let sources = resolve_field_sources(fields, &header)?;
+    let metadata = metadata_from_header(&header);
+    let points = header.points;
+
+    let data = match header.data {
+        PcdDataFormat::Ascii => StreamData::Ascii(TokenReader::new(reader)),
+        PcdDataFormat::Binary => StreamData::Binary(reader),
+        PcdDataFormat::BinaryCompressed => {
+            let mut data = Vec::new();
+            reader.read_to_end(&mut data)?;
+            StreamData::Decoded {
+                records: decode_records(&header, &data)?.into_owned(),
+                position: 0,
+            }
+        }
+    };
+
+    let source = PcdRowSource {
+        record: vec![0u8; header.point_size()],
+        header,
+        sources,
+        data,
+    };
+    Ok((Box::new(source), metadata, points))
+}
+
+/// Point data of a PCD file being streamed
+enum StreamData {
+    Ascii(TokenReader<BufReader<File>>),
+    Binary(BufReader<File>),
+    Decoded { records: Vec<u8>, position: usize },
+}
+
+/// Row source over the records of a PCD file
+struct PcdRowSource {
+    header: PcdHeader,
+    sources: Vec<FieldSource>,
+    record: Vec<u8>,
+    data: StreamData,
+}
+
+impl RowSource for PcdRowSource {
+    fn read_row(&mut self, values: &mut [f64]) -> Result<()> {
+        let record = match &mut self.data {
+            StreamData::Ascii(tokens) => {
+                let mut offset = 0;
+                for field in &self.header.fields {
+                    for _ in 0..field.count {
+                        let token = tokens.next_token()?.ok_or_else(|| {
+                            CloudError::format_error("PCD ascii data has fewer points than POINTS")
+                        })?;
+                        parse_ascii_value(token, field.datatype, &mut self.record[offset..])?;
+                        offset += field.datatype.size();
+                    }
+                }
+                &self.record[..]
+            }
+            StreamData::Binary(reader) => {
+                reader.read_exact(&mut self.record).map_err(|e| {
+                    if e.kind() == ErrorKind::UnexpectedEof {
+                        CloudError::format_error("PCD binary data is truncated")
+                    } else {
+                        e.into()
+                    }
+                })?;
+                &self.record[..]
+            }
+            StreamData::Decoded { records, position } => {
+                let size = self.record.len();
+                let record = records.get(*position..*position + size).ok_or_else(|| {
+                    CloudError::format_error("PCD data has fewer points than POINTS")
+                })?;
+                *position += size;
+                record
             }
+        };
+
+        for (value, source) in values.iter_mut().zip(self.sources.iter()) {
+            *value = source.read(record);
+        }
+        Ok(())
+    }
+}
+
+/// Create a PCD file for writing rows one chunk at a time
+pub(crate) fn create_row_sink(
+    path: &Path,
+    fields: &'static [FieldDescriptor],
+    format: PcdDataFormat,
+) -> Result<Box<dyn RowSink>> {
+    if format == PcdDataFormat::BinaryCompressed {
+        return Err(CloudError::invalid_parameter(
+            "binary_compressed PCD data cannot be written in chunks",
+        ));
+    }
+
+    Ok(Box::new(PcdRowSink {
+        writer: BufWriter::new(File::create(path)?),
+        columns: output_columns(fields),
+        format,
+        metadata: None,
+        len: 0,
+    }))
+}
+
+/// Row sink appending records to a PCD file
+struct PcdRowSink {
+    writer: BufWriter<File>,
+    columns: Vec<OutputColumn>,
+    format: PcdDataFormat,
+    /// Metadata of the first chunk, set once the header has been written
+    metadata: Option<Metadata>,
+    len: usize,
+}
+
+impl PcdRowSink {
+    fn write_padded_header(&mut self, metadata: &Metadata) -> Result<()> {
+        let mut metadata = metadata.clone();
+        metadata.is_organized = false;
+
+        let header = output_header(&self.columns, &metadata, self.len, self.format);
+        let mut text = Vec::new();
+        write_header(&mut text, &header)?;
+        self.writer
+            .write_all(&pad_count_lines(&text, &["WIDTH ", "POINTS "]))?;
+        self.metadata = Some(metadata);
+        Ok(())
+    }
+}
+
+impl RowSink for PcdRowSink {
+    fn write_rows(&mut self, rows: &PointRows<'_>) -> Result<()> {
+        if self.metadata.is_none() {
+            self.write_padded_header(rows.metadata())?;
         }
-        Field::I32(values) => {
-            if values.is_empty() {
-                Err(CloudError::format_error("Empty I32 field"))
+
+        let mut values = vec![0.0f64; rows.fields().len()];
+        let mut line = String::new();
+        let point_size: usize = self.columns.iter().map(|c| c.datatype.size()).sum();
+        let mut record = vec![0u8; point_size];
+        for index in 0..rows.len() {
+            rows.write_row(index, &mut values);
+            if self.format == PcdDataFormat::Ascii {
+                format_ascii_line(&self.columns, &values, &mut line);
+                self.writer.write_all(line.as_bytes())?;
             } else {
-                Ok(values[0] as f32)
+                let mut offset = 0;
+                for column in &self.columns {
+                    column.encode(&values, &mut record[offset..]);
+                    offset += column.datatype.size();
+                }
+                self.writer.write_all(&record)?;
+            }
+        }
+
+        self.len += rows.len();
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        let metadata = self
+            .metadata
+            .clone()
+            .unwrap_or_else(|| Metadata::new_unorganized(0));
+        if self.metadata.is_none() {
+            self.write_padded_header(&metadata)?;
+        }
+
+        self.writer.seek(SeekFrom::Start(0))?;
+        self.write_padded_header(&metadata)?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Header describing the given output columns and cloud metadata
+fn output_header(
+    columns: &[OutputColumn],
+    metadata: &Metadata,
+    len: usize,
+    format: PcdDataFormat,
+) -> PcdHeader {
+    let (width, height) = if metadata.is_organized && metadata.point_count() == len {
+        (metadata.width as usize, metadata.height as usize)
+    } else {
+        (len, 1)
+    };
+    let [tx, ty, tz] = metadata.sensor_origin;
+    let [qw, qx, qy, qz] = metadata.sensor_orientation;
+
+    PcdHeader {
+        version: "0.7".to_string(),
+        fields: columns
+            .iter()
+            .map(|column| PcdField {
+                name: column.name.to_string(),
+                datatype: column.datatype,
+                count: 1,
+            })
+            .collect(),
+        width,
+        height,
+        viewpoint: [tx, ty, tz, qw, qx, qy, qz].map(|v| v as f64),
+        points: len,
+        data: format,
+        data_offset: 0,
+    }
+}
+
+/// Format one point as a line of ASCII PCD data, replacing `line`
+fn format_ascii_line(columns: &[OutputColumn], values: &[f64], line: &mut String) {
+    line.clear();
+    for (i, column) in columns.iter().enumerate() {
+        if i > 0 {
+            line.push(' ');
+        }
+        line.push_str(&column.format_ascii(values));
+    }
+    line.push('\n');
+}
+
+/// Parse a PCD header, leaving the reader at the start of the point data
+pub(crate) fn parse_header<R: BufRead>(reader: &mut R) -> Result<PcdHeader> {
+    let mut version = String::from("0.7");
+    let mut names: Vec<String> = Vec::new();
+    let mut sizes: Vec<usize> = Vec::new();
+    let mut types: Vec<String> = Vec::new();
+    let mut counts: Option<Vec<usize>> = None;
+    let mut width = None;
+    let mut height = None;
+    let mut viewpoint = [0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0];
+    let mut points = None;
+
+    let mut offset = 0;
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        let read = reader.read_until(b'\n', &mut line)?;
+        if read == 0 {
+            return Err(CloudError::format_error(
+                "PCD header ended without a DATA line",
+            ));
+        }
+        offset += read;
+
+        let text = std::str::from_utf8(&line)
+            .map_err(|_| CloudError::format_error("PCD header is not valid text"))?;
+        let mut tokens = text.split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+        if keyword.starts_with('#') {
+            continue;
+        }
+        let values: Vec<&str> = tokens.collect();
+
+        match keyword.to_ascii_uppercase().as_str() {
+            "VERSION" => version = values.first().copied().unwrap_or("0.7").to_string(),
+            "FIELDS" | "COLUMNS" => names = values.iter().map(|v| v.to_string()).collect(),
+            "SIZE" => sizes = parse_list(keyword, &values)?,
+            "TYPE" => types = values.iter().map(|v| v.to_ascii_uppercase()).collect(),
+            "COUNT" => counts = Some(parse_list(keyword, &values)?),
+            "WIDTH" => width = Some(parse_single(keyword, &values)?),
+            "HEIGHT" => height = Some(parse_single(keyword, &values)?),
+            "POINTS" => points = Some(parse_single(keyword, &values)?),
+            "VIEWPOINT" => {
+                let parsed: Vec<f64> = parse_list(keyword, &values)?;
+                viewpoint = parsed
+                    .try_into()
+                    .map_err(|_| CloudError::format_error("PCD VIEWPOINT must have 7 values"))?;
+            }
+            "DATA" => {
+                let data = values
+                    .first()
+                    .and_then(|v| PcdDataFormat::from_keyword(v))
+                    .ok_or_else(|| {
+                        CloudError::format_error(format!(
+                            "Unsupported PCD data encoding: {}",
+                            values.join(" ")
+                        ))
+                    })?;
+
+                let counts = counts.unwrap_or_else(|| vec![1; names.len()]);
+                if names.is_empty()
+                    || sizes.len() != names.len()
+                    || types.len() != names.len()
+                    || counts.len() != names.len()
+                {
+                    return Err(CloudError::format_error(
+                        "PCD FIELDS, SIZE, TYPE and COUNT lines do not match",
+                    ));
+                }
+
+                let fields = names
+                    .into_iter()
+                    .zip(types.iter().zip(sizes.iter()))
+                    .zip(counts)
+                    .map(|((name, (kind, &size)), count)| {
+                        Ok(PcdField {
+                            datatype: field_type_from_pcd(kind, size)?,
+                            name,
+                            count,
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                let point_size = fields.iter().try_fold(0usize, |total, field| {
+                    field
+                        .datatype
+                        .size()
+                        .checked_mul(field.count)
+                        .filter(|&size| size > 0)
+                        .and_then(|size| total.checked_add(size))
+                });
+                if point_size.is_none() {
+                    return Err(CloudError::format_error(
+                        "PCD COUNT values must be positive and describe a valid point size",
+                    ));
+                }
+
+                let width = width
+                    .or(points)
+                    .ok_or_else(|| CloudError::format_error("PCD header is missing WIDTH"))?;
+                let height = height.unwrap_or(1);
+                let points = match points {
+                    Some(points) => points,
+                    None => width.checked_mul(height).ok_or_else(|| {
+                        CloudError::format_error("PCD WIDTH and HEIGHT are too large")
+                    })?,
+                };
+                return Ok(PcdHeader {
+                    version,
+                    fields,
+                    width,
+                    height,
+                    viewpoint,
+                    points,
+                    data,
+                    data_offset: offset,
+                });
+            }
+            _ => {
+                return Err(CloudError::format_error(format!(
+                    "Unknown PCD header entry: {}",
+                    keyword
+                )));
             }
         }
-        _ => Err(CloudError::format_error(
-            "Unsupported field type for coordinate",
-        )),
     }
 }
 
-/// Save a point cloud to a PCD file
-///
-/// # Arguments
-/// * `cloud` - The point cloud to save
-/// * `path` - Path where to save the PCD file
-///
-/// # Returns
-/// A Result indicating success or failure
-pub fn save_pcd<T: Point, P: AsRef<Path>>(cloud: &PointCloud<T>, path: P) -> Result<()> {
-    // Define the schema for x, y, z coordinates
-    let schema = vec![
-        ("x", ValueKind::F32, 1),
-        ("y", ValueKind::F32, 1),
-        ("z", ValueKind::F32, 1),
-    ];
+fn parse_single(keyword: &str, values: &[&str]) -> Result<usize> {
+    match values {
+        [value] => value.parse().map_err(|_| {
+            CloudError::format_error(format!("Invalid PCD {} value: {}", keyword, value))
+        }),
+        _ => Err(CloudError::format_error(format!(
+            "PCD {} must have a single value",
+            keyword
+        ))),
+    }
+}
 
-    // Create writer with ASCII format
-    let mut writer: DynWriter<_> = WriterInit {
-        width: cloud.len() as u64,
-        height: 1,
-        viewpoint: Default::default(),
-        data_kind: DataKind::Ascii,
-        schema: Some(Schema::from_iter(schema)),
-    }
-    .create(path.as_ref())
-    .map_err(|e| CloudError::format_error(format!("Failed to create PCD writer: {}", e)))?;
-
-    // Write point data
-    for point in cloud.points() {
-        let pos = point.position();
-        let record = DynRecord(vec![
-            Field::F32(vec![pos[0]]),
-            Field::F32(vec![pos[1]]),
-            Field::F32(vec![pos[2]]),
-        ]);
+fn parse_list<T: std::str::FromStr>(keyword: &str, values: &[&str]) -> Result<Vec<T>> {
+    values
+        .iter()
+        .map(|value| {
+            value.parse().map_err(|_| {
+                CloudError::format_error(format!("Invalid PCD {} value: {}", keyword, value))
+            })
+        })
+        .collect()
+}
 
-        writer
-            .push(&record)
-            .map_err(|e| CloudError::format_error(format!("Failed to write PCD record: {}", e)))?;
+/// Convert a PCD `TYPE`/`SIZE` pair into a field type
+fn field_type_from_pcd(kind: &str, size: usize) -> Result<FieldType> {
+    match (kind, size) {
+        ("I", 1) => Ok(FieldType::I8),
+        ("I", 2) => Ok(FieldType::I16),
+        ("I", 4) => Ok(FieldType::I32),
+        ("U", 1) => Ok(FieldType::U8),
+        ("U", 2) => Ok(FieldType::U16),
+        ("U", 4) => Ok(FieldType::U32),
+        ("F", 4) => Ok(FieldType::F32),
+        ("F", 8) => Ok(FieldType::F64),
+        _ => Err(CloudError::format_error(format!(
+            "Unsupported PCD field type {} with size {}",
+            kind, size
+        ))),
     }
+}
+
+/// PCD `TYPE` letter of a field type
+fn pcd_type_letter(datatype: FieldType) -> char {
+    match datatype {
+        FieldType::I8 | FieldType::I16 | FieldType::I32 => 'I',
+        FieldType::U8 | FieldType::U16 | FieldType::U32 => 'U',
+        FieldType::F32 | FieldType::F64 => 'F',
+    }
+}
 
-    // Finalize the writer
-    writer
-        .finish()
-        .map_err(|e| CloudError::format_error(format!("Failed to finalize PCD file: {}", e)))?;
+fn write_header<W: Write>(writer: &mut W, header: &PcdHeader) -> Result<()> {
+    let join = |items: Vec<String>| items.join(" ");
+    let fields = &header.fields;
 
+    writeln!(
+        writer,
+        "# .PCD v{} - Point Cloud Data file format",
+        header.version
+    )?;
+    writeln!(writer, "VERSION {}", header.version)?;
+    writeln!(
+        writer,
+        "FIELDS {}",
+        join(fields.iter().map(|f| f.name.clone()).collect())
+    )?;
+    writeln!(
+        writer,
+        "SIZE {}",
+        join(
+            fields
+                .iter()
+                .map(|f| f.datatype.size().to_string())
+                .collect()
+        )
+    )?;
+    writeln!(
+        writer,
+        "TYPE {}",
+        join(
+            fields
+                .iter()
+                .map(|f| pcd_type_letter(f.datatype).to_string())
+                .collect()
+        )
+    )?;
+    writeln!(
+        writer,
+        "COUNT {}",
+        join(fields.iter().map(|f| f.count.to_string()).collect())
+    )?;
+    writeln!(writer, "WIDTH {}", header.width)?;
+    writeln!(writer, "HEIGHT {}", header.height)?;
+    writeln!(
+        writer,
+        "VIEWPOINT {}",
+        join(header.viewpoint.iter().map(|v| v.to_string()).collect())
+    )?;
+    writeln!(writer, "POINTS {}", header.points)?;
+    writeln!(writer, "DATA {}", header.data.keyword())?;
     Ok(())
 }
 
+/// Decode the point data of any encoding into consecutive binary records
+fn decode_records<'a>(header: &PcdHeader, data: &'a [u8]) -> Result<Cow<'a, [u8]>> {
+    let point_size = header.point_size();
+    let expected = point_size.checked_mul(header.points).ok_or_else(|| {
+        CloudError::format_error(format!("PCD point count {} is too large", header.points))
+    })?;
+
+    match header.data {
+        PcdDataFormat::Ascii => {
+            let text = std::str::from_utf8(data)
+                .map_err(|_| CloudError::format_error("PCD ascii data is not valid text"))?;
+            let mut tokens = text.split_whitespace();
+
+            // POINTS is not trusted for the allocation; records are added as
+            // their values are parsed
+            let mut records = Vec::new();
+            let mut value = [0u8; 8];
+            for _ in 0..header.points {
+                for field in &header.fields {
+                    let size = field.datatype.size();
+                    for _ in 0..field.count {
+                        let token = tokens.next().ok_or_else(|| {
+                            CloudError::format_error("PCD ascii data has fewer points than POINTS")
+                        })?;
+                        parse_ascii_value(token, field.datatype, &mut value[..size])?;
+                        records.extend_from_slice(&value[..size]);
+                    }
+                }
+            }
+
+            Ok(Cow::Owned(records))
+        }
+        PcdDataFormat::Binary => {
+            if data.len() < expected {
+                return Err(CloudError::format_error(format!(
+                    "PCD binary data is truncated: expected {} bytes, found {}",
+                    expected,
+                    data.len()
+                )));
+            }
+            Ok(Cow::Borrowed(&data[..expected]))
+        }
+        PcdDataFormat::BinaryCompressed => {
+            if data.len() < 8 {
+                return Err(CloudError::format_error(
+                    "PCD compressed data is missing its size header",
+                ));
+            }
+            let compressed_size = LittleEndian::read_u32(&data[..4]) as usize;
+            let uncompressed_size = LittleEndian::read_u32(&data[4..8]) as usize;
+            if uncompressed_size != expected {
+                return Err(CloudError::format_error(format!(
+                    "PCD compressed data holds {} bytes, expected {}",
+                    uncompressed_size, expected
+                )));
+            }
+            if expected == 0 {
+                return Ok(Cow::Owned(Vec::new()));
+            }
+            if uncompressed_size / LZF_MAX_EXPANSION > compressed_size {
+                return Err(CloudError::format_error(format!(
+                    "PCD compressed data of {} bytes cannot hold {} bytes",
+                    compressed_size, uncompressed_size
+                )));
+            }
+
+            let compressed = data
+                .get(8..8 + compressed_size)
+                .ok_or_else(|| CloudError::format_error("PCD compressed data is truncated"))?;
+            let field_major = lzf::decompress(compressed, uncompressed_size).map_err(|e| {
+                CloudError::format_error(format!("Failed to decompress PCD data: {}", e))
+            })?;
+            if field_major.len() != expected {
+                return Err(CloudError::format_error(
+                    "PCD compressed data has an unexpected size",
+                ));
+            }
+
+            Ok(Cow::Owned(field_major_to_records(header, &field_major)))
+        }
+    }
+}
+
+/// Reorder point records into the field-major layout of `binary_compressed`
+fn records_to_field_major(header: &PcdHeader, records: &[u8]) -> Vec<u8> {
+    let point_size = header.point_size();
+    let mut field_major = Vec::with_capacity(records.len());
+    for (field, offset) in header.fields.iter().zip(header.field_offsets()) {
+        for record in records.chunks_exact(point_size) {
+            field_major.extend_from_slice(&record[offset..offset + field.size()]);
+        }
+    }
+    field_major
+}
+
+/// Reorder field-major `binary_compressed` data into point records
+fn field_major_to_records(header: &PcdHeader, field_major: &[u8]) -> Vec<u8> {
+    let point_size = header.point_size();
+    let mut records = vec![0u8; field_major.len()];
+    let mut start = 0;
+    for (field, offset) in header.fields.iter().zip(header.field_offsets()) {
+        let size = field.size();
+        let block = &field_major[start..start + size * header.points];
+        for (record, value) in records
+            .chunks_exact_mut(point_size)
+            .zip(block.chunks_exact(size))
+        {
+            record[offset..offset + size].copy_from_slice(value);
+        }
+        start += block.len();
+    }
+    records
+}
+
+/// Largest ratio of decompressed to compressed size that LZF can reach: a
+/// 3-byte back reference expands to at most 264 bytes
+pub(crate) const LZF_MAX_EXPANSION: usize = 88;
+
+/// Compress data with LZF, falling back to literal runs for incompressible input
+pub(crate) fn lzf_compress(data: &[u8]) -> Vec<u8> {
+    if let Ok(compressed) = lzf::compress(data) {
+        return compressed;
+    }
+
+    // LZF literal runs are a control byte (length - 1) followed by up to 32 bytes
+    let mut literal = Vec::with_capacity(data.len() + data.len() / 32 + 1);
+    for chunk in data.chunks(32) {
+        literal.push((chunk.len() - 1) as u8);
+        literal.extend_from_slice(chunk);
+    }
+    literal
+}
+
+/// Parse an ASCII value and store it little-endian in `out`
+fn parse_ascii_value(token: &str, datatype: FieldType, out: &mut [u8]) -> Result<()> {
+    let invalid = || CloudError::format_error(format!("Invalid PCD ascii value: {}", token));
+
+    match datatype {
+        FieldType::F32 => {
+            LittleEndian::write_f32(out, token.parse::<f32>().map_err(|_| invalid())?)
+        }
+        FieldType::F64 => {
+            LittleEndian::write_f64(out, token.parse::<f64>().map_err(|_| invalid())?)
+        }
+        _ => {
+            let value = match token.parse::<i64>() {
+                Ok(value) => value as f64,
+                Err(_) => token.parse::<f64>().map_err(|_| invalid())?,
+            };
+            write_value(datatype, value, out);
+        }
+    }
+    Ok(())
+}
+
+/// Read a little-endian value of the given type as f64
+pub(crate) fn read_value(datatype: FieldType, bytes: &[u8]) -> f64 {
+    match datatype {
+        FieldType::I8 => bytes[0] as i8 as f64,
+        FieldType::I16 => LittleEndian::read_i16(bytes) as f64,
+        FieldType::I32 => LittleEndian::read_i32(bytes) as f64,
+        FieldType::U8 => bytes[0] as f64,
+        FieldType::U16 => LittleEndian::read_u16(bytes) as f64,
+        FieldType::U32 => LittleEndian::read_u32(bytes) as f64,
+        FieldType::F32 => LittleEndian::read_f32(bytes) as f64,
+        FieldType::F64 => LittleEndian::read_f64(bytes),
+    }
+}
+
+/// Write an f64 value little-endian as the given type
+pub(crate) fn write_value(datatype: FieldType, value: f64, out: &mut [u8]) {
+    match datatype {
+        FieldType::I8 => out[0] = value as i8 as u8,
+        FieldType::I16 => LittleEndian::write_i16(out, value as i16),
+        FieldType::I32 => LittleEndian::write_i32(out, value as i32),
+        FieldType::U8 => out[0] = value as u8,
+        FieldType::U16 => LittleEndian::write_u16(out, value as u16),
+        FieldType::U32 => LittleEndian::write_u32(out, value as u32),
+        FieldType::F32 => LittleEndian::write_f32(out, value as f32),
+        FieldType::F64 => LittleEndian::write_f64(out, value),
+    }
+}
+
+/// Location of a point field inside a PCD record
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum FieldSource {
+    /// First element of a field at the given byte offset
+    Scalar { offset: usize, datatype: FieldType },
+
+    /// One byte of a packed `rgb`/`rgba` field, selected by bit shift
+    PackedColor {
+        offset: usize,
+        datatype: FieldType,
+        shift: u32,
+    },
+}
+
+impl FieldSource {
+    pub(crate) fn read(&self, record: &[u8]) -> f64 {
+        match *self {
+            FieldSource::Scalar { offset, datatype } => read_value(datatype, &record[offset..]),
+            FieldSource::PackedColor {
+                offset,
+                datatype,
+                shift,
+            } => {
+                let packed = read_packed_color(datatype, &record[offset..]);
+                ((packed >> shift) & 0xFF) as f64
+            }
+        }
+    }
+}
+
+/// Map every requested field to its location in the PCD records
+pub(crate) fn resolve_field_sources(
+    fields: &[FieldDescriptor],
+    header: &PcdHeader,
+) -> Result<Vec<FieldSource>> {
+    let offsets = header.field_offsets();
+    let find = |name: &str| {
+        header
+            .fields
+            .iter()
+            .position(|field| field.name == name && field.count > 0)
+            .map(|index| (offsets[index], header.fields[index].datatype))
+    };
+    let packed_color = find("rgb").or_else(|| find("rgba"));
+
+    let mut sources = Vec::with_capacity(fields.len());
+    let mut missing = Vec::new();
+    for descriptor in fields {
+        let shift = match descriptor.name {
+            "r" => Some(16),
+            "g" => Some(8),
+            "b" => Some(0),
+            _ => None,
+        };
+
+        match (find(descriptor.name), shift, packed_color) {
+            (Some((offset, datatype)), _, _) => {
+                sources.push(FieldSource::Scalar { offset, datatype })
+            }
+            (None, Some(shift), Some((offset, datatype))) => {
+                sources.push(FieldSource::PackedColor {
+                    offset,
+                    datatype,
+                    shift,
+                })
+            }
+            _ => missing.push(descriptor.name),
+        }
+    }
+
+    if !missing.is_empty() {
+        let available: Vec<&str> = header.fields.iter().map(|f| f.name.as_str()).collect();
+        return Err(CloudError::format_error(format!(
+            "PCD file is missing required fields: {} (available fields: {})",
+            missing.join(", "),
+            available.join(", ")
+        )));
+    }
+
+    Ok(sources)
+}
+
+/// Build cloud metadata from a PCD header
+pub(crate) fn metadata_from_header(header: &PcdHeader) -> Metadata {
+    let grid = match (u32::try_from(header.width), u32::try_from(header.height)) {
+        (Ok(width), Ok(height))
+            if header.height > 1
+                && header.width.checked_mul(header.height) == Some(header.points) =>
+        {
+            Some((width, height))
+        }
+        _ => None,
+    };
+    let mut metadata = match grid {
+        Some((width, height)) => Metadata::new_organized(width, height),
+        None => Metadata::new_unorganized(header.points),
+    };
+
+    let [tx, ty, tz, qw, qx, qy, qz] = header.viewpoint.map(|v| v as f32);
+    metadata.sensor_origin = [tx, ty, tz];
+    metadata.sensor_orientation = [qw, qx, qy, qz];
+
+    metadata
+        .custom_fields
+        .insert("version".to_string(), header.version.clone());
+
+    metadata
+}
+
+/// Read a packed `0x00RRGGBB` color from an `rgb` or `rgba` field
+///
+/// PCL stores the packed integer bit-cast into a float. Some tools instead
+/// write the integer value itself as a float; integral values in the 24-bit
+/// range are interpreted that way, since bit-cast colors are denormals.
+fn read_packed_color(datatype: FieldType, bytes: &[u8]) -> u32 {
+    match datatype {
+        FieldType::F32 => {
+            let value = LittleEndian::read_f32(bytes);
+            if value.fract() == 0.0 && (1.0..=16_777_215.0).contains(&value) {
+                value as u32
+            } else {
+                value.to_bits()
+            }
+        }
+        FieldType::I32 | FieldType::U32 => LittleEndian::read_u32(bytes),
+        other => read_value(other, bytes) as u32,
+    }
+}
+
+/// Column written to a PCD file
+pub(crate) struct OutputColumn {
+    pub(crate) name: &'static str,
+    pub(crate) datatype: FieldType,
+    source: ColumnSource,
+}
+
+enum ColumnSource {
+    /// Value of the point field at the given index
+    Value(usize),
+
+    /// Indices of the `r`, `g` and `b` fields packed into one `rgb` float
+    PackedRgb([usize; 3]),
+}
+
+impl OutputColumn {
+    fn packed_rgb(values: &[f64], indices: [usize; 3]) -> u32 {
+        let [r, g, b] = indices.map(|i| values[i] as u32 & 0xFF);
+        (r << 16) | (g << 8) | b
+    }
+
+    pub(crate) fn encode(&self, values: &[f64], out: &mut [u8]) {
+        match self.source {
+            ColumnSource::Value(index) => write_value(self.datatype, values[index], out),
+            ColumnSource::PackedRgb(indices) => {
+                LittleEndian::write_u32(out, Self::packed_rgb(values, indices))
+            }
+        }
+    }
+
+    fn format_ascii(&self, values: &[f64]) -> String {
+        match self.source {
+            ColumnSource::Value(index) => match self.datatype {
+                FieldType::F32 | FieldType::F64 => format_ascii_float(values[index], self.datatype),
+                _ => (values[index] as i64).to_string(),
+            },
+            ColumnSource::PackedRgb(indices) => {
+                let packed = f32::from_bits(Self::packed_rgb(values, indices));
+                format_ascii_float(packed as f64, FieldType::F32)
+            }
+        }
+    }
+}
+
+/// Columns for the fields of a point type, packing colors into `rgb`
+pub(crate) fn output_columns(fields: &[FieldDescriptor]) -> Vec<OutputColumn> {
+    let index_of = |name: &str| fields.iter().position(|f| f.name == name);
+    let rgb = match (index_of("r"), index_of("g"), index_of("b")) {
+        (Some(r), Some(g), Some(b)) => Some([r, g, b]),
+        _ => None,
+    };
+
+    let mut columns = Vec::with_capacity(fields.len());
+    for (index, field) in fields.iter().enumerate() {
+        match rgb {
+            Some(indices) if indices.contains(&index) => {
+                if index == indices[0] {
+                    columns.push(OutputColumn {
+                        name: "rgb",
+                        datatype: FieldType::F32,
+                        source: ColumnSource::PackedRgb(indices),
+                    });
+                }
+            }
+            _ => columns.push(OutputColumn {
+                name: field.name,
+                datatype: field.datatype,
+                source: ColumnSource::Value(index),
+            }),
+        }
+    }
+    columns
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::{Point, PointXYZI, PointXYZL, PointXYZRGB, PointXYZRGBNormal};
     use tempfile::NamedTempFile;
 
     #[test]
@@ -185,4 +1193,254 @@ mod tests {
             assert!((orig_pos[2] - load_pos[2]).abs() < 1e-6);
         }
     }
+
+    fn write_temp_pcd(contents: &str) -> NamedTempFile {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(contents.as_bytes()).unwrap();
+        file
+    }
+
+    #[test]
+    fn test_load_fields_by_name() {
+        let packed = f32::from_bits(0x00FF8040);
+        let file = write_temp_pcd(&format!(
+            "VERSION .7\n\
+             FIELDS normal_x normal_y normal_z rgb z y x\n\
+             SIZE 4 4 4 4 4 4 4\n\
+             TYPE F F F F F F F\n\
+             COUNT 1 1 1 1 1 1 1\n\
+             WIDTH 2\n\
+             HEIGHT 2\n\
+             VIEWPOINT 1 2 3 1 0 0 0\n\
+             POINTS 4\n\
+             DATA ascii\n\
+             0 0 1 {packed:e} 3 2 1\n\
+             0 1 0 {packed:e} 6 5 4\n\
+             1 0 0 {packed:e} 9 8 7\n\
+             0 0 1 {packed:e} 12 11 10\n"
+        ));
+
+        let cloud: PointCloud<PointXYZRGBNormal> = load_pcd_as(file.path()).unwrap();
+        assert_eq!(cloud.len(), 4);
+        assert!(cloud.metadata().is_organized);
+        assert_eq!(cloud.metadata().width, 2);
+        assert_eq!(cloud.metadata().height, 2);
+        assert_eq!(cloud.metadata().sensor_origin, [1.0, 2.0, 3.0]);
+
+        let point = cloud.get(1).unwrap();
+        assert_eq!(point.position(), [4.0, 5.0, 6.0]);
+        assert_eq!(point.color(), Some([0xFF, 0x80, 0x40]));
+        assert_eq!(point.normal(), [0.0, 1.0, 0.0]);
+
+        // Only the coordinates are used when loading plain points
+        let xyz = load_pcd(file.path()).unwrap();
+        assert_eq!(xyz.get(3).unwrap().position(), [10.0, 11.0, 12.0]);
+    }
+
+    #[test]
+    fn test_load_separate_fields() {
+        let file = write_temp_pcd(
+            "VERSION .7\n\
+             FIELDS x y z intensity label\n\
+             SIZE 4 4 4 4 4\n\
+             TYPE F F F F U\n\
+             COUNT 1 1 1 1 1\n\
+             WIDTH 2\n\
+             HEIGHT 1\n\
+             VIEWPOINT 0 0 0 1 0 0 0\n\
+             POINTS 2\n\
+             DATA ascii\n\
+             1 2 3 0.5 7\n\
+             4 5 6 0.25 9\n",
+        );
+
+        let intensities: PointCloud<PointXYZI> = load_pcd_as(file.path()).unwrap();
+        assert_eq!(intensities.get(1).unwrap().intensity, 0.25);
+        assert!(!intensities.metadata().is_organized);
+
+        let labels: PointCloud<PointXYZL> = load_pcd_as(file.path()).unwrap();
+        assert_eq!(labels.get(0).unwrap().label, 7);
+        assert_eq!(labels.get(1).unwrap().label, 9);
+    }
+
+    #[test]
+    fn test_missing_fields() {
+        let file = write_temp_pcd(
+            "VERSION .7\n\
+             FIELDS x y z\n\
+             SIZE 4 4 4\n\
+             TYPE F F F\n\
+             COUNT 1 1 1\n\
+             WIDTH 1\n\
+             HEIGHT 1\n\
+             VIEWPOINT 0 0 0 1 0 0 0\n\
+             POINTS 1\n\
+             DATA ascii\n\
+             1 2 3\n",
+        );
+
+        let error = load_pcd_as::<PointXYZRGBNormal, _>(file.path()).unwrap_err();
+        let message = error.to_string();
+        assert!(message.contains("r, g, b, normal_x, normal_y, normal_z"));
+
+        assert!(load_pcd_as::<PointXYZI, _>(file.path()).is_err());
+    }
+
+    #[test]
+    fn test_load_example_colors() {
+        let cloud: PointCloud<PointXYZRGB> = load_pcd_as("examples/scene.pcd").unwrap();
+        assert_eq!(cloud.len(), 213);
+        // 4.2108e+06 is stored as the integer value of the packed color
+        assert_eq!(cloud.get(0).unwrap().rgb(), 4_210_800);
+    }
+
+    const FORMATS: [PcdDataFormat; 3] = [
+        PcdDataFormat::Ascii,
+        PcdDataFormat::Binary,
+        PcdDataFormat::BinaryCompressed,
+    ];
+
+    #[test]
+    fn test_format_roundtrip() {
+        let mut points = Vec::new();
+        for row in 0..3 {
+            for col in 0..4 {
+                let value = (row * 4 + col) as f32;
+                points.push(PointXYZRGBNormal::new(
+                    value * 0.1,
+                    -value,
+                    1.0e-6 * value,
+                    (row * 80) as u8,
+                    (col * 60) as u8,
+                    255,
+                    0.0,
+                    0.6,
+                    0.8,
+                ));
+            }
+        }
+        points[5] = PointXYZRGBNormal::new(
+            f32::NAN,
+            f32::NAN,
+            f32::NAN,
+            0,
+            0,
+            0,
+            f32::NAN,
+            f32::NAN,
+            f32::NAN,
+        );
+        let metadata = Metadata::new_organized(4, 3)
+            .with_sensor_origin([1.0, 2.0, 3.0])
+            .with_sensor_orientation([0.0, 1.0, 0.0, 0.0]);
+        let cloud = PointCloud::from_points_and_metadata(points, metadata);
+
+        for format in FORMATS {
+            let file = NamedTempFile::new().unwrap();
+            save_pcd_with_format(&cloud, file.path(), format).unwrap();
+
+            let header = read_pcd_header(file.path()).unwrap();
+            assert_eq!(header.data, format);
+            assert_eq!((header.width, header.height), (4, 3));
+            let names: Vec<&str> = header.fields.iter().map(|f| f.name.as_str()).collect();
+            assert_eq!(
+                names,
+                ["x", "y", "z", "rgb", "normal_x", "normal_y", "normal_z"]
+            );
+
+            let loaded: PointCloud<PointXYZRGBNormal> = load_pcd_as(file.path()).unwrap();
+            assert_eq!(
+                format!("{:?}", loaded.points()),
+                format!("{:?}", cloud.points())
+            );
+            assert!(loaded.metadata().is_organized);
+            assert_eq!(loaded.metadata().width, 4);
+            assert_eq!(loaded.metadata().sensor_origin, [1.0, 2.0, 3.0]);
+            assert_eq!(loaded.metadata().sensor_orientation, [0.0, 1.0, 0.0, 0.0]);
+        }
+    }
+
+    #[test]
+    fn test_scalar_field_roundtrip() {
+        let labels = PointCloud::from_points(vec![
+            PointXYZL::new(1.0, 2.0, 3.0, 4_000_000_000),
+            PointXYZL::new(4.0, 5.0, 6.0, 0),
+        ]);
+        let intensities = PointCloud::from_points(vec![PointXYZI::new(1.0, 2.0, 3.0, 0.75)]);
+        let empty: PointCloud<PointXYZ> = PointCloud::from_points(Vec::new());
+
+        for format in FORMATS {
+            let file = NamedTempFile::new().unwrap();
+            save_pcd_with_format(&labels, file.path(), format).unwrap();
+            let loaded: PointCloud<PointXYZL> = load_pcd_as(file.path()).unwrap();
+            assert_eq!(loaded.points(), labels.points());
+            assert!(!loaded.metadata().is_organized);
+
+            save_pcd_with_format(&intensities, file.path(), format).unwrap();
+            let loaded: PointCloud<PointXYZI> = load_pcd_as(file.path()).unwrap();
+            assert_eq!(loaded.points(), intensities.points());
+
+            save_pcd_with_format(&empty, file.path(), format).unwrap();
+            assert!(load_pcd(file.path()).unwrap().is_empty());
+        }
+    }
+
+    #[test]
+    fn test_compressed_is_smaller() {
+        let points: Vec<PointXYZ> = (0..1000)
+            .map(|i| PointXYZ::new((i % 10) as f32, (i / 10) as f32, 0.0))
+            .collect();
+        let cloud = PointCloud::from_points(points);
+
+        let binary = NamedTempFile::new().unwrap();
+        let compressed = NamedTempFile::new().unwrap();
+        save_pcd_with_format(&cloud, binary.path(), PcdDataFormat::Binary).unwrap();
+        save_pcd_with_format(&cloud, compressed.path(), PcdDataFormat::BinaryCompressed).unwrap();
+
+        let binary_size = fs::metadata(binary.path()).unwrap().len();
+        let compressed_size = fs::metadata(compressed.path()).unwrap().len();
+        assert!(compressed_size < binary_size);
+        assert_eq!(
+            load_pcd(compressed.path()).unwrap().points(),
+            cloud.points()
+        );
+    }
+
+    #[test]
+    fn test_rejects_oversized_headers() {
+        let header_with_grid = |grid: &str, count: &str, points: &str, data: &str| {
+            format!(
+                "VERSION 0.7\nFIELDS x y z\nSIZE 4 4 4\nTYPE F F F\nCOUNT {}\n{}\nPOINTS {}\nDATA {}\n",
+                count, grid, points, data
+            )
+        };
+        let header = |count: &str, points: &str, data: &str| {
+            header_with_grid("WIDTH 1\nHEIGHT 1", count, points, data)
+        };
+        let huge = usize::MAX.to_string();
+        let mut compressed = header("1 1 1", "100000000", "binary_compressed").into_bytes();
+        compressed.extend_from_slice(&[4, 0, 0, 0, 0x00, 0x8C, 0x86, 0x47, 0, 0, 0, 0]);
+
+        let files = [
+            header("1 1 1", &huge, "binary").into_bytes(),
+            header("1 1 1", &huge, "ascii").into_bytes(),
+            header("1 1 1", "1000000000000", "ascii").into_bytes(),
+            header("1 0 1", "1", "ascii").into_bytes(),
+            header(&format!("1 1 {}", huge), "1", "binary").into_bytes(),
+            compressed,
+        ];
+        let file = NamedTempFile::new().unwrap();
+        for bytes in files {
+            fs::write(file.path(), bytes).unwrap();
+            assert!(load_pcd(file.path()).is_err());
+        }
+
+        // A grid whose area overflows usize must not panic
+        let overflowing =
+            header_with_grid("WIDTH 4294967296\nHEIGHT 4294967296", "1 1 1", "0", "ascii");
+        fs::write(file.path(), overflowing).unwrap();
+        let cloud = load_pcd(file.path()).unwrap();
+        assert!(cloud.is_empty());
+        assert!(!cloud.metadata().is_organized);
+    }
 }
@@ -3,19 +3,170 @@
 //! This module provides functionality for reading and writing PLY files,
 //! a popular format for storing 3D polygon data.
 //!
-//! Uses the `ply-rs` crate for efficient and robust PLY file handling.
+//! ASCII as well as little- and big-endian binary files are supported. Point
+//! attributes are mapped to vertex properties by name through the
+//! [`PointFields`] trait, using the usual PLY names (`red`, `green`, `blue`,
+//! `nx`, `ny`, `nz`). Vertex properties the point type does not cover and the
+//! `face` element are kept in [`PlyData`] so that they survive a load/save
+//! cycle.
 
-use crate::core::{Point, PointCloud, PointXYZ};
+use super::stream::{RowSink, RowSource, TokenReader, pad_count_lines};
+use super::{PointCloudReader, PointCloudWriter, PointRows, format_ascii_float};
+use crate::core::{FieldDescriptor, FieldType, Metadata, Point, PointCloud, PointFields, PointXYZ};
 use crate::error::{CloudError, Result};
-use ply_rs::parser::Parser;
-use ply_rs::ply::{
-    DefaultElement, ElementDef, Encoding, Ply, PropertyDef, PropertyType, ScalarType,
-};
-use ply_rs::writer::Writer;
-use std::fs::File;
-use std::io::{BufReader, BufWriter};
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, ErrorKind, Seek, SeekFrom, Write};
 use std::path::Path;
 
+/// Payload encoding of a PLY file
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PlyEncoding {
+    /// Whitespace separated text, one element per line
+    #[default]
+    Ascii,
+
+    /// Little-endian binary
+    BinaryLittleEndian,
+
+    /// Big-endian binary
+    BinaryBigEndian,
+}
+
+impl PlyEncoding {
+    /// Keyword used on the `format` header line
+    pub fn keyword(&self) -> &'static str {
+        match self {
+            PlyEncoding::Ascii => "ascii",
+            PlyEncoding::BinaryLittleEndian => "binary_little_endian",
+            PlyEncoding::BinaryBigEndian => "binary_big_endian",
+        }
+    }
+
+    fn from_keyword(keyword: &str) -> Option<Self> {
+        match keyword {
+            "ascii" => Some(PlyEncoding::Ascii),
+            "binary_little_endian" => Some(PlyEncoding::BinaryLittleEndian),
+            "binary_big_endian" => Some(PlyEncoding::BinaryBigEndian),
+            _ => None,
+        }
+    }
+}
+
+/// Type of a property declared in a PLY header
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlyPropertyKind {
+    /// A single value
+    Scalar(FieldType),
+
+    /// A length-prefixed list of values
+    List { count: FieldType, item: FieldType },
+}
+
+/// Property declared in a PLY header
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PlyPropertyDef {
+    pub name: String,
+    pub kind: PlyPropertyKind,
+}
+
+/// Element declared in a PLY header
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PlyElement {
+    pub name: String,
+    pub count: usize,
+    pub properties: Vec<PlyPropertyDef>,
+}
+
+impl PlyElement {
+    /// Size of one element in bytes, or `None` if it has list properties
+    pub fn record_size(&self) -> Option<usize> {
+        self.properties
+            .iter()
+            .map(|property| match property.kind {
+                PlyPropertyKind::Scalar(datatype) => Some(datatype.size()),
+                PlyPropertyKind::List { .. } => None,
+            })
+            .sum()
+    }
+
+    /// Index of the property with the given name
+    pub fn property_index(&self, name: &str) -> Option<usize> {
+        self.properties.iter().position(|p| p.name == name)
+    }
+}
+
+/// Parsed header of a PLY file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PlyHeader {
+    /// Payload encoding
+    pub encoding: PlyEncoding,
+
+    /// `comment` lines, without the keyword
+    pub comments: Vec<String>,
+
+    /// Elements in payload order
+    pub elements: Vec<PlyElement>,
+
+    /// Byte offset of the payload from the start of the file
+    pub data_offset: usize,
+}
+
+impl PlyHeader {
+    /// Find an element by name
+    pub fn element(&self, name: &str) -> Option<&PlyElement> {
+        self.elements.iter().find(|e| e.name == name)
+    }
+}
+
+/// Scalar vertex property that is not part of the point type
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlyProperty {
+    /// Property name
+    pub name: String,
+
+    /// Scalar type used in the file
+    pub datatype: FieldType,
+
+    /// One value per vertex
+    pub values: Vec<f64>,
+}
+
+/// Contents of a PLY file: the vertices plus everything needed to write it back
+#[derive(Clone, Debug)]
+pub struct PlyData<T: Point> {
+    /// Vertices as typed points
+    pub cloud: PointCloud<T>,
+
+    /// Scalar vertex properties not covered by the point type, in file order
+    pub properties: Vec<PlyProperty>,
+
+    /// Faces of the `face` element as lists of vertex indices
+    pub faces: Vec<Vec<u32>>,
+}
+
+impl<T: Point> PlyData<T> {
+    /// Wrap a point cloud without extra properties or faces
+    pub fn new(cloud: PointCloud<T>) -> Self {
+        Self {
+            cloud,
+            properties: Vec::new(),
+            faces: Vec::new(),
+        }
+    }
+
+    /// Find an extra vertex property by name
+    pub fn property(&self, name: &str) -> Option<&PlyProperty> {
+        self.properties.iter().find(|p| p.name == name)
+    }
+}
+
+/// Read only the header of a PLY file
+pub fn read_ply_header<P: AsRef<Path>>(path: P) -> Result<PlyHeader> {
+    let file = File::open(path.as_ref())?;
+    parse_header(&mut BufReader::new(file))
+}
+
 /// Load a point cloud from a PLY file
 ///
 /// # Arguments
@@ -24,59 +175,277 @@ use std::path::Path;
 /// # Returns
 /// A Result containing the loaded PointCloud or an error
 pub fn load_ply<P: AsRef<Path>>(path: P) -> Result<PointCloud<PointXYZ>> {
-    let file = File::open(path.as_ref())?;
+    load_ply_as(path)
+}
 
-    let mut reader = BufReader::new(file);
+/// Load the vertices of a PLY file into any point type with named fields
+///
+/// See [`load_ply_data`] to also keep unused vertex properties and faces.
+pub fn load_ply_as<T: PointFields, P: AsRef<Path>>(path: P) -> Result<PointCloud<T>> {
+    Ok(load_ply_data(path)?.cloud)
+}
 
-    // Create parser for DefaultElement
-    let parser = Parser::<DefaultElement>::new();
+/// Load a PLY file with its extra vertex properties and faces
+///
+/// Point fields are matched against the vertex properties by name. Colors
+/// are read from `red`/`green`/`blue` and normals from `nx`/`ny`/`nz`; the
+/// field names themselves (`r`, `normal_x`, ...) are accepted as well.
+///
+/// # Errors
+/// Returns a format error naming the missing properties if the vertex
+/// element does not provide every field of `T`.
+pub fn load_ply_data<T: PointFields, P: AsRef<Path>>(path: P) -> Result<PlyData<T>> {
+    let mut points = Vec::new();
+    let (properties, faces) = read_ply_rows(path.as_ref(), T::fields(), &mut |values| {
+        points.push(T::from_values(values))
+    })?;
 
-    // Parse the PLY file
-    let ply = parser
-        .read_ply(&mut reader)
-        .map_err(|e| CloudError::format_error(format!("Failed to parse PLY file: {}", e)))?;
+    Ok(PlyData {
+        cloud: PointCloud::from_points(points),
+        properties,
+        faces,
+    })
+}
+
+/// Read the requested fields of every vertex, plus extra properties and faces
+fn read_ply_rows(
+    path: &Path,
+    fields: &[FieldDescriptor],
+    sink: &mut dyn FnMut(&[f64]),
+) -> Result<(Vec<PlyProperty>, Vec<Vec<u32>>)> {
+    let bytes = fs::read(path)?;
+    let header = parse_header(&mut bytes.as_slice())?;
 
-    // Extract vertex data
-    let vertices = ply
-        .payload
-        .get("vertex")
+    let vertex = header
+        .element("vertex")
         .ok_or_else(|| CloudError::format_error("PLY file does not contain vertex data"))?;
 
-    let mut points = Vec::new();
-    points.reserve(vertices.len());
+    let sources = resolve_vertex_sources(vertex, fields)?;
+
+    // Remaining scalar properties are kept as extra properties
+    let mut properties: Vec<(usize, PlyProperty)> = vertex
+        .properties
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| !sources.contains(index))
+        .filter_map(|(index, def)| match def.kind {
+            PlyPropertyKind::Scalar(datatype) => Some((
+                index,
+                PlyProperty {
+                    name: def.name.clone(),
+                    datatype,
+                    values: Vec::new(),
+                },
+            )),
+            PlyPropertyKind::List { .. } => None,
+        })
+        .collect();
+
+    let mut reader = PayloadReader::new(header.encoding, &bytes[header.data_offset..]);
+    let mut faces = Vec::new();
+    let mut face = Vec::new();
+    let mut row = Vec::new();
+    let mut values = vec![0.0f64; fields.len()];
+
+    for element in &header.elements {
+        let face_indices = (element.name == "face")
+            .then(|| {
+                element
+                    .property_index("vertex_indices")
+                    .or_else(|| element.property_index("vertex_index"))
+            })
+            .flatten();
 
-    for vertex in vertices {
-        let x = extract_coordinate(vertex, "x")?;
-        let y = extract_coordinate(vertex, "y")?;
-        let z = extract_coordinate(vertex, "z")?;
+        for _ in 0..element.count {
+            reader.read_element(element, face_indices, &mut row, &mut face)?;
+            if face_indices.is_some() {
+                faces.push(face.clone());
+            }
 
-        points.push(PointXYZ::new(x, y, z));
+            if element.name == "vertex" {
+                for (value, &source) in values.iter_mut().zip(sources.iter()) {
+                    *value = row[source];
+                }
+                sink(&values);
+                for (index, property) in properties.iter_mut() {
+                    property.values.push(row[*index]);
+                }
+            }
+        }
     }
 
-    Ok(PointCloud::from_points(points))
+    let properties = properties.into_iter().map(|(_, p)| p).collect();
+    Ok((properties, faces))
 }
 
-/// Extract coordinate value from a DefaultElement
-fn extract_coordinate(element: &DefaultElement, coord_name: &str) -> Result<f32> {
-    element
-        .get(coord_name)
-        .and_then(|prop| match prop {
-            ply_rs::ply::Property::Float(f) => Some(*f),
-            ply_rs::ply::Property::Double(d) => Some(*d as f32),
-            ply_rs::ply::Property::Int(i) => Some(*i as f32),
-            ply_rs::ply::Property::UInt(u) => Some(*u as f32),
-            ply_rs::ply::Property::Short(s) => Some(*s as f32),
-            ply_rs::ply::Property::UShort(us) => Some(*us as f32),
-            ply_rs::ply::Property::Char(c) => Some(*c as f32),
-            ply_rs::ply::Property::UChar(uc) => Some(*uc as f32),
-            _ => None,
-        })
-        .ok_or_else(|| {
-            CloudError::format_error(format!("Missing or invalid {} coordinate", coord_name))
-        })
+/// Map every point field to the index of a scalar vertex property
+pub(crate) fn resolve_vertex_sources(
+    vertex: &PlyElement,
+    fields: &[FieldDescriptor],
+) -> Result<Vec<usize>> {
+    let mut sources = Vec::with_capacity(fields.len());
+    let mut missing = Vec::new();
+    for field in fields {
+        let aliases = property_aliases(field.name);
+        let found = aliases.iter().find_map(|name| {
+            vertex
+                .property_index(name)
+                .filter(|&i| matches!(vertex.properties[i].kind, PlyPropertyKind::Scalar(_)))
+        });
+        match found {
+            Some(index) => sources.push(index),
+            None => missing.push(aliases[0]),
+        }
+    }
+
+    if !missing.is_empty() {
+        return Err(CloudError::format_error(format!(
+            "PLY vertex element is missing required properties: {}",
+            missing.join(", ")
+        )));
+    }
+    Ok(sources)
+}
+
+/// Open the vertices of a PLY file for reading rows one at a time
+///
+/// Returns the row source, the metadata and the number of vertices.
+pub(crate) fn open_row_source(
+    path: &Path,
+    fields: &[FieldDescriptor],
+) -> Result<(Box<dyn RowSource>, Metadata, usize)> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let header = parse_header(&mut reader)?;
+    let vertex_index = header
+        .elements
+        .iter()
+        .position(|e| e.name == "vertex")
+        .ok_or_else(|| CloudError::format_error("PLY file does not contain vertex data"))?;
+    let vertex = header.elements[vertex_index].clone();
+    let sources = resolve_vertex_sources(&vertex, fields)?;
+
+    // Skip the elements stored before the vertices
+    let mut payload = PayloadReader::new(header.encoding, reader);
+    let mut row = Vec::new();
+    for element in &header.elements[..vertex_index] {
+        for _ in 0..element.count {
+            payload.read_element(element, None, &mut row, &mut Vec::new())?;
+        }
+    }
+
+    let count = vertex.count;
+    let source = PlyRowSource {
+        payload,
+        vertex,
+        sources,
+        row,
+    };
+    Ok((Box::new(source), Metadata::new_unorganized(count), count))
+}
+
+/// Row source over the vertices of a PLY file
+struct PlyRowSource {
+    payload: PayloadReader<BufReader<File>>,
+    vertex: PlyElement,
+    sources: Vec<usize>,
+    row: Vec<f64>,
+}
+
+impl RowSource for PlyRowSource {
+    fn read_row(&mut self, values: &mut [f64]) -> Result<()> {
+        self.payload
+            .read_element(&self.vertex, None, &mut self.row, &mut Vec::new())?;
+        for (value, &source) in values.iter_mut().zip(self.sources.iter()) {
+            *value = self.row[source];
+        }
+        Ok(())
+    }
+}
+
+/// Create a PLY file for writing vertices one chunk at a time
+pub(crate) fn create_row_sink(
+    path: &Path,
+    fields: &'static [FieldDescriptor],
+    encoding: PlyEncoding,
+) -> Result<Box<dyn RowSink>> {
+    let mut sink = PlyRowSink {
+        writer: BufWriter::new(File::create(path)?),
+        fields,
+        payload: PayloadWriter::new(encoding),
+        encoding,
+        len: 0,
+    };
+    sink.write_padded_header()?;
+    Ok(Box::new(sink))
+}
+
+/// Row sink appending vertices to a PLY file
+struct PlyRowSink {
+    writer: BufWriter<File>,
+    fields: &'static [FieldDescriptor],
+    payload: PayloadWriter,
+    encoding: PlyEncoding,
+    len: usize,
+}
+
+impl PlyRowSink {
+    fn write_padded_header(&mut self) -> Result<()> {
+        let header = PlyHeader {
+            encoding: self.encoding,
+            comments: vec!["Generated by ferrum_cloud".to_string()],
+            elements: vec![vertex_element(self.fields, self.len)],
+            data_offset: 0,
+        };
+        let mut text = Vec::new();
+        write_header(&mut text, &header)?;
+        self.writer
+            .write_all(&pad_count_lines(&text, &["element vertex "]))?;
+        Ok(())
+    }
+}
+
+impl RowSink for PlyRowSink {
+    fn write_rows(&mut self, rows: &PointRows<'_>) -> Result<()> {
+        let mut values = vec![0.0f64; self.fields.len()];
+        for index in 0..rows.len() {
+            rows.write_row(index, &mut values);
+            for (field, &value) in self.fields.iter().zip(values.iter()) {
+                self.payload.push(field.datatype, value);
+            }
+            self.payload.end_element(&mut self.writer)?;
+        }
+        self.len += rows.len();
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        self.writer.seek(SeekFrom::Start(0))?;
+        self.write_padded_header()?;
+        self.writer.flush()?;
+        Ok(())
+    }
 }
 
-/// Save a point cloud to a PLY file
+/// Vertex element with one scalar property per point field
+fn vertex_element(fields: &[FieldDescriptor], count: usize) -> PlyElement {
+    PlyElement {
+        name: "vertex".to_string(),
+        count,
+        properties: fields
+            .iter()
+            .map(|field| PlyPropertyDef {
+                name: property_aliases(field.name)[0].to_string(),
+                kind: PlyPropertyKind::Scalar(field.datatype),
+            })
+            .collect(),
+    }
+}
+
+/// Save a point cloud to an ASCII PLY file
+///
+/// Unlike [`io::save`](super::save), whose default [`PlyFormat`] writes
+/// little-endian binary, this keeps writing ASCII for compatibility with
+/// earlier versions.
 ///
 /// # Arguments
 /// * `cloud` - The point cloud to save
@@ -84,65 +453,520 @@ fn extract_coordinate(element: &DefaultElement, coord_name: &str) -> Result<f32>
 ///
 /// # Returns
 /// A Result indicating success or failure
-pub fn save_ply<P: Point, Q: AsRef<Path>>(cloud: &PointCloud<P>, path: Q) -> Result<()> {
-    let file = File::create(path.as_ref())?;
-
-    let mut writer = BufWriter::new(file);
-
-    // Create PLY structure
-    let mut ply = Ply::<DefaultElement>::new();
-    ply.header.encoding = Encoding::Ascii;
-    ply.header
-        .comments
-        .push("Generated by ferrum_cloud".to_string());
-
-    // Define vertex element
-    let mut vertex_element = ElementDef::new("vertex".to_string());
-    vertex_element.count = cloud.len();
-
-    // Add properties for x, y, z coordinates
-    vertex_element.properties.insert(
-        "x".to_string(),
-        PropertyDef::new("x".to_string(), PropertyType::Scalar(ScalarType::Float)),
-    );
-    vertex_element.properties.insert(
-        "y".to_string(),
-        PropertyDef::new("y".to_string(), PropertyType::Scalar(ScalarType::Float)),
-    );
-    vertex_element.properties.insert(
-        "z".to_string(),
-        PropertyDef::new("z".to_string(), PropertyType::Scalar(ScalarType::Float)),
-    );
-
-    ply.header
-        .elements
-        .insert("vertex".to_string(), vertex_element);
+pub fn save_ply<P: PointFields, Q: AsRef<Path>>(cloud: &PointCloud<P>, path: Q) -> Result<()> {
+    save_ply_with_encoding(cloud, path, PlyEncoding::Ascii)
+}
+
+/// Save a point cloud to a PLY file with the given encoding
+///
+/// Every field of the point type becomes a vertex property.
+pub fn save_ply_with_encoding<P: PointFields, Q: AsRef<Path>>(
+    cloud: &PointCloud<P>,
+    path: Q,
+    encoding: PlyEncoding,
+) -> Result<()> {
+    write_ply(
+        path.as_ref(),
+        &PointRows::from_cloud(cloud),
+        &[],
+        &[],
+        encoding,
+    )
+}
 
-    // Create vertex data
-    let mut vertices = Vec::new();
-    for point in cloud.points() {
-        let pos = point.position();
-        let mut vertex = DefaultElement::new();
-        vertex.insert("x".to_string(), ply_rs::ply::Property::Float(pos[0]));
-        vertex.insert("y".to_string(), ply_rs::ply::Property::Float(pos[1]));
-        vertex.insert("z".to_string(), ply_rs::ply::Property::Float(pos[2]));
-        vertices.push(vertex);
+/// Save a PLY file including extra vertex properties and faces
+pub fn save_ply_data<P: PointFields, Q: AsRef<Path>>(
+    data: &PlyData<P>,
+    path: Q,
+    encoding: PlyEncoding,
+) -> Result<()> {
+    write_ply(
+        path.as_ref(),
+        &PointRows::from_cloud(&data.cloud),
+        &data.properties,
+        &data.faces,
+        encoding,
+    )
+}
+
+/// PLY format for the [`io::load`](super::load) and [`io::save`](super::save) registry
+///
+/// The default format writes little-endian binary, while [`save_ply`]
+/// writes ASCII.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PlyFormat {
+    /// Encoding used when writing
+    pub encoding: PlyEncoding,
+}
+
+impl PlyFormat {
+    /// Create a PLY format writing the given encoding
+    pub fn new(encoding: PlyEncoding) -> Self {
+        Self { encoding }
+    }
+}
+
+impl Default for PlyFormat {
+    fn default() -> Self {
+        Self::new(PlyEncoding::BinaryLittleEndian)
+    }
+}
+
+impl PointCloudReader for PlyFormat {
+    fn name(&self) -> &str {
+        "ply"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ply"]
+    }
+
+    fn matches_magic(&self, bytes: &[u8]) -> bool {
+        bytes.starts_with(b"ply\n") || bytes.starts_with(b"ply\r\n")
+    }
+
+    fn read(
+        &self,
+        path: &Path,
+        fields: &[FieldDescriptor],
+        sink: &mut dyn FnMut(&[f64]),
+    ) -> Result<Metadata> {
+        let mut count = 0;
+        read_ply_rows(path, fields, &mut |values| {
+            count += 1;
+            sink(values)
+        })?;
+        Ok(Metadata::new_unorganized(count))
+    }
+}
+
+impl PointCloudWriter for PlyFormat {
+    fn name(&self) -> &str {
+        "ply"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ply"]
+    }
+
+    fn write(&self, path: &Path, rows: &PointRows<'_>) -> Result<()> {
+        write_ply(path, rows, &[], &[], self.encoding)
+    }
+}
+
+fn write_ply(
+    path: &Path,
+    rows: &PointRows<'_>,
+    properties: &[PlyProperty],
+    faces: &[Vec<u32>],
+    encoding: PlyEncoding,
+) -> Result<()> {
+    if let Some(property) = properties.iter().find(|p| p.values.len() != rows.len()) {
+        return Err(CloudError::invalid_parameter(format!(
+            "PLY property {} has {} values for {} vertices",
+            property.name,
+            property.values.len(),
+            rows.len()
+        )));
+    }
+
+    let fields = rows.fields();
+    let mut vertex = vertex_element(fields, rows.len());
+    vertex
+        .properties
+        .extend(properties.iter().map(|property| PlyPropertyDef {
+            name: property.name.clone(),
+            kind: PlyPropertyKind::Scalar(property.datatype),
+        }));
+
+    let mut header = PlyHeader {
+        encoding,
+        comments: vec!["Generated by ferrum_cloud".to_string()],
+        elements: vec![vertex],
+        data_offset: 0,
+    };
+
+    let face_count_type = if faces.iter().all(|face| face.len() <= u8::MAX as usize) {
+        FieldType::U8
+    } else {
+        FieldType::I32
+    };
+    if !faces.is_empty() {
+        header.elements.push(PlyElement {
+            name: "face".to_string(),
+            count: faces.len(),
+            properties: vec![PlyPropertyDef {
+                name: "vertex_indices".to_string(),
+                kind: PlyPropertyKind::List {
+                    count: face_count_type,
+                    item: FieldType::I32,
+                },
+            }],
+        });
     }
 
-    ply.payload.insert("vertex".to_string(), vertices);
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_header(&mut writer, &header)?;
 
-    // Write PLY file
-    let ply_writer = Writer::new();
-    ply_writer
-        .write_ply(&mut writer, &mut ply)
-        .map_err(|e| CloudError::format_error(format!("Failed to write PLY file: {}", e)))?;
+    let mut payload = PayloadWriter::new(encoding);
+    let mut values = vec![0.0f64; fields.len()];
+    for index in 0..rows.len() {
+        rows.write_row(index, &mut values);
+        for (field, &value) in fields.iter().zip(values.iter()) {
+            payload.push(field.datatype, value);
+        }
+        for property in properties {
+            payload.push(property.datatype, property.values[index]);
+        }
+        payload.end_element(&mut writer)?;
+    }
+
+    for face in faces {
+        payload.push(face_count_type, face.len() as f64);
+        for &index in face {
+            payload.push(FieldType::I32, index as f64);
+        }
+        payload.end_element(&mut writer)?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+/// Parse a PLY header, leaving the reader at the start of the payload
+pub(crate) fn parse_header<R: BufRead>(reader: &mut R) -> Result<PlyHeader> {
+    let mut encoding = None;
+    let mut comments = Vec::new();
+    let mut elements: Vec<PlyElement> = Vec::new();
+
+    let mut offset = 0;
+    let mut line = Vec::new();
+    let mut first = true;
+    loop {
+        line.clear();
+        let read = reader.read_until(b'\n', &mut line)?;
+        if read == 0 {
+            return Err(CloudError::format_error(
+                "PLY header ended without end_header",
+            ));
+        }
+        offset += read;
+
+        let text = std::str::from_utf8(&line)
+            .map_err(|_| CloudError::format_error("PLY header is not valid text"))?;
+        let tokens: Vec<&str> = text.split_whitespace().collect();
+
+        if first {
+            if tokens != ["ply"] {
+                return Err(CloudError::format_error("Not a PLY file"));
+            }
+            first = false;
+            continue;
+        }
+
+        match tokens.as_slice() {
+            [] => {}
+            ["format", kind, _version] => {
+                encoding = Some(PlyEncoding::from_keyword(kind).ok_or_else(|| {
+                    CloudError::format_error(format!("Unsupported PLY format: {}", kind))
+                })?);
+            }
+            ["comment", ..] => {
+                let comment = text.trim_start().trim_start_matches("comment");
+                comments.push(comment.trim().to_string());
+            }
+            ["obj_info", ..] => {}
+            ["element", name, count] => elements.push(PlyElement {
+                name: name.to_string(),
+                count: count.parse().map_err(|_| {
+                    CloudError::format_error(format!("Invalid PLY element count: {}", count))
+                })?,
+                properties: Vec::new(),
+            }),
+            ["property", "list", count, item, name] => {
+                let kind = PlyPropertyKind::List {
+                    count: field_type_from_ply(count)?,
+                    item: field_type_from_ply(item)?,
+                };
+                push_property(&mut elements, name, kind)?;
+            }
+            ["property", datatype, name] => {
+                let kind = PlyPropertyKind::Scalar(field_type_from_ply(datatype)?);
+                push_property(&mut elements, name, kind)?;
+            }
+            ["end_header"] => {
+                let encoding = encoding
+                    .ok_or_else(|| CloudError::format_error("PLY header has no format line"))?;
+                return Ok(PlyHeader {
+                    encoding,
+                    comments,
+                    elements,
+                    data_offset: offset,
+                });
+            }
+            _ => {
+                return Err(CloudError::format_error(format!(
+                    "Invalid PLY header line: {}",
+                    text.trim()
+                )));
+            }
+        }
+    }
+}
+
+fn push_property(elements: &mut [PlyElement], name: &str, kind: PlyPropertyKind) -> Result<()> {
+    let element = elements
+        .last_mut()
+        .ok_or_else(|| CloudError::format_error("PLY property declared before any element"))?;
+    element.properties.push(PlyPropertyDef {
+        name: name.to_string(),
+        kind,
+    });
+    Ok(())
+}
 
+fn write_header<W: Write>(writer: &mut W, header: &PlyHeader) -> Result<()> {
+    writeln!(writer, "ply")?;
+    writeln!(writer, "format {} 1.0", header.encoding.keyword())?;
+    for comment in &header.comments {
+        writeln!(writer, "comment {}", comment)?;
+    }
+    for element in &header.elements {
+        writeln!(writer, "element {} {}", element.name, element.count)?;
+        for property in &element.properties {
+            match property.kind {
+                PlyPropertyKind::Scalar(datatype) => writeln!(
+                    writer,
+                    "property {} {}",
+                    ply_type_name(datatype),
+                    property.name
+                )?,
+                PlyPropertyKind::List { count, item } => writeln!(
+                    writer,
+                    "property list {} {} {}",
+                    ply_type_name(count),
+                    ply_type_name(item),
+                    property.name
+                )?,
+            }
+        }
+    }
+    writeln!(writer, "end_header")?;
     Ok(())
 }
 
+/// PLY property names accepted for a point field, preferred name first
+pub(crate) fn property_aliases(field: &str) -> Vec<&str> {
+    match field {
+        "r" => vec!["red", "r", "diffuse_red"],
+        "g" => vec!["green", "g", "diffuse_green"],
+        "b" => vec!["blue", "b", "diffuse_blue"],
+        "normal_x" => vec!["nx", "normal_x"],
+        "normal_y" => vec!["ny", "normal_y"],
+        "normal_z" => vec!["nz", "normal_z"],
+        _ => vec![field],
+    }
+}
+
+fn field_type_from_ply(name: &str) -> Result<FieldType> {
+    match name {
+        "char" | "int8" => Ok(FieldType::I8),
+        "uchar" | "uint8" => Ok(FieldType::U8),
+        "short" | "int16" => Ok(FieldType::I16),
+        "ushort" | "uint16" => Ok(FieldType::U16),
+        "int" | "int32" => Ok(FieldType::I32),
+        "uint" | "uint32" => Ok(FieldType::U32),
+        "float" | "float32" => Ok(FieldType::F32),
+        "double" | "float64" => Ok(FieldType::F64),
+        _ => Err(CloudError::format_error(format!(
+            "Unsupported PLY property type: {}",
+            name
+        ))),
+    }
+}
+
+fn ply_type_name(datatype: FieldType) -> &'static str {
+    match datatype {
+        FieldType::I8 => "char",
+        FieldType::U8 => "uchar",
+        FieldType::I16 => "short",
+        FieldType::U16 => "ushort",
+        FieldType::I32 => "int",
+        FieldType::U32 => "uint",
+        FieldType::F32 => "float",
+        FieldType::F64 => "double",
+    }
+}
+
+/// Sequential reader over a PLY payload
+enum PayloadReader<R> {
+    Ascii(TokenReader<R>),
+    Binary { reader: R, big_endian: bool },
+}
+
+impl<R: BufRead> PayloadReader<R> {
+    fn new(encoding: PlyEncoding, reader: R) -> Self {
+        match encoding {
+            PlyEncoding::Ascii => PayloadReader::Ascii(TokenReader::new(reader)),
+            PlyEncoding::BinaryLittleEndian | PlyEncoding::BinaryBigEndian => {
+                PayloadReader::Binary {
+                    reader,
+                    big_endian: encoding == PlyEncoding::BinaryBigEndian,
+                }
+            }
+        }
+    }
+
+    fn read(&mut self, datatype: FieldType) -> Result<f64> {
+        match self {
+            PayloadReader::Ascii(tokens) => {
+                let token = tokens
+                    .next_token()?
+                    .ok_or_else(|| CloudError::format_error("PLY ascii data is truncated"))?;
+                let invalid =
+                    || CloudError::format_error(format!("Invalid PLY ascii value: {}", token));
+                match datatype {
+                    FieldType::F32 => token.parse::<f32>().map(f64::from).map_err(|_| invalid()),
+                    FieldType::F64 => token.parse::<f64>().map_err(|_| invalid()),
+                    _ => match token.parse::<i64>() {
+                        Ok(value) => Ok(value as f64),
+                        Err(_) => token.parse::<f64>().map_err(|_| invalid()),
+                    },
+                }
+            }
+            PayloadReader::Binary { reader, big_endian } => {
+                let mut bytes = [0u8; 8];
+                let bytes = &mut bytes[..datatype.size()];
+                reader.read_exact(bytes).map_err(|e| {
+                    if e.kind() == ErrorKind::UnexpectedEof {
+                        CloudError::format_error("PLY binary data is truncated")
+                    } else {
+                        e.into()
+                    }
+                })?;
+                Ok(if *big_endian {
+                    read_binary::<BigEndian>(datatype, bytes)
+                } else {
+                    read_binary::<LittleEndian>(datatype, bytes)
+                })
+            }
+        }
+    }
+
+    /// Read the values of one element, with `NaN` in place of list properties
+    ///
+    /// The items of the list property at `keep_list`, if any, are stored in
+    /// `list`; other lists are skipped.
+    fn read_element(
+        &mut self,
+        element: &PlyElement,
+        keep_list: Option<usize>,
+        row: &mut Vec<f64>,
+        list: &mut Vec<u32>,
+    ) -> Result<()> {
+        row.clear();
+        for (index, property) in element.properties.iter().enumerate() {
+            match property.kind {
+                PlyPropertyKind::Scalar(datatype) => row.push(self.read(datatype)?),
+                PlyPropertyKind::List { count, item } => {
+                    let length = self.read(count)? as usize;
+                    if keep_list == Some(index) {
+                        list.clear();
+                        for _ in 0..length {
+                            list.push(self.read(item)? as u32);
+                        }
+                    } else {
+                        for _ in 0..length {
+                            self.read(item)?;
+                        }
+                    }
+                    row.push(f64::NAN);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Element-at-a-time writer of a PLY payload
+struct PayloadWriter {
+    encoding: PlyEncoding,
+    buffer: Vec<u8>,
+}
+
+impl PayloadWriter {
+    fn new(encoding: PlyEncoding) -> Self {
+        Self {
+            encoding,
+            buffer: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, datatype: FieldType, value: f64) {
+        match self.encoding {
+            PlyEncoding::Ascii => {
+                if !self.buffer.is_empty() {
+                    self.buffer.push(b' ');
+                }
+                let text = match datatype {
+                    FieldType::F32 | FieldType::F64 => format_ascii_float(value, datatype),
+                    _ => (value as i64).to_string(),
+                };
+                self.buffer.extend_from_slice(text.as_bytes());
+            }
+            PlyEncoding::BinaryLittleEndian => {
+                write_binary::<LittleEndian>(datatype, value, &mut self.buffer)
+            }
+            PlyEncoding::BinaryBigEndian => {
+                write_binary::<BigEndian>(datatype, value, &mut self.buffer)
+            }
+        }
+    }
+
+    fn end_element<W: Write>(&mut self, writer: &mut W) -> Result<()> {
+        if self.encoding == PlyEncoding::Ascii {
+            self.buffer.push(b'\n');
+        }
+        writer.write_all(&self.buffer)?;
+        self.buffer.clear();
+        Ok(())
+    }
+}
+
+pub(crate) fn read_binary<B: ByteOrder>(datatype: FieldType, bytes: &[u8]) -> f64 {
+    match datatype {
+        FieldType::I8 => bytes[0] as i8 as f64,
+        FieldType::U8 => bytes[0] as f64,
+        FieldType::I16 => B::read_i16(bytes) as f64,
+        FieldType::U16 => B::read_u16(bytes) as f64,
+        FieldType::I32 => B::read_i32(bytes) as f64,
+        FieldType::U32 => B::read_u32(bytes) as f64,
+        FieldType::F32 => B::read_f32(bytes) as f64,
+        FieldType::F64 => B::read_f64(bytes),
+    }
+}
+
+fn write_binary<B: ByteOrder>(datatype: FieldType, value: f64, out: &mut Vec<u8>) {
+    let start = out.len();
+    out.resize(start + datatype.size(), 0);
+    let bytes = &mut out[start..];
+    match datatype {
+        FieldType::I8 => bytes[0] = value as i8 as u8,
+        FieldType::U8 => bytes[0] = value as u8,
+        FieldType::I16 => B::write_i16(bytes, value as i16),
+        FieldType::U16 => B::write_u16(bytes, value as u16),
+        FieldType::I32 => B::write_i32(bytes, value as i32),
+        FieldType::U32 => B::write_u32(bytes, value as u32),
+        FieldType::F32 => B::write_f32(bytes, value as f32),
+        FieldType::F64 => B::write_f64(bytes, value),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::{PointXYZI, PointXYZRGB, PointXYZRGBNormal};
+    use std::io::Write;
     use tempfile::NamedTempFile;
 
     #[test]
@@ -175,4 +999,96 @@ mod tests {
             assert!((orig_pos[2] - load_pos[2]).abs() < 1e-6);
         }
     }
+
+    #[test]
+    fn test_encodings_roundtrip() {
+        let points = vec![
+            PointXYZRGBNormal::new(0.0, 0.5, 1.0, 255, 0, 10, 0.0, 0.0, 1.0),
+            PointXYZRGBNormal::new(1.0, -2.0, 3.5, 1, 2, 3, 0.6, 0.8, 0.0),
+            PointXYZRGBNormal::new(f32::NAN, 0.0, 0.0, 0, 0, 0, 1.0, 0.0, 0.0),
+        ];
+        let cloud = PointCloud::from_points(points);
+
+        for encoding in [
+            PlyEncoding::Ascii,
+            PlyEncoding::BinaryLittleEndian,
+            PlyEncoding::BinaryBigEndian,
+        ] {
+            let file = NamedTempFile::new().unwrap();
+            save_ply_with_encoding(&cloud, file.path(), encoding).unwrap();
+
+            let loaded: PointCloud<PointXYZRGBNormal> = load_ply_as(file.path()).unwrap();
+            assert_eq!(
+                format!("{:?}", loaded.points()),
+                format!("{:?}", cloud.points())
+            );
+
+            let intensity = load_ply_as::<PointXYZI, _>(file.path()).unwrap_err();
+            assert!(intensity.to_string().contains("intensity"));
+        }
+    }
+
+    #[test]
+    fn test_extra_properties_and_faces() {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(
+            b"ply\n\
+              format ascii 1.0\n\
+              element vertex 3\n\
+              property float x\n\
+              property float y\n\
+              property float z\n\
+              property uchar red\n\
+              property uchar green\n\
+              property uchar blue\n\
+              property float intensity\n\
+              property ushort segment\n\
+              element face 1\n\
+              property list uchar uint vertex_index\n\
+              end_header\n\
+              0 0 0 255 0 0 0.5 7\n\
+              1 0 0 0 255 0 0.25 7\n\
+              0 1 0 0 0 255 1 9\n\
+              3 0 1 2\n",
+        )
+        .unwrap();
+
+        let data: PlyData<PointXYZI> = load_ply_data(file.path()).unwrap();
+        assert_eq!(data.cloud.get(1).unwrap().intensity, 0.25);
+        assert_eq!(data.faces, vec![vec![0, 1, 2]]);
+
+        let names: Vec<&str> = data.properties.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["red", "green", "blue", "segment"]);
+        let segment = data.property("segment").unwrap();
+        assert_eq!(segment.datatype, FieldType::U16);
+        assert_eq!(segment.values, vec![7.0, 7.0, 9.0]);
+
+        // Colors and faces survive a binary save and a load as another point type
+        let saved = NamedTempFile::new().unwrap();
+        save_ply_data(&data, saved.path(), PlyEncoding::BinaryLittleEndian).unwrap();
+
+        let colored: PlyData<PointXYZRGB> = load_ply_data(saved.path()).unwrap();
+        assert_eq!(colored.cloud.get(2).unwrap().color(), Some([0, 0, 255]));
+        assert_eq!(colored.faces, data.faces);
+        assert_eq!(colored.property("intensity").unwrap().values[0], 0.5);
+        assert_eq!(colored.property("segment").unwrap().values, segment.values);
+    }
+
+    #[test]
+    fn test_rejects_oversized_vertex_count() {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(
+            format!(
+                "ply\nformat binary_little_endian 1.0\nelement vertex {}\n\
+                 property float x\nproperty float y\nproperty float z\n\
+                 property float intensity\nend_header\n",
+                usize::MAX
+            )
+            .as_bytes(),
+        )
+        .unwrap();
+        file.write_all(&[0; 16]).unwrap();
+
+        assert!(load_ply_data::<PointXYZ, _>(file.path()).is_err());
+    }
 }
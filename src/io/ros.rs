@@ -0,0 +1,1286 @@
+//! ROS point cloud message support
+//!
+//! This module converts between point clouds and `sensor_msgs/PointCloud2`
+//! messages, and reads PointCloud2 topics from recordings on disk:
+//!
+//! - [`PointCloud2`] holds a decoded message. It can be parsed from and
+//!   serialized to both the ROS1 wire format and the CDR encoding used by
+//!   ROS2.
+//! - [`load_bag`] reads a topic from a ROS1 `.bag` file (format 2.0).
+//! - [`load_mcap`] reads a topic from a ROS2 MCAP file.
+//!
+//! Point fields are matched by name; colors may come from a packed `rgb` or
+//! `rgba` field as written by PCL. The message header is kept in the
+//! metadata as the custom fields `frame_id` and `stamp` (`seconds.nanoseconds`),
+//! and `is_dense` maps to [`Metadata::is_dense`].
+//!
+//! Bag chunks may be uncompressed or compressed with lz4 or bz2, MCAP chunks
+//! uncompressed or compressed with lz4 or zstd.
+
+use super::mmap::map_file;
+use super::pcd::{output_columns, read_value};
+use super::ply::read_binary;
+use crate::core::{FieldDescriptor, FieldType, Metadata, PointCloud, PointFields};
+use crate::error::{CloudError, Result};
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::Path;
+
+/// File signature of ROS1 bag files
+const BAG_MAGIC: &[u8] = b"#ROSBAG V2.0\n";
+
+/// File signature of MCAP files
+const MCAP_MAGIC: &[u8] = b"\x89MCAP0\r\n";
+
+/// ROS1 message type of point clouds
+const ROS1_POINT_CLOUD2: &str = "sensor_msgs/PointCloud2";
+
+/// ROS2 message type of point clouds
+const ROS2_POINT_CLOUD2: &str = "sensor_msgs/msg/PointCloud2";
+
+/// Standard message header
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RosHeader {
+    /// Sequence number, only present in ROS1 messages
+    pub seq: u32,
+    pub stamp_sec: u32,
+    pub stamp_nsec: u32,
+    pub frame_id: String,
+}
+
+/// Description of one field in a [`PointCloud2`] record
+#[derive(Clone, Debug, PartialEq)]
+pub struct PointField {
+    pub name: String,
+    /// Byte offset of the field within a point record
+    pub offset: u32,
+    /// Data type code, one of the associated constants
+    pub datatype: u8,
+    /// Number of elements of the field
+    pub count: u32,
+}
+
+impl PointField {
+    pub const INT8: u8 = 1;
+    pub const UINT8: u8 = 2;
+    pub const INT16: u8 = 3;
+    pub const UINT16: u8 = 4;
+    pub const INT32: u8 = 5;
+    pub const UINT32: u8 = 6;
+    pub const FLOAT32: u8 = 7;
+    pub const FLOAT64: u8 = 8;
+
+    /// Field type of the data type code
+    pub fn field_type(&self) -> Option<FieldType> {
+        Some(match self.datatype {
+            Self::INT8 => FieldType::I8,
+            Self::UINT8 => FieldType::U8,
+            Self::INT16 => FieldType::I16,
+            Self::UINT16 => FieldType::U16,
+            Self::INT32 => FieldType::I32,
+            Self::UINT32 => FieldType::U32,
+            Self::FLOAT32 => FieldType::F32,
+            Self::FLOAT64 => FieldType::F64,
+            _ => return None,
+        })
+    }
+
+    fn datatype_code(datatype: FieldType) -> u8 {
+        match datatype {
+            FieldType::I8 => Self::INT8,
+            FieldType::U8 => Self::UINT8,
+            FieldType::I16 => Self::INT16,
+            FieldType::U16 => Self::UINT16,
+            FieldType::I32 => Self::INT32,
+            FieldType::U32 => Self::UINT32,
+            FieldType::F32 => Self::FLOAT32,
+            FieldType::F64 => Self::FLOAT64,
+        }
+    }
+}
+
+/// A `sensor_msgs/PointCloud2` message
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PointCloud2 {
+    pub header: RosHeader,
+    /// Number of rows, 1 for unorganized clouds
+    pub height: u32,
+    /// Number of points per row
+    pub width: u32,
+    pub fields: Vec<PointField>,
+    pub is_bigendian: bool,
+    /// Size of one point record in bytes
+    pub point_step: u32,
+    /// Size of one row in bytes
+    pub row_step: u32,
+    pub data: Vec<u8>,
+    /// Whether all points are finite
+    pub is_dense: bool,
+}
+
+impl PointCloud2 {
+    /// Encode a point cloud as a little-endian message
+    ///
+    /// Colors are packed into an `rgb` field and the header is taken from
+    /// the `frame_id` and `stamp` custom metadata fields, if present.
+    ///
+    /// # Example
+    /// ```rust
+    /// use ferrum_cloud::io::ros::PointCloud2;
+    /// use ferrum_cloud::prelude::*;
+    ///
+    /// let cloud = PointCloud::from_points(vec![PointXYZ::new(1.0, 2.0, 3.0)]);
+    /// let message = PointCloud2::from_cloud(&cloud);
+    /// let bytes = message.to_ros1_bytes();
+    ///
+    /// let decoded: PointCloud<PointXYZ> = PointCloud2::from_ros1_bytes(&bytes)?.to_cloud()?;
+    /// assert_eq!(decoded.points(), cloud.points());
+    /// # Ok::<(), CloudError>(())
+    /// ```
+    pub fn from_cloud<T: PointFields>(cloud: &PointCloud<T>) -> Self {
+        let metadata = cloud.metadata();
+        let columns = output_columns(T::fields());
+
+        let mut fields = Vec::with_capacity(columns.len());
+        let mut offset = 0;
+        for column in &columns {
+            fields.push(PointField {
+                name: column.name.to_string(),
+                offset: offset as u32,
+                datatype: PointField::datatype_code(column.datatype),
+                count: 1,
+            });
+            offset += column.datatype.size();
+        }
+        let point_step = offset;
+
+        let mut data = vec![0u8; cloud.len() * point_step];
+        let mut values = vec![0.0f64; T::fields().len()];
+        for (point, record) in cloud.iter().zip(data.chunks_exact_mut(point_step.max(1))) {
+            point.write_values(&mut values);
+            for (column, field) in columns.iter().zip(fields.iter()) {
+                column.encode(&values, &mut record[field.offset as usize..]);
+            }
+        }
+
+        let (width, height) = if metadata.is_organized && metadata.point_count() == cloud.len() {
+            (metadata.width, metadata.height)
+        } else {
+            (cloud.len() as u32, 1)
+        };
+
+        let (stamp_sec, stamp_nsec) = metadata
+            .get_custom_field("stamp")
+            .and_then(|stamp| parse_stamp(stamp))
+            .unwrap_or((0, 0));
+        let header = RosHeader {
+            seq: 0,
+            stamp_sec,
+            stamp_nsec,
+            frame_id: metadata
+                .get_custom_field("frame_id")
+                .cloned()
+                .unwrap_or_default(),
+        };
+
+        Self {
+            header,
+            height,
+            width,
+            fields,
+            is_bigendian: false,
+            point_step: point_step as u32,
+            row_step: (point_step * width as usize) as u32,
+            data,
+            is_dense: metadata.is_dense(),
+        }
+    }
+
+    /// Decode the message into a point cloud
+    ///
+    /// Messages with a height above 1 become organized clouds.
+    pub fn to_cloud<T: PointFields>(&self) -> Result<PointCloud<T>> {
+        let fields = T::fields();
+        let sources = self.resolve_field_sources(fields)?;
+
+        let width = self.width as usize;
+        let height = self.height as usize;
+        let point_step = self.point_step as usize;
+        let row_step = self.row_step as usize;
+        if width > 0 && height > 0 {
+            let needed = (height - 1)
+                .checked_mul(row_step)
+                .and_then(|rows| rows.checked_add(width.checked_mul(point_step)?))
+                .ok_or_else(|| CloudError::format_error("PointCloud2 dimensions are too large"))?;
+            if row_step < width * point_step {
+                return Err(CloudError::format_error(format!(
+                    "PointCloud2 row step {} is smaller than {} points of {} bytes",
+                    row_step, width, point_step
+                )));
+            }
+            if self.data.len() < needed {
+                return Err(CloudError::format_error(format!(
+                    "PointCloud2 data is truncated: expected {} bytes, found {}",
+                    needed,
+                    self.data.len()
+                )));
+            }
+        }
+
+        let mut points = Vec::with_capacity(width * height);
+        let mut values = vec![0.0f64; fields.len()];
+        for row in 0..height {
+            for column in 0..width {
+                let start = row * row_step + column * point_step;
+                let record = &self.data[start..start + point_step];
+                for (value, source) in values.iter_mut().zip(sources.iter()) {
+                    *value = source.read(record, self.is_bigendian);
+                }
+                points.push(T::from_values(&values));
+            }
+        }
+
+        let mut metadata = if height > 1 {
+            Metadata::new_organized(self.width, self.height)
+        } else {
+            Metadata::new_unorganized(points.len())
+        };
+        metadata.set_dense(self.is_dense);
+        metadata = metadata.with_custom_field(
+            "stamp",
+            format!("{}.{:09}", self.header.stamp_sec, self.header.stamp_nsec),
+        );
+        if !self.header.frame_id.is_empty() {
+            metadata = metadata.with_custom_field("frame_id", self.header.frame_id.as_str());
+        }
+
+        Ok(PointCloud::from_points_and_metadata(points, metadata))
+    }
+
+    /// Parse a message serialized in the ROS1 wire format
+    pub fn from_ros1_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut reader = MessageReader::new(bytes, false);
+        let header = RosHeader {
+            seq: reader.u32()?,
+            stamp_sec: reader.u32()?,
+            stamp_nsec: reader.u32()?,
+            frame_id: reader.string()?,
+        };
+        Self::read_body(&mut reader, header)
+    }
+
+    /// Serialize the message in the ROS1 wire format
+    pub fn to_ros1_bytes(&self) -> Vec<u8> {
+        let mut writer = MessageWriter::new(false);
+        writer.u32(self.header.seq);
+        writer.u32(self.header.stamp_sec);
+        writer.u32(self.header.stamp_nsec);
+        writer.string(&self.header.frame_id);
+        self.write_body(&mut writer);
+        writer.bytes
+    }
+
+    /// Parse a message serialized with CDR, as used by ROS2
+    pub fn from_cdr_bytes(bytes: &[u8]) -> Result<Self> {
+        let big_endian = match bytes.get(..4) {
+            Some([0x00, 0x00, _, _]) => true,
+            Some([0x00, 0x01, _, _]) => false,
+            _ => {
+                return Err(CloudError::format_error(
+                    "PointCloud2 message has an unsupported CDR encapsulation",
+                ));
+            }
+        };
+        let mut reader = MessageReader::new(&bytes[4..], true);
+        reader.big_endian = big_endian;
+        let header = RosHeader {
+            seq: 0,
+            stamp_sec: reader.u32()?,
+            stamp_nsec: reader.u32()?,
+            frame_id: reader.string()?,
+        };
+        Self::read_body(&mut reader, header)
+    }
+
+    /// Serialize the message with little-endian CDR, as used by ROS2
+    pub fn to_cdr_bytes(&self) -> Vec<u8> {
+        let mut writer = MessageWriter::new(true);
+        writer.u32(self.header.stamp_sec);
+        writer.u32(self.header.stamp_nsec);
+        writer.string(&self.header.frame_id);
+        self.write_body(&mut writer);
+
+        let mut bytes = vec![0x00, 0x01, 0x00, 0x00];
+        bytes.append(&mut writer.bytes);
+        bytes
+    }
+
+    /// Read everything after the header
+    fn read_body(reader: &mut MessageReader<'_>, header: RosHeader) -> Result<Self> {
+        let height = reader.u32()?;
+        let width = reader.u32()?;
+        let field_count = reader.u32()? as usize;
+        let mut fields = Vec::with_capacity(field_count.min(64));
+        for _ in 0..field_count {
+            fields.push(PointField {
+                name: reader.string()?,
+                offset: reader.u32()?,
+                datatype: reader.u8()?,
+                count: reader.u32()?,
+            });
+        }
+        Ok(Self {
+            header,
+            height,
+            width,
+            fields,
+            is_bigendian: reader.u8()? != 0,
+            point_step: reader.u32()?,
+            row_step: reader.u32()?,
+            data: reader.byte_sequence()?.to_vec(),
+            is_dense: reader.u8()? != 0,
+        })
+    }
+
+    /// Write everything after the header
+    fn write_body(&self, writer: &mut MessageWriter) {
+        writer.u32(self.height);
+        writer.u32(self.width);
+        writer.u32(self.fields.len() as u32);
+        for field in &self.fields {
+            writer.string(&field.name);
+            writer.u32(field.offset);
+            writer.u8(field.datatype);
+            writer.u32(field.count);
+        }
+        writer.u8(self.is_bigendian as u8);
+        writer.u32(self.point_step);
+        writer.u32(self.row_step);
+        writer.byte_sequence(&self.data);
+        writer.u8(self.is_dense as u8);
+    }
+
+    /// Map every requested field to its location in the point records
+    fn resolve_field_sources(&self, fields: &[FieldDescriptor]) -> Result<Vec<FieldSource>> {
+        let point_step = self.point_step as usize;
+        let find = |name: &str| -> Result<Option<(usize, FieldType)>> {
+            let Some(field) = self.fields.iter().find(|f| f.name == name && f.count > 0) else {
+                return Ok(None);
+            };
+            let datatype = field.field_type().ok_or_else(|| {
+                CloudError::format_error(format!(
+                    "PointCloud2 field '{}' has unknown datatype {}",
+                    field.name, field.datatype
+                ))
+            })?;
+            let offset = field.offset as usize;
+            if offset + datatype.size() > point_step {
+                return Err(CloudError::format_error(format!(
+                    "PointCloud2 field '{}' exceeds the point step of {} bytes",
+                    field.name, point_step
+                )));
+            }
+            Ok(Some((offset, datatype)))
+        };
+        let packed_color = match find("rgb")? {
+            Some(found) => Some(found),
+            None => find("rgba")?,
+        };
+
+        let mut sources = Vec::with_capacity(fields.len());
+        let mut missing = Vec::new();
+        for descriptor in fields {
+            let shift = match descriptor.name {
+                "r" => Some(16),
+                "g" => Some(8),
+                "b" => Some(0),
+                _ => None,
+            };
+
+            match (find(descriptor.name)?, shift, packed_color) {
+                (Some((offset, datatype)), _, _) => {
+                    sources.push(FieldSource::Scalar { offset, datatype })
+                }
+                (None, Some(shift), Some((offset, datatype))) if datatype.size() == 4 => {
+                    sources.push(FieldSource::PackedColor { offset, shift })
+                }
+                _ => missing.push(descriptor.name),
+            }
+        }
+
+        if !missing.is_empty() {
+            let available: Vec<&str> = self.fields.iter().map(|f| f.name.as_str()).collect();
+            return Err(CloudError::format_error(format!(
+                "PointCloud2 message is missing required fields: {} (available fields: {})",
+                missing.join(", "),
+                available.join(", ")
+            )));
+        }
+        Ok(sources)
+    }
+}
+
+/// Load the point clouds recorded on a topic of a ROS1 bag file
+///
+/// # Example
+/// ```rust,no_run
+/// use ferrum_cloud::io::ros::load_bag;
+/// use ferrum_cloud::prelude::*;
+///
+/// let scans: Vec<PointCloud<PointXYZI>> = load_bag("drive.bag", "/velodyne_points")?;
+/// # Ok::<(), CloudError>(())
+/// ```
+pub fn load_bag<T: PointFields, P: AsRef<Path>>(
+    path: P,
+    topic: &str,
+) -> Result<Vec<PointCloud<T>>> {
+    read_bag_messages(path, topic)?
+        .iter()
+        .map(PointCloud2::to_cloud)
+        .collect()
+}
+
+/// Read the PointCloud2 messages recorded on a topic of a ROS1 bag file
+///
+/// The file is memory mapped while it is read and must not be modified
+/// meanwhile.
+pub fn read_bag_messages<P: AsRef<Path>>(path: P, topic: &str) -> Result<Vec<PointCloud2>> {
+    let bytes = map_file(path.as_ref())?;
+    if !bytes.starts_with(BAG_MAGIC) {
+        return Err(CloudError::format_error(
+            "Not a ROS bag file (missing #ROSBAG V2.0 signature)",
+        ));
+    }
+
+    let mut connections = HashMap::new();
+    let mut messages = Vec::new();
+    scan_bag_records(
+        &bytes[BAG_MAGIC.len()..],
+        false,
+        &mut connections,
+        &mut messages,
+    )?;
+
+    let mut clouds = Vec::new();
+    for (connection, data) in messages {
+        let Some((connection_topic, datatype)) = connections.get(&connection) else {
+            return Err(CloudError::format_error(format!(
+                "ROS bag message refers to unknown connection {}",
+                connection
+            )));
+        };
+        if connection_topic != topic {
+            continue;
+        }
+        if datatype != ROS1_POINT_CLOUD2 {
+            return Err(CloudError::format_error(format!(
+                "ROS bag topic '{}' has type {}, expected {}",
+                topic, datatype, ROS1_POINT_CLOUD2
+            )));
+        }
+        clouds.push(PointCloud2::from_ros1_bytes(&data)?);
+    }
+    Ok(clouds)
+}
+
+/// Load the point clouds recorded on a topic of an MCAP file
+///
+/// Messages must be CDR encoded, as recorded by ROS2.
+pub fn load_mcap<T: PointFields, P: AsRef<Path>>(
+    path: P,
+    topic: &str,
+) -> Result<Vec<PointCloud<T>>> {
+    read_mcap_messages(path, topic)?
+        .iter()
+        .map(PointCloud2::to_cloud)
+        .collect()
+}
+
+/// Read the PointCloud2 messages recorded on a topic of an MCAP file
+///
+/// The file is memory mapped while it is read and must not be modified
+/// meanwhile.
+pub fn read_mcap_messages<P: AsRef<Path>>(path: P, topic: &str) -> Result<Vec<PointCloud2>> {
+    let bytes = map_file(path.as_ref())?;
+    if !bytes.starts_with(MCAP_MAGIC) {
+        return Err(CloudError::format_error(
+            "Not an MCAP file (missing MCAP signature)",
+        ));
+    }
+
+    let mut records = McapRecords::default();
+    scan_mcap_records(&bytes[MCAP_MAGIC.len()..], false, &mut records)?;
+
+    let mut clouds = Vec::new();
+    for (channel_id, data) in records.messages {
+        let Some(channel) = records.channels.get(&channel_id) else {
+            return Err(CloudError::format_error(format!(
+                "MCAP message refers to unknown channel {}",
+                channel_id
+            )));
+        };
+        if channel.topic != topic {
+            continue;
+        }
+        let schema = records
+            .schemas
+            .get(&channel.schema_id)
+            .map_or("", String::as_str);
+        if schema != ROS2_POINT_CLOUD2 || channel.message_encoding != "cdr" {
+            return Err(CloudError::format_error(format!(
+                "MCAP topic '{}' has schema '{}' with {} encoding, expected {} with cdr encoding",
+                topic, schema, channel.message_encoding, ROS2_POINT_CLOUD2
+            )));
+        }
+        clouds.push(PointCloud2::from_cdr_bytes(&data)?);
+    }
+    Ok(clouds)
+}
+
+/// Location of a point field inside a PointCloud2 record
+#[derive(Clone, Copy, Debug)]
+enum FieldSource {
+    /// First element of a field at the given byte offset
+    Scalar { offset: usize, datatype: FieldType },
+
+    /// One byte of a packed 32-bit `rgb`/`rgba` field, selected by bit shift
+    PackedColor { offset: usize, shift: u32 },
+}
+
+impl FieldSource {
+    fn read(&self, record: &[u8], big_endian: bool) -> f64 {
+        match *self {
+            FieldSource::Scalar { offset, datatype } if big_endian => {
+                read_binary::<BigEndian>(datatype, &record[offset..])
+            }
+            FieldSource::Scalar { offset, datatype } => read_value(datatype, &record[offset..]),
+            FieldSource::PackedColor { offset, shift } => {
+                let packed = if big_endian {
+                    BigEndian::read_u32(&record[offset..])
+                } else {
+                    LittleEndian::read_u32(&record[offset..])
+                };
+                ((packed >> shift) & 0xFF) as f64
+            }
+        }
+    }
+}
+
+/// Reader for serialized messages and recording records
+///
+/// With `cdr` set, values are aligned to their size relative to the start of
+/// the reader, as required by CDR.
+struct MessageReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    cdr: bool,
+    big_endian: bool,
+}
+
+impl<'a> MessageReader<'a> {
+    fn new(bytes: &'a [u8], cdr: bool) -> Self {
+        Self {
+            bytes,
+            position: 0,
+            cdr,
+            big_endian: false,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.position >= self.bytes.len()
+    }
+
+    fn take(&mut self, length: usize, alignment: usize) -> Result<&'a [u8]> {
+        if self.cdr {
+            self.position = self.position.next_multiple_of(alignment);
+        }
+        let bytes = self
+            .position
+            .checked_add(length)
+            .and_then(|end| self.bytes.get(self.position..end))
+            .ok_or_else(|| CloudError::format_error("ROS message data is truncated"))?;
+        self.position += length;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1, 1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        let bytes = self.take(2, 2)?;
+        Ok(match self.big_endian {
+            true => BigEndian::read_u16(bytes),
+            false => LittleEndian::read_u16(bytes),
+        })
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        let bytes = self.take(4, 4)?;
+        Ok(match self.big_endian {
+            true => BigEndian::read_u32(bytes),
+            false => LittleEndian::read_u32(bytes),
+        })
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        let bytes = self.take(8, 8)?;
+        Ok(match self.big_endian {
+            true => BigEndian::read_u64(bytes),
+            false => LittleEndian::read_u64(bytes),
+        })
+    }
+
+    /// Bytes prefixed by their 32-bit length
+    fn byte_sequence(&mut self) -> Result<&'a [u8]> {
+        let length = self.u32()? as usize;
+        self.take(length, 1)
+    }
+
+    /// String prefixed by its 32-bit length, NUL terminated in CDR
+    fn string(&mut self) -> Result<String> {
+        let mut bytes = self.byte_sequence()?;
+        if self.cdr
+            && let [rest @ .., 0] = bytes
+        {
+            bytes = rest;
+        }
+        utf8(bytes).map(str::to_string)
+    }
+}
+
+/// Little-endian writer for serialized messages, see [`MessageReader`]
+struct MessageWriter {
+    bytes: Vec<u8>,
+    cdr: bool,
+}
+
+impl MessageWriter {
+    fn new(cdr: bool) -> Self {
+        Self {
+            bytes: Vec::new(),
+            cdr,
+        }
+    }
+
+    fn put(&mut self, bytes: &[u8], alignment: usize) {
+        if self.cdr {
+            let aligned = self.bytes.len().next_multiple_of(alignment);
+            self.bytes.resize(aligned, 0);
+        }
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    fn u8(&mut self, value: u8) {
+        self.put(&[value], 1);
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.put(&value.to_le_bytes(), 4);
+    }
+
+    fn byte_sequence(&mut self, bytes: &[u8]) {
+        self.u32(bytes.len() as u32);
+        self.put(bytes, 1);
+    }
+
+    fn string(&mut self, value: &str) {
+        if self.cdr {
+            self.u32(value.len() as u32 + 1);
+            self.put(value.as_bytes(), 1);
+            self.put(&[0], 1);
+        } else {
+            self.byte_sequence(value.as_bytes());
+        }
+    }
+}
+
+/// Collect the connections and messages of a sequence of bag records
+///
+/// Connections map to their topic and message type; messages are returned
+/// with their connection in recording order. Chunks may not contain further
+/// chunks, which keeps crafted files from recursing without bound.
+fn scan_bag_records<'a>(
+    bytes: &'a [u8],
+    in_chunk: bool,
+    connections: &mut HashMap<u32, (String, String)>,
+    messages: &mut Vec<(u32, Cow<'a, [u8]>)>,
+) -> Result<()> {
+    const OP_MESSAGE_DATA: u8 = 0x02;
+    const OP_CHUNK: u8 = 0x05;
+    const OP_CONNECTION: u8 = 0x07;
+
+    let mut reader = MessageReader::new(bytes, false);
+    while !reader.is_empty() {
+        let header = bag_header_fields(reader.byte_sequence()?)?;
+        let data = reader.byte_sequence()?;
+        let field = |name: &str| {
+            header.get(name).copied().ok_or_else(|| {
+                CloudError::format_error(format!("ROS bag record is missing the '{}' field", name))
+            })
+        };
+        let connection_id = || -> Result<u32> {
+            let id = field("conn")?;
+            if id.len() != 4 {
+                return Err(CloudError::format_error("ROS bag connection id is invalid"));
+            }
+            Ok(LittleEndian::read_u32(id))
+        };
+
+        match field("op")?.first().copied() {
+            Some(OP_CHUNK) => {
+                if in_chunk {
+                    return Err(CloudError::format_error(
+                        "ROS bag chunk records cannot be nested",
+                    ));
+                }
+                let decoder: Box<dyn Read> = match utf8(field("compression")?)? {
+                    "none" => {
+                        scan_bag_records(data, true, connections, messages)?;
+                        continue;
+                    }
+                    "lz4" => Box::new(lz4_flex::frame::FrameDecoder::new(data)),
+                    "bz2" => Box::new(bzip2::read::BzDecoder::new(data)),
+                    compression => {
+                        return Err(CloudError::format_error(format!(
+                            "ROS bag chunk compression '{}' is not supported",
+                            compression
+                        )));
+                    }
+                };
+                let size = field("size")?;
+                if size.len() != 4 {
+                    return Err(CloudError::format_error("ROS bag chunk size is invalid"));
+                }
+                let size = LittleEndian::read_u32(size) as usize;
+
+                let chunk = decompress_chunk(decoder, size, "ROS bag")?;
+                let mut chunk_messages = Vec::new();
+                scan_bag_records(&chunk, true, connections, &mut chunk_messages)?;
+                messages.extend(
+                    chunk_messages
+                        .into_iter()
+                        .map(|(id, data)| (id, Cow::Owned(data.into_owned()))),
+                );
+            }
+            Some(OP_CONNECTION) => {
+                let topic = utf8(field("topic")?)?.to_string();
+                let datatype = bag_header_fields(data)?
+                    .get("type")
+                    .map(|datatype| utf8(datatype))
+                    .transpose()?
+                    .unwrap_or_default()
+                    .to_string();
+                connections.insert(connection_id()?, (topic, datatype));
+            }
+            Some(OP_MESSAGE_DATA) => messages.push((connection_id()?, Cow::Borrowed(data))),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Split a bag record header into its `name=value` fields
+fn bag_header_fields(bytes: &[u8]) -> Result<HashMap<&str, &[u8]>> {
+    let mut reader = MessageReader::new(bytes, false);
+    let mut fields = HashMap::new();
+    while !reader.is_empty() {
+        let field = reader.byte_sequence()?;
+        let separator = field
+            .iter()
+            .position(|&b| b == b'=')
+            .ok_or_else(|| CloudError::format_error("ROS bag header field has no '='"))?;
+        fields.insert(utf8(&field[..separator])?, &field[separator + 1..]);
+    }
+    Ok(fields)
+}
+
+/// Channel of an MCAP file
+struct McapChannel {
+    topic: String,
+    schema_id: u16,
+    message_encoding: String,
+}
+
+/// Schemas, channels and messages collected from MCAP records
+#[derive(Default)]
+struct McapRecords<'a> {
+    schemas: HashMap<u16, String>,
+    channels: HashMap<u16, McapChannel>,
+    messages: Vec<(u16, Cow<'a, [u8]>)>,
+}
+
+/// Collect the schemas, channels and messages of a sequence of MCAP records
+///
+/// Chunks may not contain further chunks.
+fn scan_mcap_records<'a>(
+    bytes: &'a [u8],
+    in_chunk: bool,
+    records: &mut McapRecords<'a>,
+) -> Result<()> {
+    const OP_FOOTER: u8 = 0x02;
+    const OP_SCHEMA: u8 = 0x03;
+    const OP_CHANNEL: u8 = 0x04;
+    const OP_MESSAGE: u8 = 0x05;
+    const OP_CHUNK: u8 = 0x06;
+
+    let mut reader = MessageReader::new(bytes, false);
+    while !reader.is_empty() {
+        let opcode = reader.u8()?;
+        let length = usize::try_from(reader.u64()?)
+            .map_err(|_| CloudError::format_error("MCAP record is too large"))?;
+        let mut content = MessageReader::new(reader.take(length, 1)?, false);
+
+        match opcode {
+            OP_FOOTER => break,
+            OP_SCHEMA => {
+                let id = content.u16()?;
+                let name = content.string()?;
+                records.schemas.insert(id, name);
+            }
+            OP_CHANNEL => {
+                let id = content.u16()?;
+                let channel = McapChannel {
+                    schema_id: content.u16()?,
+                    topic: content.string()?,
+                    message_encoding: content.string()?,
+                };
+                records.channels.insert(id, channel);
+            }
+            OP_MESSAGE => {
+                let channel_id = content.u16()?;
+                // Sequence number, log time and publish time
+                content.take(4 + 8 + 8, 1)?;
+                let data = &content.bytes[content.position..];
+                records.messages.push((channel_id, Cow::Borrowed(data)));
+            }
+            OP_CHUNK => {
+                if in_chunk {
+                    return Err(CloudError::format_error(
+                        "MCAP chunk records cannot be nested",
+                    ));
+                }
+                // Start and end time
+                content.take(8 + 8, 1)?;
+                let uncompressed_size = usize::try_from(content.u64()?)
+                    .map_err(|_| CloudError::format_error("MCAP chunk is too large"))?;
+                // CRC of the uncompressed records
+                content.take(4, 1)?;
+                let compression = content.string()?;
+                let length = usize::try_from(content.u64()?)
+                    .map_err(|_| CloudError::format_error("MCAP chunk is too large"))?;
+                let data = content.take(length, 1)?;
+
+                let decoder: Box<dyn Read> = match compression.as_str() {
+                    "" => {
+                        scan_mcap_records(data, true, records)?;
+                        continue;
+                    }
+                    "lz4" => Box::new(lz4_flex::frame::FrameDecoder::new(data)),
+                    "zstd" => {
+                        Box::new(ruzstd::decoding::StreamingDecoder::new(data).map_err(|e| {
+                            CloudError::format_error(format!(
+                                "MCAP chunk cannot be decompressed: {}",
+                                e
+                            ))
+                        })?)
+                    }
+                    _ => {
+                        return Err(CloudError::format_error(format!(
+                            "MCAP chunk compression '{}' is not supported",
+                            compression
+                        )));
+                    }
+                };
+
+                let chunk = decompress_chunk(decoder, uncompressed_size, "MCAP")?;
+                let mut chunk_records = McapRecords::default();
+                scan_mcap_records(&chunk, true, &mut chunk_records)?;
+                records.schemas.extend(chunk_records.schemas);
+                records.channels.extend(chunk_records.channels);
+                records.messages.extend(
+                    chunk_records
+                        .messages
+                        .into_iter()
+                        .map(|(id, data)| (id, Cow::Owned(data.into_owned()))),
+                );
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Decompress a chunk of a recording
+///
+/// At most `uncompressed_size` bytes, as declared by the chunk, are
+/// decompressed, and the chunk must decompress to exactly that size.
+fn decompress_chunk(decoder: impl Read, uncompressed_size: usize, format: &str) -> Result<Vec<u8>> {
+    let mut chunk = Vec::new();
+    decoder
+        .take((uncompressed_size as u64).saturating_add(1))
+        .read_to_end(&mut chunk)
+        .map_err(|e| {
+            CloudError::format_error(format!("{} chunk cannot be decompressed: {}", format, e))
+        })?;
+    if chunk.len() > uncompressed_size {
+        return Err(CloudError::format_error(format!(
+            "{} chunk decompresses to more than its declared {} bytes",
+            format, uncompressed_size
+        )));
+    }
+    if chunk.len() < uncompressed_size {
+        return Err(CloudError::format_error(format!(
+            "{} chunk decompresses to {} bytes instead of its declared {}",
+            format,
+            chunk.len(),
+            uncompressed_size
+        )));
+    }
+    Ok(chunk)
+}
+
+/// Parse a `seconds.nanoseconds` stamp
+fn parse_stamp(stamp: &str) -> Option<(u32, u32)> {
+    let (sec, fraction) = stamp.split_once('.').unwrap_or((stamp, ""));
+    if fraction.len() > 9 || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let nsec = format!("{:0<9}", fraction).parse().ok()?;
+    Some((sec.parse().ok()?, nsec))
+}
+
+fn utf8(bytes: &[u8]) -> Result<&str> {
+    std::str::from_utf8(bytes)
+        .map_err(|_| CloudError::format_error("ROS message string is not valid UTF-8"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{Point, PointXYZ, PointXYZI, PointXYZRGB};
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    fn organized_cloud() -> PointCloud<PointXYZRGB> {
+        let points = vec![
+            PointXYZRGB::new(1.0, 2.0, 3.0, 255, 128, 0),
+            PointXYZRGB::new(f32::NAN, f32::NAN, f32::NAN, 0, 0, 0),
+            PointXYZRGB::new(-1.0, 0.5, 2.0, 1, 2, 3),
+            PointXYZRGB::new(0.0, 0.0, 1.0, 10, 20, 30),
+        ];
+        let mut metadata = Metadata::new_organized(2, 2)
+            .with_custom_field("frame_id", "camera")
+            .with_custom_field("stamp", "1700000000.000000123");
+        metadata.set_dense(false);
+        PointCloud::from_points_and_metadata(points, metadata)
+    }
+
+    fn bag_record(writer: &mut Vec<u8>, header: &[(&str, &[u8])], data: &[u8]) {
+        let mut fields = Vec::new();
+        for (name, value) in header {
+            fields.extend_from_slice(&((name.len() + 1 + value.len()) as u32).to_le_bytes());
+            fields.extend_from_slice(name.as_bytes());
+            fields.push(b'=');
+            fields.extend_from_slice(value);
+        }
+        writer.extend_from_slice(&(fields.len() as u32).to_le_bytes());
+        writer.extend_from_slice(&fields);
+        writer.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        writer.extend_from_slice(data);
+    }
+
+    fn mcap_record(writer: &mut Vec<u8>, opcode: u8, content: &[u8]) {
+        writer.push(opcode);
+        writer.extend_from_slice(&(content.len() as u64).to_le_bytes());
+        writer.extend_from_slice(content);
+    }
+
+    /// Compress chunk records as a recorder would; unknown names store them as is
+    fn compress(compression: &str, records: &[u8]) -> Vec<u8> {
+        match compression {
+            "lz4" => {
+                let mut encoder = lz4_flex::frame::FrameEncoder::new(Vec::new());
+                encoder.write_all(records).unwrap();
+                encoder.finish().unwrap()
+            }
+            "bz2" => {
+                let mut encoder =
+                    bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::fast());
+                encoder.write_all(records).unwrap();
+                encoder.finish().unwrap()
+            }
+            "zstd" => ruzstd::encoding::compress_to_vec(
+                records,
+                ruzstd::encoding::CompressionLevel::Fastest,
+            ),
+            _ => records.to_vec(),
+        }
+    }
+
+    fn mcap_string(content: &mut Vec<u8>, value: &str) {
+        content.extend_from_slice(&(value.len() as u32).to_le_bytes());
+        content.extend_from_slice(value.as_bytes());
+    }
+
+    #[test]
+    fn test_message_roundtrip() {
+        let cloud = organized_cloud();
+        let message = PointCloud2::from_cloud(&cloud);
+        assert_eq!((message.width, message.height), (2, 2));
+        assert_eq!(message.point_step, 16);
+        assert_eq!(message.fields[3].name, "rgb");
+        assert_eq!(message.header.frame_id, "camera");
+        assert_eq!(message.header.stamp_nsec, 123);
+        assert!(!message.is_dense);
+
+        let ros1 = PointCloud2::from_ros1_bytes(&message.to_ros1_bytes()).unwrap();
+        assert_eq!(ros1, message);
+        let cdr = PointCloud2::from_cdr_bytes(&message.to_cdr_bytes()).unwrap();
+        assert_eq!(cdr, message);
+
+        let decoded: PointCloud<PointXYZRGB> = cdr.to_cloud().unwrap();
+        assert_eq!(decoded.metadata(), cloud.metadata());
+        assert_eq!(decoded.get(0), cloud.get(0));
+        assert_eq!(decoded.get(2).unwrap().color(), Some([1, 2, 3]));
+        assert!(decoded.get(1).unwrap().x.is_nan());
+
+        let error = message.to_cloud::<PointXYZI>().unwrap_err();
+        assert!(error.to_string().contains("intensity"));
+    }
+
+    #[test]
+    fn test_big_endian_with_padding() {
+        // x, y, z as big-endian floats and an intensity, padded to 32 bytes
+        let mut data = Vec::new();
+        for (x, intensity) in [(1.5f32, 7.0f32), (-2.0, 9.0)] {
+            let mut record = [0u8; 32];
+            BigEndian::write_f32(&mut record[0..], x);
+            BigEndian::write_f32(&mut record[4..], 2.0);
+            BigEndian::write_f32(&mut record[8..], 3.0);
+            BigEndian::write_f32(&mut record[16..], intensity);
+            data.extend_from_slice(&record);
+        }
+        let field = |name: &str, offset| PointField {
+            name: name.to_string(),
+            offset,
+            datatype: PointField::FLOAT32,
+            count: 1,
+        };
+        let message = PointCloud2 {
+            height: 1,
+            width: 2,
+            fields: vec![
+                field("x", 0),
+                field("y", 4),
+                field("z", 8),
+                field("intensity", 16),
+            ],
+            is_bigendian: true,
+            point_step: 32,
+            row_step: 64,
+            data,
+            is_dense: true,
+            ..Default::default()
+        };
+
+        let cloud: PointCloud<PointXYZI> = message.to_cloud().unwrap();
+        assert!(!cloud.metadata().is_organized);
+        assert_eq!(cloud.get(1).unwrap(), &PointXYZI::new(-2.0, 2.0, 3.0, 9.0));
+
+        let mut truncated = message.clone();
+        truncated.data.truncate(40);
+        assert!(truncated.to_cloud::<PointXYZ>().is_err());
+    }
+
+    #[test]
+    fn test_read_bag() {
+        let mut cloud = organized_cloud();
+        let message = PointCloud2::from_cloud(&cloud).to_ros1_bytes();
+        cloud.metadata_mut().custom_fields.remove("frame_id");
+        let other = PointCloud2::from_cloud(&cloud).to_ros1_bytes();
+
+        let mut chunk = Vec::new();
+        for (id, topic, datatype) in [
+            (0u32, "/points", ROS1_POINT_CLOUD2),
+            (1, "/status", "std_msgs/String"),
+        ] {
+            let mut connection = Vec::new();
+            for field in [format!("topic={}", topic), format!("type={}", datatype)] {
+                connection.extend_from_slice(&(field.len() as u32).to_le_bytes());
+                connection.extend_from_slice(field.as_bytes());
+            }
+            bag_record(
+                &mut chunk,
+                &[
+                    ("op", &[0x07]),
+                    ("conn", &id.to_le_bytes()),
+                    ("topic", topic.as_bytes()),
+                ],
+                &connection,
+            );
+        }
+        for (id, data) in [(0u32, &message), (1, &b"ok".to_vec()), (0, &other)] {
+            bag_record(
+                &mut chunk,
+                &[
+                    ("op", &[0x02]),
+                    ("conn", &id.to_le_bytes()),
+                    ("time", &[0; 8]),
+                ],
+                data,
+            );
+        }
+
+        let write_bag = |compression: &str, size: usize| {
+            let mut bag = BAG_MAGIC.to_vec();
+            bag_record(&mut bag, &[("op", &[0x03])], &[b' '; 16]);
+            bag_record(
+                &mut bag,
+                &[
+                    ("op", &[0x05]),
+                    ("compression", compression.as_bytes()),
+                    ("size", &(size as u32).to_le_bytes()),
+                ],
+                &compress(compression, &chunk),
+            );
+            let file = NamedTempFile::new().unwrap();
+            std::fs::write(file.path(), &bag).unwrap();
+            file
+        };
+
+        for compression in ["none", "lz4", "bz2"] {
+            let file = write_bag(compression, chunk.len());
+            let clouds: Vec<PointCloud<PointXYZ>> = load_bag(file.path(), "/points").unwrap();
+            assert_eq!(clouds.len(), 2);
+            assert_eq!(
+                clouds[0].metadata().get_custom_field("frame_id").unwrap(),
+                "camera"
+            );
+            assert!(clouds[1].metadata().get_custom_field("frame_id").is_none());
+            assert_eq!(clouds[1].get(3).unwrap().position(), [0.0, 0.0, 1.0]);
+
+            assert!(
+                load_bag::<PointXYZ, _>(file.path(), "/missing")
+                    .unwrap()
+                    .is_empty()
+            );
+            let error = load_bag::<PointXYZ, _>(file.path(), "/status").unwrap_err();
+            assert!(error.to_string().contains("std_msgs/String"));
+        }
+
+        // Compressed chunks must match their declared size
+        for size in [chunk.len() - 1, chunk.len() + 1] {
+            let file = write_bag("bz2", size);
+            let error = load_bag::<PointXYZ, _>(file.path(), "/points").unwrap_err();
+            assert!(error.to_string().contains("declared"));
+        }
+        let file = write_bag("zstd", chunk.len());
+        let error = load_bag::<PointXYZ, _>(file.path(), "/points").unwrap_err();
+        assert!(error.to_string().contains("zstd"));
+    }
+
+    #[test]
+    fn test_read_mcap() {
+        let message = PointCloud2::from_cloud(&organized_cloud()).to_cdr_bytes();
+
+        let mut schema = 1u16.to_le_bytes().to_vec();
+        mcap_string(&mut schema, ROS2_POINT_CLOUD2);
+        mcap_string(&mut schema, "ros2msg");
+        mcap_string(&mut schema, "");
+        let mut channel = 3u16.to_le_bytes().to_vec();
+        channel.extend_from_slice(&1u16.to_le_bytes());
+        mcap_string(&mut channel, "/points");
+        mcap_string(&mut channel, "cdr");
+        channel.extend_from_slice(&0u32.to_le_bytes());
+        let mut entry = 3u16.to_le_bytes().to_vec();
+        entry.extend_from_slice(&[0; 20]);
+        entry.extend_from_slice(&message);
+
+        // One message inside a chunk and one outside
+        let mut records = Vec::new();
+        mcap_record(&mut records, 0x03, &schema);
+        mcap_record(&mut records, 0x04, &channel);
+        mcap_record(&mut records, 0x05, &entry);
+        let write_mcap = |compression: &str, size: usize| {
+            let stored = compress(compression, &records);
+            let mut chunk = vec![0; 16];
+            chunk.extend_from_slice(&(size as u64).to_le_bytes());
+            chunk.extend_from_slice(&[0; 4]);
+            mcap_string(&mut chunk, compression);
+            chunk.extend_from_slice(&(stored.len() as u64).to_le_bytes());
+            chunk.extend_from_slice(&stored);
+
+            let mut header = Vec::new();
+            mcap_string(&mut header, "ros2");
+            mcap_string(&mut header, "test");
+            let mut mcap = MCAP_MAGIC.to_vec();
+            mcap_record(&mut mcap, 0x01, &header);
+            mcap_record(&mut mcap, 0x06, &chunk);
+            mcap_record(&mut mcap, 0x05, &entry);
+            mcap_record(&mut mcap, 0x02, &[0; 20]);
+            mcap.extend_from_slice(MCAP_MAGIC);
+
+            let file = NamedTempFile::new().unwrap();
+            std::fs::write(file.path(), &mcap).unwrap();
+            file
+        };
+
+        for compression in ["", "lz4", "zstd"] {
+            let file = write_mcap(compression, records.len());
+            let clouds: Vec<PointCloud<PointXYZRGB>> = load_mcap(file.path(), "/points").unwrap();
+            assert_eq!(clouds.len(), 2);
+            assert!(clouds[1].metadata().is_organized);
+            assert_eq!(clouds[1].get(0).unwrap().color(), Some([255, 128, 0]));
+        }
+
+        // Compressed chunks must match their declared size
+        for size in [records.len() - 1, records.len() + 1] {
+            let file = write_mcap("zstd", size);
+            let error = load_mcap::<PointXYZ, _>(file.path(), "/points").unwrap_err();
+            assert!(error.to_string().contains("declared"));
+        }
+        let file = write_mcap("bz2", records.len());
+        let error = load_mcap::<PointXYZ, _>(file.path(), "/points").unwrap_err();
+        assert!(error.to_string().contains("bz2"));
+    }
+
+    #[test]
+    fn test_rejects_nested_chunks() {
+        let bag_chunk = |writer: &mut Vec<u8>, records: &[u8]| {
+            let size = (records.len() as u32).to_le_bytes();
+            bag_record(
+                writer,
+                &[("op", &[0x05]), ("compression", b"none"), ("size", &size)],
+                records,
+            );
+        };
+        let mut inner = Vec::new();
+        bag_chunk(&mut inner, &[]);
+        let mut bag = BAG_MAGIC.to_vec();
+        bag_chunk(&mut bag, &inner);
+        let file = NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), &bag).unwrap();
+        let error = load_bag::<PointXYZ, _>(file.path(), "/points").unwrap_err();
+        assert!(error.to_string().contains("nested"));
+
+        let chunk = |records: &[u8]| {
+            let mut chunk = vec![0; 28];
+            mcap_string(&mut chunk, "");
+            chunk.extend_from_slice(&(records.len() as u64).to_le_bytes());
+            chunk.extend_from_slice(records);
+            chunk
+        };
+        let mut inner = Vec::new();
+        mcap_record(&mut inner, 0x06, &chunk(&[]));
+        let mut mcap = MCAP_MAGIC.to_vec();
+        mcap_record(&mut mcap, 0x06, &chunk(&inner));
+        let file = NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), &mcap).unwrap();
+        let error = load_mcap::<PointXYZ, _>(file.path(), "/points").unwrap_err();
+        assert!(error.to_string().contains("nested"));
+    }
+}
@@ -0,0 +1,394 @@
+//! Streaming point cloud I/O
+//!
+//! This module reads and writes PCD and PLY files in chunks so that files
+//! larger than the available memory can be processed piece by piece. A
+//! [`ChunkReader`] yields the points of a file as a sequence of point clouds
+//! of a configurable size, and a [`ChunkWriter`] appends point clouds to a
+//! file and fixes up the point count in its header when finished.
+//!
+//! Chunks are unorganized clouds that carry the sensor pose and custom
+//! fields of the file's metadata.
+//!
+//! # Example
+//! ```rust,no_run
+//! use ferrum_cloud::io::stream::{ChunkReader, ChunkWriter};
+//! use ferrum_cloud::io::PcdDataFormat;
+//! use ferrum_cloud::prelude::*;
+//!
+//! let reader = ChunkReader::<PointXYZ>::open("huge.pcd", 1_000_000)?;
+//! let mut writer = ChunkWriter::<PointXYZ>::create_pcd("ground.pcd", PcdDataFormat::Binary)?;
+//! for chunk in reader {
+//!     writer.write_chunk(&chunk?.filter(|p| p.z < 0.2))?;
+//! }
+//! writer.finish()?;
+//! # Ok::<(), CloudError>(())
+//! ```
+
+use super::PointRows;
+use super::pcd::{self, PcdDataFormat};
+use super::ply::{self, PlyEncoding};
+use crate::core::{Metadata, PointCloud, PointFields};
+use crate::error::{CloudError, Result};
+use std::fs::File;
+use std::io::{BufRead, Read};
+use std::marker::PhantomData;
+use std::path::Path;
+
+/// Width reserved for point counts in headers that are rewritten on finish
+const COUNT_WIDTH: usize = 20;
+
+/// Source of point rows, read one at a time
+pub(crate) trait RowSource: Send {
+    /// Read the field values of the next point
+    fn read_row(&mut self, values: &mut [f64]) -> Result<()>;
+}
+
+/// Destination of point rows, written one chunk at a time
+pub(crate) trait RowSink: Send {
+    /// Append all rows of a chunk
+    fn write_rows(&mut self, rows: &PointRows<'_>) -> Result<()>;
+
+    /// Write the final header and flush the file
+    fn finish(&mut self) -> Result<()>;
+}
+
+/// Iterator over a point cloud file in chunks of a fixed number of points
+///
+/// Every item is a point cloud with up to `chunk_size` points; only the last
+/// chunk is smaller. After an error the iterator ends.
+pub struct ChunkReader<T: PointFields> {
+    rows: Box<dyn RowSource>,
+    metadata: Metadata,
+    len: usize,
+    remaining: usize,
+    chunk_size: usize,
+    _point: PhantomData<fn() -> T>,
+}
+
+impl<T: PointFields> ChunkReader<T> {
+    /// Open a PCD or PLY file, detecting the format from its header
+    pub fn open<P: AsRef<Path>>(path: P, chunk_size: usize) -> Result<Self> {
+        let path = path.as_ref();
+        let mut magic = [0u8; 7];
+        let read = File::open(path)?.read(&mut magic)?;
+        let magic = &magic[..read];
+
+        if magic.starts_with(b"ply\n") || magic.starts_with(b"ply\r\n") {
+            Self::open_ply(path, chunk_size)
+        } else if magic.starts_with(b"# .PCD") || magic.starts_with(b"VERSION") {
+            Self::open_pcd(path, chunk_size)
+        } else {
+            Err(CloudError::format_error(format!(
+                "Unrecognized point cloud format: {}",
+                path.display()
+            )))
+        }
+    }
+
+    /// Open a PCD file of any data encoding
+    ///
+    /// `binary_compressed` data stores every field contiguously, so such
+    /// files are decompressed as a whole when opened; `ascii` and `binary`
+    /// data is read as the chunks are requested.
+    pub fn open_pcd<P: AsRef<Path>>(path: P, chunk_size: usize) -> Result<Self> {
+        let (rows, metadata, len) = pcd::open_row_source(path.as_ref(), T::fields())?;
+        Self::new(rows, metadata, len, chunk_size)
+    }
+
+    /// Open the vertices of a PLY file of any encoding
+    pub fn open_ply<P: AsRef<Path>>(path: P, chunk_size: usize) -> Result<Self> {
+        let (rows, metadata, len) = ply::open_row_source(path.as_ref(), T::fields())?;
+        Self::new(rows, metadata, len, chunk_size)
+    }
+
+    fn new(
+        rows: Box<dyn RowSource>,
+        metadata: Metadata,
+        len: usize,
+        chunk_size: usize,
+    ) -> Result<Self> {
+        if chunk_size == 0 {
+            return Err(CloudError::invalid_parameter(
+                "Chunk size must be greater than zero",
+            ));
+        }
+        Ok(Self {
+            rows,
+            metadata,
+            len,
+            remaining: len,
+            chunk_size,
+            _point: PhantomData,
+        })
+    }
+
+    /// Metadata of the whole file
+    pub fn metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+
+    /// Total number of points in the file
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Check whether the file has no points
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of points not yet returned
+    pub fn remaining(&self) -> usize {
+        self.remaining
+    }
+
+    fn read_chunk(&mut self, count: usize) -> Result<PointCloud<T>> {
+        let mut values = vec![0.0f64; T::fields().len()];
+        let mut points = Vec::with_capacity(count);
+        for _ in 0..count {
+            self.rows.read_row(&mut values)?;
+            points.push(T::from_values(&values));
+        }
+
+        let mut metadata = Metadata::new_unorganized(count);
+        metadata.sensor_origin = self.metadata.sensor_origin;
+        metadata.sensor_orientation = self.metadata.sensor_orientation;
+        metadata.custom_fields = self.metadata.custom_fields.clone();
+        Ok(PointCloud::from_points_and_metadata(points, metadata))
+    }
+}
+
+impl<T: PointFields> Iterator for ChunkReader<T> {
+    type Item = Result<PointCloud<T>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let count = self.remaining.min(self.chunk_size);
+        let chunk = self.read_chunk(count);
+        self.remaining = if chunk.is_ok() {
+            self.remaining - count
+        } else {
+            0
+        };
+        Some(chunk)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let chunks = self.remaining.div_ceil(self.chunk_size);
+        (0, Some(chunks))
+    }
+}
+
+/// Writer appending point cloud chunks to a file
+///
+/// The header is written with the first chunk, using its sensor pose, and
+/// rewritten with the final point count by [`finish`](Self::finish), which
+/// must be called for the file to be valid.
+pub struct ChunkWriter<T: PointFields> {
+    rows: Box<dyn RowSink>,
+    len: usize,
+    _point: PhantomData<fn() -> T>,
+}
+
+impl<T: PointFields> ChunkWriter<T> {
+    /// Create a PCD file with `ascii` or `binary` data
+    ///
+    /// `binary_compressed` data cannot be written incrementally.
+    pub fn create_pcd<P: AsRef<Path>>(path: P, format: PcdDataFormat) -> Result<Self> {
+        Ok(Self::new(pcd::create_row_sink(
+            path.as_ref(),
+            T::fields(),
+            format,
+        )?))
+    }
+
+    /// Create a PLY file with the given encoding
+    pub fn create_ply<P: AsRef<Path>>(path: P, encoding: PlyEncoding) -> Result<Self> {
+        Ok(Self::new(ply::create_row_sink(
+            path.as_ref(),
+            T::fields(),
+            encoding,
+        )?))
+    }
+
+    fn new(rows: Box<dyn RowSink>) -> Self {
+        Self {
+            rows,
+            len: 0,
+            _point: PhantomData,
+        }
+    }
+
+    /// Append the points of a chunk
+    pub fn write_chunk(&mut self, chunk: &PointCloud<T>) -> Result<()> {
+        self.rows.write_rows(&PointRows::from_cloud(chunk))?;
+        self.len += chunk.len();
+        Ok(())
+    }
+
+    /// Number of points written so far
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Check whether no points have been written
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Complete the file, returning the total number of points written
+    pub fn finish(mut self) -> Result<usize> {
+        self.rows.finish()?;
+        Ok(self.len)
+    }
+}
+
+/// Pad the count on header lines starting with one of `prefixes`
+///
+/// Counts padded to a fixed width keep the header length unchanged, so the
+/// header can be rewritten in place once the final count is known.
+pub(crate) fn pad_count_lines(header: &[u8], prefixes: &[&str]) -> Vec<u8> {
+    let text = String::from_utf8_lossy(header);
+    let mut padded = String::with_capacity(text.len() + prefixes.len() * COUNT_WIDTH);
+    for line in text.split_inclusive('\n') {
+        let content = line.trim_end_matches('\n');
+        match prefixes.iter().find(|prefix| content.starts_with(*prefix)) {
+            Some(prefix) => {
+                let width = prefix.len() + COUNT_WIDTH;
+                padded.push_str(&format!("{:<width$}\n", content, width = width));
+            }
+            None => padded.push_str(line),
+        }
+    }
+    padded.into_bytes()
+}
+
+/// Whitespace separated tokens of a text stream
+pub(crate) struct TokenReader<R> {
+    reader: R,
+    line: String,
+    position: usize,
+}
+
+impl<R: BufRead> TokenReader<R> {
+    pub(crate) fn new(reader: R) -> Self {
+        Self {
+            reader,
+            line: String::new(),
+            position: 0,
+        }
+    }
+
+    /// Next token, or `None` at the end of the stream
+    pub(crate) fn next_token(&mut self) -> Result<Option<&str>> {
+        loop {
+            let bytes = self.line.as_bytes();
+            if let Some(skip) = bytes[self.position..]
+                .iter()
+                .position(|b| !b.is_ascii_whitespace())
+            {
+                let start = self.position + skip;
+                let end = bytes[start..]
+                    .iter()
+                    .position(|b| b.is_ascii_whitespace())
+                    .map_or(bytes.len(), |length| start + length);
+                self.position = end;
+                return Ok(Some(&self.line[start..end]));
+            }
+
+            self.line.clear();
+            self.position = 0;
+            if self.reader.read_line(&mut self.line)? == 0 {
+                return Ok(None);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{Point, PointXYZ, PointXYZRGB};
+    use tempfile::TempDir;
+
+    fn cloud(count: usize) -> PointCloud<PointXYZRGB> {
+        let mut cloud = PointCloud::from_points(
+            (0..count)
+                .map(|i| PointXYZRGB::new(i as f32, 0.5, -(i as f32), i as u8, 2, 3))
+                .collect(),
+        );
+        cloud.metadata_mut().sensor_origin = [1.0, 2.0, 3.0];
+        cloud
+    }
+
+    #[test]
+    fn test_chunked_roundtrip() {
+        let dir = TempDir::new().unwrap();
+        let source = cloud(25);
+
+        for name in ["ascii.pcd", "binary.pcd", "ascii.ply", "big.ply"] {
+            let path = dir.path().join(name);
+            let mut writer = match name {
+                "ascii.pcd" => ChunkWriter::create_pcd(&path, PcdDataFormat::Ascii),
+                "binary.pcd" => ChunkWriter::create_pcd(&path, PcdDataFormat::Binary),
+                "ascii.ply" => ChunkWriter::create_ply(&path, PlyEncoding::Ascii),
+                _ => ChunkWriter::create_ply(&path, PlyEncoding::BinaryBigEndian),
+            }
+            .unwrap();
+            for chunk in source.points().chunks(10) {
+                let mut chunk = PointCloud::from_points(chunk.to_vec());
+                *chunk.metadata_mut() = source.metadata().clone();
+                writer.write_chunk(&chunk).unwrap();
+            }
+            assert_eq!(writer.finish().unwrap(), 25);
+
+            // The rewritten header is readable by the whole-file loaders
+            let loaded: PointCloud<PointXYZRGB> = crate::io::load(&path).unwrap();
+            assert_eq!(loaded.points(), source.points(), "{}", name);
+
+            let reader = ChunkReader::<PointXYZRGB>::open(&path, 8).unwrap();
+            assert_eq!(reader.len(), 25);
+            let chunks: Vec<_> = reader.map(|chunk| chunk.unwrap()).collect();
+            assert_eq!(
+                chunks.iter().map(|c| c.len()).collect::<Vec<_>>(),
+                [8, 8, 8, 1]
+            );
+            let points: Vec<_> = chunks.iter().flat_map(|c| c.points().to_vec()).collect();
+            assert_eq!(points, source.points());
+            if name.ends_with(".pcd") {
+                assert_eq!(chunks[0].metadata().sensor_origin, [1.0, 2.0, 3.0]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_compressed_and_errors() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("compressed.pcd");
+        pcd::save_pcd_with_format(&cloud(5), &path, PcdDataFormat::BinaryCompressed).unwrap();
+
+        let chunks: Vec<_> = ChunkReader::<PointXYZ>::open(&path, 2)
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[2].get(0).unwrap().position(), [4.0, 0.5, -4.0]);
+
+        assert!(ChunkReader::<PointXYZ>::open(&path, 0).is_err());
+        assert!(
+            ChunkWriter::<PointXYZ>::create_pcd(&path, PcdDataFormat::BinaryCompressed).is_err()
+        );
+
+        // Truncated data ends the iteration with an error
+        let path = dir.path().join("truncated.pcd");
+        pcd::save_pcd_with_format(&cloud(5), &path, PcdDataFormat::Binary).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::write(&path, &bytes[..bytes.len() - 20]).unwrap();
+        let results: Vec<_> = ChunkReader::<PointXYZ>::open(&path, 3).unwrap().collect();
+        assert_eq!(results.len(), 2);
+        assert!(results[0].is_ok() && results[1].is_err());
+    }
+}
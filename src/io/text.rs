@@ -0,0 +1,545 @@
+//! Delimited text point formats (XYZ, CSV, PTS, TXT)
+//!
+//! This module reads and writes point clouds stored as one point per line
+//! with values separated by whitespace or a delimiter character. Columns are
+//! mapped to point fields by name through a [`TextConfig`], so files with
+//! colors, intensities or normals in any column order can be loaded into
+//! the existing point types.
+
+use super::{PointCloudReader, PointCloudWriter, PointRows, format_ascii_float};
+use crate::core::{FieldDescriptor, FieldType, Metadata, PointCloud, PointFields};
+use crate::error::{CloudError, Result};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+/// Column layout and parsing options for text point files
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextConfig {
+    /// Column delimiter; `None` splits on any run of whitespace
+    pub delimiter: Option<char>,
+
+    /// Point field name of every column, e.g. `["x", "y", "z", "intensity"]`
+    ///
+    /// Columns whose name is not a field of the point type are skipped when
+    /// reading; when writing every column must be a field of the point type.
+    /// An empty list means the fields of the point type in order. Common
+    /// aliases such as `red` or `nx` are accepted.
+    pub columns: Vec<String>,
+
+    /// The first line holds column names, which replace `columns` when reading
+    /// and are written from `columns` when saving
+    pub has_header: bool,
+
+    /// The first line holds the number of points (PTS files)
+    pub point_count_line: bool,
+
+    /// With no `columns` configured, use the PTS layouts instead of the
+    /// point type's fields: the number of values on the first line selects
+    /// `x y z`, `x y z intensity`, `x y z r g b` or `x y z intensity r g b`
+    /// when reading, and the layout covering the point type's fields is
+    /// written
+    pub pts_columns: bool,
+
+    /// Number of additional lines to skip at the start of the file
+    pub skip_lines: usize,
+
+    /// Lines starting with this character are ignored
+    pub comment: Option<char>,
+}
+
+impl Default for TextConfig {
+    fn default() -> Self {
+        Self {
+            delimiter: None,
+            columns: Vec::new(),
+            has_header: false,
+            point_count_line: false,
+            pts_columns: false,
+            skip_lines: 0,
+            comment: Some('#'),
+        }
+    }
+}
+
+impl TextConfig {
+    /// Whitespace separated values in the given column order
+    pub fn with_columns<S: AsRef<str>>(columns: &[S]) -> Self {
+        Self {
+            columns: columns.iter().map(|c| c.as_ref().to_string()).collect(),
+            ..Self::default()
+        }
+    }
+
+    /// Comma separated values with a header row naming the columns
+    pub fn csv() -> Self {
+        Self {
+            delimiter: Some(','),
+            has_header: true,
+            ..Self::default()
+        }
+    }
+
+    /// Leica PTS layout: a point count line, then `x y z` followed by an
+    /// optional intensity and optional `r g b` color
+    pub fn pts() -> Self {
+        Self {
+            point_count_line: true,
+            pts_columns: true,
+            ..Self::default()
+        }
+    }
+
+    /// Set the column delimiter
+    pub fn with_delimiter(mut self, delimiter: char) -> Self {
+        self.delimiter = Some(delimiter);
+        self
+    }
+
+    /// Set the number of lines to skip at the start of the file
+    pub fn with_skip_lines(mut self, skip_lines: usize) -> Self {
+        self.skip_lines = skip_lines;
+        self
+    }
+
+    fn split<'a>(&self, line: &'a str) -> Vec<&'a str> {
+        match self.delimiter {
+            Some(delimiter) => line.split(delimiter).map(str::trim).collect(),
+            None => line.split_whitespace().collect(),
+        }
+    }
+}
+
+/// Load a point cloud from a delimited text file
+///
+/// Every field of `T` must be provided by a column.
+///
+/// # Example
+/// ```rust,no_run
+/// use ferrum_cloud::io::text::{TextConfig, load_text};
+/// use ferrum_cloud::prelude::*;
+///
+/// let config = TextConfig::with_columns(&["x", "y", "z", "red", "green", "blue"]);
+/// let cloud: PointCloud<PointXYZRGB> = load_text("scan.txt", &config)?;
+/// # Ok::<(), CloudError>(())
+/// ```
+pub fn load_text<T: PointFields, P: AsRef<Path>>(
+    path: P,
+    config: &TextConfig,
+) -> Result<PointCloud<T>> {
+    let mut points = Vec::new();
+    read_text_rows(path.as_ref(), config, T::fields(), &mut |values| {
+        points.push(T::from_values(values))
+    })?;
+    Ok(PointCloud::from_points(points))
+}
+
+/// Save a point cloud to a delimited text file
+///
+/// The configured columns are written in order; with no columns configured
+/// all fields of the point type are written.
+pub fn save_text<T: PointFields, P: AsRef<Path>>(
+    cloud: &PointCloud<T>,
+    path: P,
+    config: &TextConfig,
+) -> Result<()> {
+    write_text_rows(path.as_ref(), &PointRows::from_cloud(cloud), config)
+}
+
+/// Text format for the [`io::load`](super::load) and [`io::save`](super::save) registry
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextFormat {
+    /// Layout used for reading and writing
+    pub config: TextConfig,
+
+    extensions: &'static [&'static str],
+}
+
+impl TextFormat {
+    /// Create a text format for the given file extensions
+    pub fn new(config: TextConfig, extensions: &'static [&'static str]) -> Self {
+        Self { config, extensions }
+    }
+
+    /// Whitespace separated `.xyz` and `.txt` files with the point type's fields
+    pub fn xyz() -> Self {
+        Self::new(TextConfig::default(), &["xyz", "txt"])
+    }
+
+    /// Comma separated `.csv` files with a header row
+    pub fn csv() -> Self {
+        Self::new(TextConfig::csv(), &["csv"])
+    }
+
+    /// Leica `.pts` files
+    pub fn pts() -> Self {
+        Self::new(TextConfig::pts(), &["pts"])
+    }
+}
+
+impl PointCloudReader for TextFormat {
+    fn name(&self) -> &str {
+        "text"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        self.extensions
+    }
+
+    fn read(
+        &self,
+        path: &Path,
+        fields: &[FieldDescriptor],
+        sink: &mut dyn FnMut(&[f64]),
+    ) -> Result<Metadata> {
+        let count = read_text_rows(path, &self.config, fields, sink)?;
+        Ok(Metadata::new_unorganized(count))
+    }
+}
+
+impl PointCloudWriter for TextFormat {
+    fn name(&self) -> &str {
+        "text"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        self.extensions
+    }
+
+    fn write(&self, path: &Path, rows: &PointRows<'_>) -> Result<()> {
+        write_text_rows(path, rows, &self.config)
+    }
+}
+
+/// Column layouts of PTS files, ordered by number of columns
+const PTS_LAYOUTS: [&[&str]; 4] = [
+    &["x", "y", "z"],
+    &["x", "y", "z", "intensity"],
+    &["x", "y", "z", "r", "g", "b"],
+    &["x", "y", "z", "intensity", "r", "g", "b"],
+];
+
+/// Map a column name to the point field name it stands for
+fn canonical_name(column: &str) -> String {
+    let lower = column.trim().to_ascii_lowercase();
+    match lower.as_str() {
+        "red" => "r".to_string(),
+        "green" => "g".to_string(),
+        "blue" => "b".to_string(),
+        "nx" => "normal_x".to_string(),
+        "ny" => "normal_y".to_string(),
+        "nz" => "normal_z".to_string(),
+        "i" => "intensity".to_string(),
+        _ => lower,
+    }
+}
+
+/// Read the requested fields of every line, returning the number of points
+fn read_text_rows(
+    path: &Path,
+    config: &TextConfig,
+    fields: &[FieldDescriptor],
+    sink: &mut dyn FnMut(&[f64]),
+) -> Result<usize> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut lines = reader.lines().enumerate();
+
+    let preamble = config.skip_lines + usize::from(config.point_count_line);
+    for _ in 0..preamble {
+        next_line(&mut lines)?;
+    }
+
+    let is_content = |line: &str| {
+        let trimmed = line.trim();
+        !trimmed.is_empty()
+            && !config
+                .comment
+                .is_some_and(|comment| trimmed.starts_with(comment))
+    };
+
+    let mut columns: Vec<String> = config.columns.iter().map(|c| canonical_name(c)).collect();
+    let mut first = None;
+    if config.has_header {
+        loop {
+            match next_line(&mut lines)? {
+                Some((_, line)) if is_content(&line) => {
+                    columns = config
+                        .split(&line)
+                        .into_iter()
+                        .map(canonical_name)
+                        .collect();
+                    break;
+                }
+                Some(_) => continue,
+                None => break,
+            }
+        }
+    }
+    if columns.is_empty() && config.pts_columns {
+        while let Some((number, line)) = next_line(&mut lines)? {
+            if is_content(&line) {
+                let count = config.split(&line).len();
+                let layout = PTS_LAYOUTS
+                    .iter()
+                    .find(|layout| layout.len() == count)
+                    .ok_or_else(|| {
+                        CloudError::format_error(format!(
+                            "Line {} has {} values, which is not a PTS layout",
+                            number + 1,
+                            count
+                        ))
+                    })?;
+                columns = layout.iter().map(|c| c.to_string()).collect();
+                first = Some((number, line));
+                break;
+            }
+        }
+    }
+    if columns.is_empty() {
+        columns = fields.iter().map(|f| f.name.to_string()).collect();
+    }
+
+    // Column index of every requested field
+    let mut sources = Vec::with_capacity(fields.len());
+    let mut missing = Vec::new();
+    for field in fields {
+        match columns.iter().position(|c| c == field.name) {
+            Some(index) => sources.push(index),
+            None => missing.push(field.name),
+        }
+    }
+    if !missing.is_empty() {
+        return Err(CloudError::format_error(format!(
+            "Text file has no columns for fields: {} (columns: {})",
+            missing.join(", "),
+            columns.join(", ")
+        )));
+    }
+    let needed = sources.iter().map(|&i| i + 1).max().unwrap_or(0);
+
+    let mut values = vec![0.0f64; fields.len()];
+    let mut count = 0;
+    // The line used to infer a PTS layout is the first data line
+    while let Some((number, line)) = match first.take() {
+        Some(line) => Some(line),
+        None => next_line(&mut lines)?,
+    } {
+        if !is_content(&line) {
+            continue;
+        }
+
+        let tokens = config.split(&line);
+        if tokens.len() < needed {
+            return Err(CloudError::format_error(format!(
+                "Line {} has {} columns, expected at least {}",
+                number + 1,
+                tokens.len(),
+                needed
+            )));
+        }
+
+        for (value, &source) in values.iter_mut().zip(sources.iter()) {
+            let token = tokens[source];
+            *value = token.parse().map_err(|_| {
+                CloudError::format_error(format!(
+                    "Invalid value '{}' on line {}",
+                    token,
+                    number + 1
+                ))
+            })?;
+        }
+        sink(&values);
+        count += 1;
+    }
+
+    Ok(count)
+}
+
+/// Write rows of point fields as delimited text
+fn write_text_rows(path: &Path, rows: &PointRows<'_>, config: &TextConfig) -> Result<()> {
+    let fields = rows.fields();
+    let columns: Vec<String> = if config.columns.is_empty() && config.pts_columns {
+        // The largest layout whose columns the point type provides
+        let has = |column: &&str| fields.iter().any(|f| f.name == *column);
+        PTS_LAYOUTS
+            .iter()
+            .rev()
+            .find(|layout| layout.iter().all(has))
+            .ok_or_else(|| CloudError::invalid_parameter("PTS files require x, y and z fields"))?
+            .iter()
+            .map(|c| c.to_string())
+            .collect()
+    } else if config.columns.is_empty() {
+        fields.iter().map(|f| f.name.to_string()).collect()
+    } else {
+        config.columns.clone()
+    };
+
+    let mut sources = Vec::with_capacity(columns.len());
+    for column in &columns {
+        let name = canonical_name(column);
+        let index = fields.iter().position(|f| f.name == name).ok_or_else(|| {
+            CloudError::invalid_parameter(format!("Point type has no field for column {}", column))
+        })?;
+        sources.push(index);
+    }
+
+    let separator = match config.delimiter {
+        Some(delimiter) => delimiter.to_string(),
+        None => " ".to_string(),
+    };
+
+    let mut writer = BufWriter::new(File::create(path)?);
+    if config.point_count_line {
+        writeln!(writer, "{}", rows.len())?;
+    }
+    if config.has_header {
+        writeln!(writer, "{}", columns.join(&separator))?;
+    }
+
+    let mut values = vec![0.0f64; fields.len()];
+    let mut line = String::new();
+    for index in 0..rows.len() {
+        rows.write_row(index, &mut values);
+        line.clear();
+        for (i, &source) in sources.iter().enumerate() {
+            if i > 0 {
+                line.push_str(&separator);
+            }
+            let value = values[source];
+            match fields[source].datatype {
+                FieldType::F32 | FieldType::F64 => {
+                    line.push_str(&format_ascii_float(value, fields[source].datatype))
+                }
+                _ => line.push_str(&(value as i64).to_string()),
+            }
+        }
+        line.push('\n');
+        writer.write_all(line.as_bytes())?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+/// Next line of an enumerated line iterator, with I/O errors surfaced
+fn next_line<I>(lines: &mut I) -> Result<Option<(usize, String)>>
+where
+    I: Iterator<Item = (usize, std::io::Result<String>)>,
+{
+    match lines.next() {
+        Some((number, line)) => Ok(Some((number, line?))),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{PointXYZ, PointXYZI, PointXYZRGB, PointXYZRGBNormal};
+    use tempfile::NamedTempFile;
+
+    fn write_file(contents: &str) -> NamedTempFile {
+        let file = NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), contents).unwrap();
+        file
+    }
+
+    #[test]
+    fn test_column_mapping() {
+        let file = write_file(
+            "# scanner export\n\
+             0.5 1 2 3 255 128 0\n\
+             \n\
+             0.25 4 5 6 0 0 255\n",
+        );
+        let config = TextConfig::with_columns(&["i", "x", "y", "z", "red", "green", "blue"]);
+
+        let colored: PointCloud<PointXYZRGB> = load_text(file.path(), &config).unwrap();
+        assert_eq!(
+            colored.points(),
+            &[
+                PointXYZRGB::new(1.0, 2.0, 3.0, 255, 128, 0),
+                PointXYZRGB::new(4.0, 5.0, 6.0, 0, 0, 255),
+            ]
+        );
+
+        let intensities: PointCloud<PointXYZI> = load_text(file.path(), &config).unwrap();
+        assert_eq!(intensities.get(1).unwrap().intensity, 0.25);
+
+        let error = load_text::<PointXYZRGBNormal, _>(file.path(), &config).unwrap_err();
+        assert!(error.to_string().contains("normal_x"));
+    }
+
+    #[test]
+    fn test_csv_header() {
+        let file = write_file("Z,X,Y,Intensity\n3,1,2,0.5\n6,4,5,0.75\n");
+
+        let cloud: PointCloud<PointXYZI> = load_text(file.path(), &TextConfig::csv()).unwrap();
+        assert_eq!(
+            cloud.points(),
+            &[
+                PointXYZI::new(1.0, 2.0, 3.0, 0.5),
+                PointXYZI::new(4.0, 5.0, 6.0, 0.75)
+            ]
+        );
+
+        let skipped = TextConfig::with_columns(&["z", "x", "y"])
+            .with_delimiter(',')
+            .with_skip_lines(1);
+        let cloud: PointCloud<PointXYZ> = load_text(file.path(), &skipped).unwrap();
+        assert_eq!(cloud.get(1).unwrap(), &PointXYZ::new(4.0, 5.0, 6.0));
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let cloud = PointCloud::from_points(vec![
+            PointXYZRGBNormal::new(0.1, 0.2, 0.3, 1, 2, 3, 0.0, 0.0, 1.0),
+            PointXYZRGBNormal::new(-1.5, 2e-7, 1e9, 255, 255, 255, 0.6, 0.8, 0.0),
+        ]);
+
+        for config in [TextConfig::default(), TextConfig::csv()] {
+            let file = NamedTempFile::new().unwrap();
+            save_text(&cloud, file.path(), &config).unwrap();
+            let loaded: PointCloud<PointXYZRGBNormal> = load_text(file.path(), &config).unwrap();
+            assert_eq!(loaded.points(), cloud.points());
+        }
+
+        // PTS writes the layout matching the point type
+        let colored = PointCloud::from_points(vec![PointXYZRGB::new(1.0, 2.0, 3.0, 4, 5, 6)]);
+        let file = NamedTempFile::new().unwrap();
+        save_text(&colored, file.path(), &TextConfig::pts()).unwrap();
+        let contents = std::fs::read_to_string(file.path()).unwrap();
+        assert_eq!(contents, "1\n1 2 3 4 5 6\n");
+        let loaded: PointCloud<PointXYZRGB> = load_text(file.path(), &TextConfig::pts()).unwrap();
+        assert_eq!(loaded.points(), colored.points());
+
+        let plain = PointCloud::from_points(vec![PointXYZ::new(1.0, 2.0, 3.0)]);
+        save_text(&plain, file.path(), &TextConfig::pts()).unwrap();
+        let contents = std::fs::read_to_string(file.path()).unwrap();
+        assert_eq!(contents, "1\n1 2 3\n");
+    }
+
+    #[test]
+    fn test_pts_layouts() {
+        let config = TextConfig::pts();
+        let file = write_file("2\n1 2 3 -120\n4 5 6 300\n");
+        let cloud: PointCloud<PointXYZI> = load_text(file.path(), &config).unwrap();
+        assert_eq!(cloud.get(1).unwrap(), &PointXYZI::new(4.0, 5.0, 6.0, 300.0));
+        assert!(load_text::<PointXYZRGB, _>(file.path(), &config).is_err());
+
+        let file = write_file("1\n1 2 3 10 20 30\n");
+        let cloud: PointCloud<PointXYZRGB> = load_text(file.path(), &config).unwrap();
+        assert_eq!(
+            cloud.get(0).unwrap(),
+            &PointXYZRGB::new(1.0, 2.0, 3.0, 10, 20, 30)
+        );
+
+        let file = write_file("1\n1 2 3 -5 10 20 30\n");
+        let cloud: PointCloud<PointXYZ> = load_text(file.path(), &config).unwrap();
+        assert_eq!(cloud.get(0).unwrap(), &PointXYZ::new(1.0, 2.0, 3.0));
+
+        let file = write_file("1\n1 2 3 4 5\n");
+        assert!(load_text::<PointXYZ, _>(file.path(), &config).is_err());
+    }
+}
@@ -43,6 +43,7 @@
 //! ```rust,no_run
 //! use ferrum_cloud::prelude::*;
 //!
+//! # #[cfg(feature = "visualization")]
 //! #[tokio::main]
 //! async fn main() -> Result<()> {
 //!     let cloud = io::load_pcd("examples/scene.pcd")?;
@@ -53,6 +54,8 @@
 //!     
 //!     Ok(())
 //! }
+//! # #[cfg(not(feature = "visualization"))]
+//! # fn main() {}
 //! ```
 
 pub mod algorithms;
@@ -68,7 +71,10 @@ pub mod visualization;
 /// Prelude module for convenient imports
 pub mod prelude {
     pub use crate::algorithms::*;
-    pub use crate::core::{Point, PointCloud, PointCloudView, PointXYZ, PointXYZRGB};
+    pub use crate::core::{
+        Point, PointCloud, PointCloudView, PointColor, PointFields, PointMut, PointNormal,
+        PointXYZ, PointXYZI, PointXYZL, PointXYZRGB, PointXYZRGBNormal,
+    };
     pub use crate::error::{CloudError, Result};
     pub use crate::io;
     pub use crate::search::*;
@@ -78,7 +84,7 @@ pub mod prelude {
 }
 
 // Re-export commonly used types
-pub use crate::core::{Point, PointCloud, PointXYZ, PointXYZRGB};
+pub use crate::core::{Point, PointCloud, PointMut, PointXYZ, PointXYZRGB};
 pub use crate::error::{CloudError, Result};
 
 #[cfg(test)]
@@ -4,19 +4,25 @@
 //! nearest neighbor queries.
 
 use crate::core::Point;
-use crate::error::{CloudError, Result};
 
 /// KD-tree for efficient spatial queries
 ///
 /// This is a simplified implementation. A production KD-tree would include
 /// more optimizations and better balancing algorithms.
+///
+/// Every node remembers the index of its point in the slice the tree was built
+/// from, so the `*_index`/`*_indices` queries can be used to address the
+/// original point cloud directly. Points with non-finite coordinates are
+/// skipped when building the tree.
 pub struct KdTree<P: Point> {
     root: Option<Box<KdNode<P>>>,
+    len: usize,
 }
 
 /// Node in the KD-tree
 struct KdNode<P: Point> {
     point: P,
+    index: usize,
     axis: usize, // 0=x, 1=y, 2=z
     left: Option<Box<KdNode<P>>>,
     right: Option<Box<KdNode<P>>>,
@@ -25,44 +31,63 @@ struct KdNode<P: Point> {
 impl<P: Point> KdTree<P> {
     /// Create a new empty KD-tree
     pub fn new() -> Self {
-        Self { root: None }
+        Self { root: None, len: 0 }
     }
 
     /// Build a KD-tree from a slice of points
     pub fn build(points: &[P]) -> Self {
-        let mut points_copy = points.to_vec();
-        let root = Self::build_recursive(&mut points_copy, 0);
-        Self { root }
+        let mut indices: Vec<usize> = (0..points.len())
+            .filter(|&i| points[i].position().iter().all(|v| v.is_finite()))
+            .collect();
+        let len = indices.len();
+        let root = Self::build_recursive(points, &mut indices, 0);
+        Self { root, len }
     }
 
     /// Recursively build the KD-tree
-    fn build_recursive(points: &mut [P], depth: usize) -> Option<Box<KdNode<P>>> {
-        if points.is_empty() {
+    fn build_recursive(
+        points: &[P],
+        indices: &mut [usize],
+        depth: usize,
+    ) -> Option<Box<KdNode<P>>> {
+        if indices.is_empty() {
             return None;
         }
 
         let axis = depth % 3;
 
-        // Sort points by the current axis
-        points.sort_by(|a, b| a.position()[axis].partial_cmp(&b.position()[axis]).unwrap());
-
-        let median = points.len() / 2;
-        let point = points[median].clone();
+        // Partition indices around the median of the current axis
+        let median = indices.len() / 2;
+        indices.select_nth_unstable_by(median, |&a, &b| {
+            points[a].position()[axis].total_cmp(&points[b].position()[axis])
+        });
 
-        let (left_points, right_points) = points.split_at_mut(median);
-        let right_points = &mut right_points[1..]; // Skip the median point
+        let index = indices[median];
+        let (left_indices, right_indices) = indices.split_at_mut(median);
+        let right_indices = &mut right_indices[1..]; // Skip the median point
 
-        let left = Self::build_recursive(left_points, depth + 1);
-        let right = Self::build_recursive(right_points, depth + 1);
+        let left = Self::build_recursive(points, left_indices, depth + 1);
+        let right = Self::build_recursive(points, right_indices, depth + 1);
 
         Some(Box::new(KdNode {
-            point,
+            point: points[index].clone(),
+            index,
             axis,
             left,
             right,
         }))
     }
 
+    /// Get the number of points stored in the tree
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Check if the tree is empty
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
     /// Calculate squared Euclidean distance between two 3D points
     fn distance_squared(p1: &[f32; 3], p2: &[f32; 3]) -> f32 {
         let dx = p1[0] - p2[0];
@@ -72,41 +97,114 @@ impl<P: Point> KdTree<P> {
     }
 
     /// Find the nearest neighbor to a query point
+    ///
+    /// Returns `None` if the tree is empty or the query has a non-finite
+    /// coordinate.
     pub fn nearest_neighbor(&self, query: &P) -> Option<&P> {
+        self.nearest_node(query.position())
+            .map(|(node, _)| &node.point)
+    }
+
+    /// Find the index and squared distance of the nearest neighbor to a position
+    ///
+    /// Returns `None` if the tree is empty or the query has a non-finite
+    /// coordinate.
+    pub fn nearest_index(&self, query: [f32; 3]) -> Option<(usize, f32)> {
+        self.nearest_node(query)
+            .map(|(node, distance_squared)| (node.index, distance_squared))
+    }
+
+    /// Find the node closest to a query position
+    fn nearest_node(&self, query: [f32; 3]) -> Option<(&KdNode<P>, f32)> {
+        if !query.iter().all(|v| v.is_finite()) {
+            return None;
+        }
         self.root.as_ref().map(|root| {
-            let mut best_point = &root.point;
+            let mut best_node: &KdNode<P> = root;
             let mut best_distance_squared = f32::INFINITY;
 
             Self::nearest_neighbor_recursive(
                 root,
-                query,
-                &mut best_point,
+                &query,
+                &mut best_node,
                 &mut best_distance_squared,
-                0,
             );
 
-            best_point
+            (best_node, best_distance_squared)
         })
     }
 
     /// Recursive helper for nearest neighbor search
     fn nearest_neighbor_recursive<'a>(
         node: &'a KdNode<P>,
-        query: &P,
-        best_point: &mut &'a P,
+        query_pos: &[f32; 3],
+        best_node: &mut &'a KdNode<P>,
         best_distance_squared: &mut f32,
-        depth: usize,
     ) {
-        let distance_squared = Self::distance_squared(&node.point.position(), &query.position());
+        let node_pos = node.point.position();
+        let distance_squared = Self::distance_squared(&node_pos, query_pos);
 
         if distance_squared < *best_distance_squared {
             *best_distance_squared = distance_squared;
-            *best_point = &node.point;
+            *best_node = node;
         }
 
-        let axis = depth % 3;
-        let query_pos = query.position();
+        let axis = node.axis;
+
+        let (primary, secondary) = if query_pos[axis] < node_pos[axis] {
+            (&node.left, &node.right)
+        } else {
+            (&node.right, &node.left)
+        };
+
+        if let Some(child) = primary {
+            Self::nearest_neighbor_recursive(child, query_pos, best_node, best_distance_squared);
+        }
+
+        let axis_distance = query_pos[axis] - node_pos[axis];
+        if axis_distance * axis_distance < *best_distance_squared
+            && let Some(child) = secondary
+        {
+            Self::nearest_neighbor_recursive(child, query_pos, best_node, best_distance_squared);
+        }
+    }
+
+    /// Find the point with the lowest custom cost relative to a position
+    ///
+    /// `cost` receives the index and squared distance of every candidate and
+    /// returns its cost, or `None` to skip the candidate. The cost must never
+    /// be smaller than the squared distance, which is what allows subtrees to
+    /// be pruned. Returns `(index, cost)` of the best candidate.
+    pub fn nearest_index_by<F>(&self, query: [f32; 3], cost: F) -> Option<(usize, f32)>
+    where
+        F: Fn(usize, f32) -> Option<f32>,
+    {
+        let mut best = None;
+        if let Some(ref root) = self.root {
+            Self::nearest_by_recursive(root, &query, &cost, &mut best);
+        }
+        best
+    }
+
+    /// Recursive helper for custom-cost nearest neighbor search
+    fn nearest_by_recursive<F>(
+        node: &KdNode<P>,
+        query_pos: &[f32; 3],
+        cost: &F,
+        best: &mut Option<(usize, f32)>,
+    ) where
+        F: Fn(usize, f32) -> Option<f32>,
+    {
         let node_pos = node.point.position();
+        let distance_squared = Self::distance_squared(&node_pos, query_pos);
+
+        if let Some(node_cost) = cost(node.index, distance_squared)
+            && best.is_none_or(|(_, best_cost)| node_cost < best_cost)
+        {
+            *best = Some((node.index, node_cost));
+        }
+
+        let axis = node.axis;
 
         let (primary, secondary) = if query_pos[axis] < node_pos[axis] {
             (&node.left, &node.right)
@@ -115,36 +213,44 @@ impl<P: Point> KdTree<P> {
         };
 
         if let Some(child) = primary {
-            Self::nearest_neighbor_recursive(
-                child,
-                query,
-                best_point,
-                best_distance_squared,
-                depth + 1,
-            );
+            Self::nearest_by_recursive(child, query_pos, cost, best);
         }
 
         let axis_distance = query_pos[axis] - node_pos[axis];
-        if axis_distance * axis_distance < *best_distance_squared {
-            if let Some(child) = secondary {
-                Self::nearest_neighbor_recursive(
-                    child,
-                    query,
-                    best_point,
-                    best_distance_squared,
-                    depth + 1,
-                );
-            }
+        if best.is_none_or(|(_, best_cost)| axis_distance * axis_distance < best_cost)
+            && let Some(child) = secondary
+        {
+            Self::nearest_by_recursive(child, query_pos, cost, best);
         }
     }
 
     /// Find all points within a given radius of the query point
+    ///
+    /// Returns the points together with their squared distances.
     pub fn radius_search(&self, query: &P, radius: f32) -> Vec<(&P, f32)> {
+        self.radius_nodes(query.position(), radius)
+            .into_iter()
+            .map(|(node, distance_squared)| (&node.point, distance_squared))
+            .collect()
+    }
+
+    /// Find the indices of all points within a given radius of a position
+    ///
+    /// Returns `(index, squared_distance)` pairs in no particular order.
+    pub fn radius_search_indices(&self, query: [f32; 3], radius: f32) -> Vec<(usize, f32)> {
+        self.radius_nodes(query, radius)
+            .into_iter()
+            .map(|(node, distance_squared)| (node.index, distance_squared))
+            .collect()
+    }
+
+    /// Collect all nodes within a given radius of a query position
+    fn radius_nodes(&self, query: [f32; 3], radius: f32) -> Vec<(&KdNode<P>, f32)> {
         let mut results = Vec::new();
         let radius_squared = radius * radius;
 
         if let Some(ref root) = self.root {
-            Self::radius_search_recursive(root, query, radius, radius_squared, &mut results, 0);
+            Self::radius_search_recursive(root, &query, radius, radius_squared, &mut results);
         }
 
         results
@@ -153,84 +259,86 @@ impl<P: Point> KdTree<P> {
     /// Recursive helper for radius search
     fn radius_search_recursive<'a>(
         node: &'a KdNode<P>,
-        query: &P,
+        query_pos: &[f32; 3],
         radius: f32,
         radius_squared: f32,
-        results: &mut Vec<(&'a P, f32)>,
-        depth: usize,
+        results: &mut Vec<(&'a KdNode<P>, f32)>,
     ) {
-        let distance_squared = Self::distance_squared(&node.point.position(), &query.position());
+        let node_pos = node.point.position();
+        let distance_squared = Self::distance_squared(&node_pos, query_pos);
 
         if distance_squared <= radius_squared {
-            results.push((&node.point, distance_squared));
+            results.push((node, distance_squared));
         }
 
-        let axis = depth % 3;
-        let query_pos = query.position();
-        let node_pos = node.point.position();
+        let axis = node.axis;
 
-        if let Some(left) = &node.left {
-            if query_pos[axis] - radius <= node_pos[axis] {
-                Self::radius_search_recursive(
-                    left,
-                    query,
-                    radius,
-                    radius_squared,
-                    results,
-                    depth + 1,
-                );
-            }
+        if let Some(left) = &node.left
+            && query_pos[axis] - radius <= node_pos[axis]
+        {
+            Self::radius_search_recursive(left, query_pos, radius, radius_squared, results);
         }
 
-        if let Some(right) = &node.right {
-            if query_pos[axis] + radius >= node_pos[axis] {
-                Self::radius_search_recursive(
-                    right,
-                    query,
-                    radius,
-                    radius_squared,
-                    results,
-                    depth + 1,
-                );
-            }
+        if let Some(right) = &node.right
+            && query_pos[axis] + radius >= node_pos[axis]
+        {
+            Self::radius_search_recursive(right, query_pos, radius, radius_squared, results);
         }
     }
 
     /// Find the k nearest neighbors to a query point
+    ///
+    /// Returns the points together with their squared distances, closest first.
     pub fn k_nearest(&self, query: &P, k: usize) -> Vec<(&P, f32)> {
-        let mut results = Vec::new();
+        self.k_nearest_nodes(query.position(), k)
+            .into_iter()
+            .map(|(node, distance_squared)| (&node.point, distance_squared))
+            .collect()
+    }
 
-        if let Some(ref root) = self.root {
-            Self::k_nearest_recursive(root, query, k, &mut results, 0);
+    /// Find the indices of the k nearest neighbors to a position
+    ///
+    /// Returns `(index, squared_distance)` pairs, closest first.
+    pub fn k_nearest_indices(&self, query: [f32; 3], k: usize) -> Vec<(usize, f32)> {
+        self.k_nearest_nodes(query, k)
+            .into_iter()
+            .map(|(node, distance_squared)| (node.index, distance_squared))
+            .collect()
+    }
+
+    /// Collect the k nodes closest to a query position, sorted by distance
+    fn k_nearest_nodes(&self, query: [f32; 3], k: usize) -> Vec<(&KdNode<P>, f32)> {
+        let mut results = Vec::with_capacity(k + 1);
+
+        if k > 0
+            && let Some(ref root) = self.root
+        {
+            Self::k_nearest_recursive(root, &query, k, &mut results);
         }
 
-        // Sort by distance and take only k results
-        results.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
-        results.truncate(k);
         results
     }
 
     /// Recursive helper for k-nearest search
+    ///
+    /// `results` is kept sorted by distance and never holds more than k entries.
     fn k_nearest_recursive<'a>(
         node: &'a KdNode<P>,
-        query: &P,
+        query_pos: &[f32; 3],
         k: usize,
-        results: &mut Vec<(&'a P, f32)>,
-        depth: usize,
+        results: &mut Vec<(&'a KdNode<P>, f32)>,
     ) {
-        let distance_squared = Self::distance_squared(&node.point.position(), &query.position());
-
-        results.push((&node.point, distance_squared));
+        let node_pos = node.point.position();
+        let distance_squared = Self::distance_squared(&node_pos, query_pos);
 
         // Keep only the k closest points
-        if results.len() > k {
-            results.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+        if results.len() < k || distance_squared < results[results.len() - 1].1 {
+            let position = results.partition_point(|(_, d)| *d <= distance_squared);
+            results.insert(position, (node, distance_squared));
             results.truncate(k);
         }
 
-        let axis = depth % 3;
-        let query_pos = query.position();
-        let node_pos = node.point.position();
+        let axis = node.axis;
 
         let (primary, secondary) = if query_pos[axis] < node_pos[axis] {
             (&node.left, &node.right)
@@ -239,21 +347,21 @@ impl<P: Point> KdTree<P> {
         };
 
         if let Some(child) = primary {
-            Self::k_nearest_recursive(child, query, k, results, depth + 1);
+            Self::k_nearest_recursive(child, query_pos, k, results);
         }
 
         // Check if we need to explore the other side
         let worst_distance = if results.len() < k {
             f32::INFINITY
         } else {
-            results.iter().map(|(_, d)| *d).fold(0.0, f32::max)
+            results[results.len() - 1].1
         };
 
         let axis_distance = query_pos[axis] - node_pos[axis];
-        if axis_distance * axis_distance < worst_distance {
-            if let Some(child) = secondary {
-                Self::k_nearest_recursive(child, query, k, results, depth + 1);
-            }
+        if axis_distance * axis_distance < worst_distance
+            && let Some(child) = secondary
+        {
+            Self::k_nearest_recursive(child, query_pos, k, results);
         }
     }
 }
@@ -299,6 +407,18 @@ mod tests {
         assert_eq!(nearest.position(), [0.0, 0.0, 0.0]);
     }
 
+    #[test]
+    fn test_nearest_rejects_non_finite_queries() {
+        let points = vec![PointXYZ::new(0.0, 0.0, 0.0), PointXYZ::new(1.0, 1.0, 1.0)];
+        let tree = KdTree::build(&points);
+
+        assert!(tree.nearest_index([f32::NAN, 0.0, 0.0]).is_none());
+        assert!(tree.nearest_index([0.0, f32::INFINITY, 0.0]).is_none());
+        let query = PointXYZ::new(0.0, 0.0, f32::NAN);
+        assert!(tree.nearest_neighbor(&query).is_none());
+        assert_eq!(tree.nearest_index([0.9, 0.9, 0.9]).map(|(i, _)| i), Some(1));
+    }
+
     #[test]
     fn test_radius_search() {
         let points = vec![
@@ -313,4 +433,48 @@ mod tests {
         let results = tree.radius_search(&query, 2.0);
         assert_eq!(results.len(), 2); // Should find first two points
     }
+
+    #[test]
+    fn test_index_queries() {
+        let points = vec![
+            PointXYZ::new(0.0, 0.0, 0.0),
+            PointXYZ::new(1.0, 0.0, 0.0),
+            PointXYZ::new(f32::NAN, 0.0, 0.0),
+            PointXYZ::new(3.0, 0.0, 0.0),
+            PointXYZ::new(0.5, 0.0, 0.0),
+        ];
+
+        let tree = KdTree::build(&points);
+        assert_eq!(tree.len(), 4); // NaN point is skipped
+
+        let (index, distance_squared) = tree.nearest_index([2.9, 0.0, 0.0]).unwrap();
+        assert_eq!(index, 3);
+        assert!((distance_squared - 0.01).abs() < 1e-5);
+
+        let nearest: Vec<usize> = tree
+            .k_nearest_indices([0.1, 0.0, 0.0], 3)
+            .into_iter()
+            .map(|(i, _)| i)
+            .collect();
+        assert_eq!(nearest, vec![0, 4, 1]);
+
+        let mut within: Vec<usize> = tree
+            .radius_search_indices([0.0, 0.0, 0.0], 1.0)
+            .into_iter()
+            .map(|(i, _)| i)
+            .collect();
+        within.sort();
+        assert_eq!(within, vec![0, 1, 4]);
+
+        // Skip the two closest points and penalize index 1
+        let (index, cost) = tree
+            .nearest_index_by([0.0, 0.0, 0.0], |i, d| match i {
+                0 | 4 => None,
+                1 => Some(d + 100.0),
+                _ => Some(d),
+            })
+            .unwrap();
+        assert_eq!(index, 3);
+        assert!((cost - 9.0).abs() < 1e-5);
+    }
 }
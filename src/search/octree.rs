@@ -4,7 +4,6 @@
 //! queries and organization of 3D point data.
 
 use crate::core::Point;
-use crate::error::{CloudError, Result};
 
 /// Octree for spatial partitioning of 3D points
 ///
@@ -237,12 +236,12 @@ impl<P: Point> OctreeNode<P> {
 
         // Check if query sphere intersects with this node's bounds
         let mut min_dist_squared = 0.0;
-        for i in 0..3 {
-            if query_pos[i] < self.bounds.min[i] {
-                let d = self.bounds.min[i] - query_pos[i];
+        for (i, &q) in query_pos.iter().enumerate() {
+            if q < self.bounds.min[i] {
+                let d = self.bounds.min[i] - q;
                 min_dist_squared += d * d;
-            } else if query_pos[i] > self.bounds.max[i] {
-                let d = query_pos[i] - self.bounds.max[i];
+            } else if q > self.bounds.max[i] {
+                let d = q - self.bounds.max[i];
                 min_dist_squared += d * d;
             }
         }
@@ -64,6 +64,140 @@ pub mod math {
     pub fn magnitude(v: [f32; 3]) -> f32 {
         (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt()
     }
+
+    /// Convert a unit quaternion [w, x, y, z] to a 3x3 rotation matrix
+    ///
+    /// The quaternion is normalized first; a zero quaternion yields the identity.
+    pub fn quaternion_to_rotation(q: [f32; 4]) -> [[f32; 3]; 3] {
+        let norm = (q[0] * q[0] + q[1] * q[1] + q[2] * q[2] + q[3] * q[3]).sqrt();
+        if norm < 1e-12 {
+            return [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+        }
+        let (w, x, y, z) = (q[0] / norm, q[1] / norm, q[2] / norm, q[3] / norm);
+
+        [
+            [
+                1.0 - 2.0 * (y * y + z * z),
+                2.0 * (x * y - w * z),
+                2.0 * (x * z + w * y),
+            ],
+            [
+                2.0 * (x * y + w * z),
+                1.0 - 2.0 * (x * x + z * z),
+                2.0 * (y * z - w * x),
+            ],
+            [
+                2.0 * (x * z - w * y),
+                2.0 * (y * z + w * x),
+                1.0 - 2.0 * (x * x + y * y),
+            ],
+        ]
+    }
+
+    /// Eigen-decomposition of a symmetric matrix using cyclic Jacobi rotations
+    ///
+    /// Returns the eigenvalues in ascending order together with the matching
+    /// unit eigenvectors, where `vectors[i]` belongs to `values[i]`.
+    #[allow(clippy::needless_range_loop)]
+    pub fn symmetric_eigen<const N: usize>(matrix: [[f64; N]; N]) -> ([f64; N], [[f64; N]; N]) {
+        let mut a = matrix;
+        let mut v = [[0.0; N]; N];
+        for (i, row) in v.iter_mut().enumerate() {
+            row[i] = 1.0;
+        }
+
+        let total: f64 = a.iter().flatten().map(|x| x * x).sum();
+
+        for _ in 0..64 {
+            let mut off_diagonal = 0.0;
+            for p in 0..N {
+                for q in (p + 1)..N {
+                    off_diagonal += a[p][q] * a[p][q];
+                }
+            }
+            if off_diagonal <= 1e-30 * total || off_diagonal == 0.0 {
+                break;
+            }
+
+            for p in 0..N {
+                for q in (p + 1)..N {
+                    if a[p][q] == 0.0 {
+                        continue;
+                    }
+
+                    let theta = (a[q][q] - a[p][p]) / (2.0 * a[p][q]);
+                    let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+                    let c = 1.0 / (t * t + 1.0).sqrt();
+                    let s = t * c;
+
+                    for row in a.iter_mut() {
+                        let (akp, akq) = (row[p], row[q]);
+                        row[p] = c * akp - s * akq;
+                        row[q] = s * akp + c * akq;
+                    }
+                    for k in 0..N {
+                        let (apk, aqk) = (a[p][k], a[q][k]);
+                        a[p][k] = c * apk - s * aqk;
+                        a[q][k] = s * apk + c * aqk;
+                    }
+                    for row in v.iter_mut() {
+                        let (vkp, vkq) = (row[p], row[q]);
+                        row[p] = c * vkp - s * vkq;
+                        row[q] = s * vkp + c * vkq;
+                    }
+                }
+            }
+        }
+
+        // Sort eigenpairs by ascending eigenvalue
+        let mut order: [usize; N] = std::array::from_fn(|i| i);
+        order.sort_by(|&i, &j| a[i][i].total_cmp(&a[j][j]));
+
+        let values = std::array::from_fn(|i| a[order[i]][order[i]]);
+        let vectors = std::array::from_fn(|i| std::array::from_fn(|k| v[k][order[i]]));
+
+        (values, vectors)
+    }
+
+    /// Solve the linear system `a * x = b` using Gaussian elimination
+    ///
+    /// Uses partial pivoting and returns `None` if the matrix is singular.
+    #[allow(clippy::needless_range_loop)]
+    pub fn solve_linear_system<const N: usize>(a: [[f64; N]; N], b: [f64; N]) -> Option<[f64; N]> {
+        let mut a = a;
+        let mut b = b;
+
+        let scale = a
+            .iter()
+            .flatten()
+            .fold(0.0f64, |acc, v| acc.max(v.abs()))
+            .max(f64::MIN_POSITIVE);
+
+        for col in 0..N {
+            let pivot = (col..N).max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))?;
+            if a[pivot][col].abs() <= 1e-12 * scale {
+                return None;
+            }
+            a.swap(col, pivot);
+            b.swap(col, pivot);
+
+            for row in (col + 1)..N {
+                let factor = a[row][col] / a[col][col];
+                for k in col..N {
+                    a[row][k] -= factor * a[col][k];
+                }
+                b[row] -= factor * b[col];
+            }
+        }
+
+        let mut x = [0.0; N];
+        for row in (0..N).rev() {
+            let sum: f64 = ((row + 1)..N).map(|k| a[row][k] * x[k]).sum();
+            x[row] = (b[row] - sum) / a[row][row];
+        }
+
+        Some(x)
+    }
 }
 
 /// Color conversion utilities
@@ -255,6 +389,37 @@ mod tests {
         assert!((math::magnitude(normalized) - 1.0).abs() < 1e-6);
     }
 
+    #[test]
+    fn test_symmetric_eigen() {
+        let matrix = [[2.0, 1.0, 0.0], [1.0, 2.0, 0.0], [0.0, 0.0, 5.0]];
+        let (values, vectors) = math::symmetric_eigen(matrix);
+
+        assert!((values[0] - 1.0).abs() < 1e-12);
+        assert!((values[1] - 3.0).abs() < 1e-12);
+        assert!((values[2] - 5.0).abs() < 1e-12);
+
+        // A * v = lambda * v for every eigenpair
+        for (value, vector) in values.iter().zip(vectors.iter()) {
+            for (row, &component) in matrix.iter().zip(vector.iter()) {
+                let av: f64 = row.iter().zip(vector.iter()).map(|(a, b)| a * b).sum();
+                assert!((av - value * component).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn test_solve_linear_system() {
+        let a = [[2.0, 1.0, -1.0], [-3.0, -1.0, 2.0], [-2.0, 1.0, 2.0]];
+        let b = [8.0, -11.0, -3.0];
+        let x = math::solve_linear_system(a, b).unwrap();
+        assert!((x[0] - 2.0).abs() < 1e-12);
+        assert!((x[1] - 3.0).abs() < 1e-12);
+        assert!((x[2] + 1.0).abs() < 1e-12);
+
+        let singular = [[1.0, 2.0], [2.0, 4.0]];
+        assert!(math::solve_linear_system(singular, [1.0, 2.0]).is_none());
+    }
+
     #[test]
     fn test_color_conversion() {
         let rgb = color::rgb_to_normalized(255, 128, 0);